@@ -0,0 +1,85 @@
+use chrono::{Duration, NaiveDateTime};
+
+use std::cmp::Reverse;
+
+use super::resources::{Allocation, ResourceType};
+
+/// A phase whose start was pushed back, waiting for a `Resource` of some `ResourceType` to be
+/// free.
+///
+/// While scheduling, a phase may be pushed back several times, by different `ResourceType`s, until
+/// every `Resource` it needs is free over the same period. Each push is a separate `DelayEvent`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DelayEvent {
+    /// The name of the recipe containing the delayed phase.
+    pub recipe: String,
+
+    /// The id of the delayed phase, as it appears in the scheduled
+    /// [Recipe](super::recipes::Recipe).
+    pub phase_id: usize,
+
+    /// The description of the delayed phase.
+    pub phase: String,
+
+    /// The `ResourceType` that wasn't free.
+    pub resource_type: ResourceType,
+
+    /// The date at which the phase could otherwise have started.
+    pub requested_start: NaiveDateTime,
+
+    /// The date the phase was pushed back to, at which enough `Resource`s of `resource_type` are
+    /// free. The phase may still start later than this, if it is delayed again.
+    pub actual_start: NaiveDateTime,
+
+    /// The allocations of `Resource`s of `resource_type` that stood in the way, along with the
+    /// phase, or unavailable period, holding each one.
+    pub blocking: Vec<Allocation>
+}
+
+impl DelayEvent {
+    /// Retrieve how long the phase was pushed back by this `DelayEvent`.
+    pub fn delay(&self) -> Duration {
+        self.actual_start - self.requested_start
+    }
+}
+
+/// The total delay caused by a single `ResourceType`, across every phase of a schedule.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bottleneck {
+    pub resource_type: ResourceType,
+    pub total_delay: Duration,
+
+    /// The number of `DelayEvent`s the `ResourceType` caused.
+    pub event_count: usize
+}
+
+/// Rank the `ResourceType`s that caused a set of `DelayEvent`s by the total delay each caused.
+///
+/// # Arguments
+/// - `events`: The `DelayEvent`s, in the order they occurred.
+///
+/// # Returns
+/// - A `Vec` containing a [Bottleneck](Bottleneck) for each `ResourceType` that caused a delay,
+///   longest total delay first. `ResourceType`s with the same total delay are ranked in the order
+///   they first caused a delay.
+pub fn rank_bottlenecks(events: &[DelayEvent]) -> Vec<Bottleneck> {
+    let mut bottlenecks: Vec<Bottleneck> = vec![];
+    for next_event in events {
+        match bottlenecks.iter_mut().find(|x| x.resource_type == next_event.resource_type) {
+            Some(bottleneck) => {
+                bottleneck.total_delay = bottleneck.total_delay + next_event.delay();
+                bottleneck.event_count += 1;
+            },
+            None => bottlenecks.push(Bottleneck {
+                resource_type: next_event.resource_type.clone(),
+                total_delay: next_event.delay(),
+                event_count: 1
+            })
+        }
+    }
+
+    // This sort is stable, so ties stay in the order they first caused a delay.
+    bottlenecks.sort_by_key(|x| Reverse(x.total_delay));
+
+    bottlenecks
+}
@@ -0,0 +1,246 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use chrono_period::NaivePeriod;
+
+use serde::{Serialize, Deserialize};
+
+use super::util::get_naive_date_time_from_string;
+
+/// A specification of a period during which phases may not start, such as a holiday, or a
+/// shutdown of the brewery, given within a `blackouts` block of either a recipe or the timeline.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct BlackoutSpec {
+    #[serde(rename="start")]
+    pub start_string: String,
+
+    #[serde(rename="end")]
+    pub end_string: String,
+
+    /// The ids of the phase templates whose phases may not run at all during the blackout, rather
+    /// than only not start during it.
+    #[serde(rename="wholePhaseTemplates", default)]
+    pub whole_phase_templates: Vec<String>
+}
+
+impl BlackoutSpec {
+    /// Retrieve the [Blackout](Blackout) described by this `BlackoutSpec`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either the `Blackout`, or an `Err` containing the first date string
+    ///   that could not be parsed.
+    ///
+    pub fn blackout(&self) -> Result<Blackout, String> {
+        let start = match get_naive_date_time_from_string(&self.start_string[..]) {
+            Ok(x) => x,
+            Err(_e) => return Err(self.start_string.clone())
+        };
+
+        let end = match get_naive_date_time_from_string(&self.end_string[..]) {
+            Ok(x) => x,
+            Err(_e) => return Err(self.end_string.clone())
+        };
+
+        Ok(Blackout {
+            period: NaivePeriod::new(start, end),
+            whole_phase_templates: self.whole_phase_templates.clone()
+        })
+    }
+}
+
+/// A period during which phases may not start.
+///
+/// As with the periods over which resources are allocated, both the start and end of the period
+/// are included within it, so a phase moved out of a blackout starts a second after it ends.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Blackout {
+    pub period: NaivePeriod,
+    pub whole_phase_templates: Vec<String>
+}
+
+impl Blackout {
+    /// Determine if a phase would break this `Blackout`.
+    ///
+    /// # Arguments
+    /// - `template_id`: The id of the template of the phase.
+    /// - `start_date`: The [NaiveDateTime](chrono::NaiveDateTime) at which the phase would start.
+    /// - `duration`: The [Duration](chrono::Duration) of the phase.
+    ///
+    /// # Returns
+    /// - `true`, if the phase would start within this `Blackout`, or its template is one of the
+    ///   `whole_phase_templates` and it would run at any point during this `Blackout`; `false`,
+    ///   otherwise.
+    pub fn is_broken_by(&self, template_id: &str, start_date: NaiveDateTime,
+                        duration: Duration) -> bool {
+        match self.whole_phase_templates.iter().any(|id| id == template_id) {
+            true => self.period.intersects_with(NaivePeriod::from_start_duration(start_date,
+                                                                                 duration)),
+            false => self.period.start <= start_date && start_date <= self.period.end
+        }
+    }
+}
+
+/// Retrieve the earliest date, at or after a given date, at which a phase can start without
+/// breaking any `Blackout`.
+///
+/// # Arguments
+/// - `blackouts`: The `Blackout`s the phase must respect.
+/// - `template_id`: The id of the template of the phase.
+/// - `start_date`: The earliest [NaiveDateTime](chrono::NaiveDateTime) at which the phase could
+///   otherwise start.
+/// - `duration`: The [Duration](chrono::Duration) of the phase.
+pub fn get_earliest_start_date_outside_blackouts(blackouts: &[Blackout], template_id: &str,
+                                                 start_date: NaiveDateTime,
+                                                 duration: Duration) -> NaiveDateTime {
+    let mut next_start_date: NaiveDateTime = start_date;
+
+    // Moving past one blackout might land within another, so this repeats until no blackout is
+    // broken. Every move is past the end of a blackout, so this can only happen once for each.
+    while let Some(end) = blackouts.iter()
+                                   .filter(|blackout| blackout.is_broken_by(template_id,
+                                                                            next_start_date,
+                                                                            duration))
+                                   .map(|blackout| blackout.period.end)
+                                   .max() {
+        next_start_date = end + Duration::seconds(1);
+    }
+
+    next_start_date
+}
+
+fn get_all_weekdays() -> Vec<String> {
+    let weekdays = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+    weekdays.iter().map(|day| day.to_string()).collect()
+}
+
+/// A specification of the times at which people are at work in the brewery, given as the
+/// `workingCalendar` entry of either the timeline, or a recipe.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WorkingCalendarSpec {
+    /// The names of the days of the week that are worked (e.g. `"saturday"`, or `"sat"`).
+    /// Defaults to every day.
+    #[serde(default="get_all_weekdays")]
+    pub weekdays: Vec<String>,
+
+    /// The hours that are worked on each working day. Defaults to the whole day.
+    #[serde(default)]
+    pub hours: Option<WorkingHoursSpec>,
+
+    /// The dates of days that aren't worked, even though they fall on a working day of the week.
+    #[serde(default)]
+    pub holidays: Vec<String>
+}
+
+/// The hours worked on each working day, as times in `HH:MM` format, given as the `hours` entry of
+/// a `workingCalendar`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WorkingHoursSpec {
+    #[serde(rename="start")]
+    pub start_string: String,
+
+    #[serde(rename="end")]
+    pub end_string: String
+}
+
+impl WorkingCalendarSpec {
+    /// Retrieve the [WorkingCalendar](WorkingCalendar) described by this `WorkingCalendarSpec`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either the `WorkingCalendar`, or an `Err` containing the first
+    ///   weekday, time or date string that could not be parsed.
+    ///
+    pub fn calendar(&self) -> Result<WorkingCalendar, String> {
+        let mut weekdays: Vec<Weekday> = vec![];
+        for next_weekday in &self.weekdays {
+            match next_weekday.parse::<Weekday>() {
+                Ok(x) => weekdays.push(x),
+                Err(_e) => return Err(next_weekday.clone())
+            }
+        }
+
+        let hours: Option<(NaiveTime, NaiveTime)> = match &self.hours {
+            Some(hours_spec) => Some((get_time_from_string(&hours_spec.start_string)?,
+                                      get_time_from_string(&hours_spec.end_string)?)),
+            None => None
+        };
+
+        let mut holidays: Vec<NaiveDate> = vec![];
+        for next_holiday in &self.holidays {
+            match get_naive_date_time_from_string(&next_holiday[..]) {
+                Ok(x) => holidays.push(x.date()),
+                Err(_e) => return Err(next_holiday.clone())
+            }
+        }
+
+        Ok(WorkingCalendar {
+            weekdays,
+            hours,
+            holidays
+        })
+    }
+}
+
+fn get_time_from_string(time_string: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time_string, "%H:%M").map_err(|_e| time_string.to_string())
+}
+
+/// The times at which people are at work in the brewery, and so at which hands-on phases can
+/// start.
+#[derive(Clone, PartialEq, Debug)]
+pub struct WorkingCalendar {
+    pub weekdays: Vec<Weekday>,
+
+    /// The start and end of the working hours of each working day, or `None` if the whole day is
+    /// worked.
+    pub hours: Option<(NaiveTime, NaiveTime)>,
+    pub holidays: Vec<NaiveDate>
+}
+
+impl WorkingCalendar {
+    /// Determine if a date falls on a working day.
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.weekdays.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    /// Determine if a [NaiveDateTime](chrono::NaiveDateTime) falls within working time, i.e. on a
+    /// working day, within the working hours.
+    pub fn is_working_time(&self, date_time: NaiveDateTime) -> bool {
+        self.is_working_day(date_time.date()) && match self.hours {
+            Some((start, end)) => start <= date_time.time() && date_time.time() < end,
+            None => true
+        }
+    }
+
+    /// Retrieve the earliest working time at or after a given date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the earliest working time, or `None` if there is no working time
+    ///   at all, because no weekday is worked, or the working hours are empty.
+    pub fn get_next_working_time(&self, date_time: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.is_working_time(date_time) {
+            return Some(date_time);
+        }
+
+        let (start, end) = self.hours.unwrap_or((NaiveTime::from_hms(0, 0, 0),
+                                                 NaiveTime::from_hms(23, 59, 59)));
+        if end <= start && self.hours.is_some() {
+            return None;
+        }
+
+        // Every week has at least one working day, unless it falls on a holiday, so a working day
+        // must turn up within a week of every holiday that is passed over.
+        let mut date: NaiveDate = date_time.date();
+        for _ in 0..(self.holidays.len() + 1) * 7 + 1 {
+            if self.is_working_day(date) {
+                let start_of_work: NaiveDateTime = date.and_time(start);
+                if start_of_work >= date_time {
+                    return Some(start_of_work);
+                }
+            }
+
+            date = date.succ();
+        }
+
+        None
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{Duration, NaiveDateTime};
+
+use serde::{Serialize, Deserialize};
+
+use string_builder::Builder;
+
+use super::{ProductionSchedule, DISPLAY_DATE_FORMAT};
+use super::error::Error;
+use super::resources::ResourceType;
+use super::scheduler::SchedulerKind;
+
+fn get_default_count() -> usize {
+    1
+}
+
+/// A hypothetical change to the `resources` block of a BPD file.
+///
+/// Within a scenarios file, a delta is given as an object with an `action` of either `"add"`, as
+/// in `{ "action": "add", "type": "fermentor", "capacity": "14g" }`, or `"remove"`, as in
+/// `{ "action": "remove", "id": 3 }`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag="action", rename_all="lowercase")]
+pub enum ResourceDelta {
+    /// Add `count` new `Resource`s of a `ResourceType`, with the next free ids. If no `name` is
+    /// given, each is named after its type, e.g. `"Extra fermentor 1"`.
+    Add {
+        #[serde(rename="type")]
+        resource_type: ResourceType,
+
+        #[serde(default)]
+        name: Option<String>,
+
+        capacity: String,
+
+        #[serde(default="get_default_count")]
+        count: usize
+    },
+
+    /// Remove the `Resource` with a given id.
+    Remove {
+        id: usize
+    }
+}
+
+/// A named set of `ResourceDelta`s, to be compared against the schedule as given.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CapacityScenario {
+    pub name: String,
+    pub deltas: Vec<ResourceDelta>
+}
+
+/// The date at which the last phase of a recipe ends.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecipeCompletion {
+    pub recipe: String,
+    pub completion_date: NaiveDateTime
+}
+
+/// The makespan and per-recipe completion dates of a scheduled `ProductionSchedule`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScheduleSummary {
+    /// The time from the start of the first phase to the end of the last one.
+    pub makespan: Duration,
+
+    /// The completion of each recipe, in the order the recipes are given.
+    pub completions: Vec<RecipeCompletion>
+}
+
+impl ScheduleSummary {
+    pub fn new(schedule: &ProductionSchedule) -> Self {
+        let completions: Vec<RecipeCompletion> = schedule.get_recipe_iterator()
+          .filter_map(|recipe| {
+              recipe.get_phase_iterator()
+                    .map(|phase| phase.start_date + phase.duration)
+                    .max()
+                    .map(|completion_date| RecipeCompletion {
+                        recipe: recipe.name.clone(),
+                        completion_date
+                    })
+          })
+          .collect();
+
+        let first_start: Option<NaiveDateTime> = schedule.get_recipe_iterator()
+                                                         .flat_map(|recipe| recipe.get_phase_iterator())
+                                                         .map(|phase| phase.start_date)
+                                                         .min();
+        let last_end: Option<NaiveDateTime> = completions.iter()
+                                                         .map(|x| x.completion_date)
+                                                         .max();
+
+        ScheduleSummary {
+            makespan: match (first_start, last_end) {
+                (Some(start), Some(end)) => end - start,
+                _ => Duration::zero()
+            },
+            completions
+        }
+    }
+
+    /// Retrieve the completion date of the recipe with a given name, if it has any phases.
+    pub fn get_completion_date(&self, recipe: &str) -> Option<NaiveDateTime> {
+        self.completions.iter()
+                        .find(|x| x.recipe == recipe)
+                        .map(|x| x.completion_date)
+    }
+}
+
+/// The outcome of scheduling a single `CapacityScenario`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScenarioOutcome {
+    pub name: String,
+
+    /// Either the `ScheduleSummary` of the scenario, or the [Error](super::error::Error) that
+    /// stopped it from being scheduled, e.g. because a `Resource` needed by some phase was removed.
+    pub result: Result<ScheduleSummary, Error>
+}
+
+impl ScenarioOutcome {
+    /// Retrieve how much longer (or, if negative, shorter) the makespan of this scenario is than
+    /// that of a baseline, if the scenario could be scheduled.
+    pub fn get_makespan_change(&self, baseline: &ScheduleSummary) -> Option<Duration> {
+        self.result.as_ref().ok().map(|summary| summary.makespan - baseline.makespan)
+    }
+
+    /// Retrieve how much later (or, if negative, earlier) a recipe completes in this scenario than
+    /// in a baseline, if the scenario could be scheduled.
+    pub fn get_completion_change(&self, baseline: &ScheduleSummary,
+                                 recipe: &str) -> Option<Duration> {
+        let summary: &ScheduleSummary = self.result.as_ref().ok()?;
+
+        Some(summary.get_completion_date(recipe)? - baseline.get_completion_date(recipe)?)
+    }
+}
+
+/// A comparison of the schedule as given against each of a set of `CapacityScenario`s.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CapacityComparison {
+    pub baseline: ScheduleSummary,
+
+    /// The outcome of each scenario, in the order given.
+    pub scenarios: Vec<ScenarioOutcome>
+}
+
+impl CapacityComparison {
+    /// Schedule a BPD document as given, and again with each of a set of `CapacityScenario`s
+    /// applied to its `resources` block.
+    ///
+    /// # Arguments
+    /// - `json_data`: A string slice containing the BPD document.
+    /// - `scheduler`: The `SchedulerKind` to schedule with, or `None` to use the one given in the
+    ///   `timeline` block.
+    /// - `scenarios`: The `CapacityScenario`s to compare.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `CapacityComparison`, or an
+    ///   [Error](super::error::Error) if the schedule as given can't be scheduled. A scenario that
+    ///   can't be scheduled doesn't stop the others from being compared.
+    pub fn new(json_data: &str, scheduler: Option<SchedulerKind>,
+               scenarios: &[CapacityScenario]) -> Result<Self, Error> {
+        let baseline = ScheduleSummary::new(&get_schedule_with_deltas(json_data, scheduler, &[])?);
+
+        let outcomes: Vec<ScenarioOutcome> = scenarios.iter().map(|scenario| {
+            ScenarioOutcome {
+                name: scenario.name.clone(),
+                result: get_schedule_with_deltas(json_data, scheduler, &scenario.deltas)
+                          .map(|schedule| ScheduleSummary::new(&schedule))
+            }
+        }).collect();
+
+        Ok(CapacityComparison {
+            baseline,
+            scenarios: outcomes
+        })
+    }
+
+    /// Retrieve a `String` containing a plain-text table comparing the baseline and each scenario
+    /// side by side, with a row for the makespan, and one for the completion of each recipe. Each
+    /// scenario's values are followed by their change from the baseline, in days. The errors of
+    /// scenarios that couldn't be scheduled are listed after the table.
+    pub fn get_string_as_table(&self) -> String {
+        let mut rows: Vec<Vec<String>> = vec![];
+
+        let mut header: Vec<String> = vec![String::new(), String::from("baseline")];
+        header.extend(self.scenarios.iter().map(|x| x.name.clone()));
+        rows.push(header);
+
+        let mut makespan_row: Vec<String> = vec![String::from("makespan"),
+                                                 format!("{:.1}d", get_days(self.baseline.makespan))];
+        for next_scenario in &self.scenarios {
+            makespan_row.push(match &next_scenario.result {
+                Ok(summary) => format!("{:.1}d ({})", get_days(summary.makespan),
+                                       get_change_string(summary.makespan - self.baseline.makespan)),
+                Err(_e) => String::from("error")
+            });
+        }
+        rows.push(makespan_row);
+
+        for next_completion in &self.baseline.completions {
+            let mut row: Vec<String> = vec![next_completion.recipe.clone(),
+                                            next_completion.completion_date
+                                                           .format(DISPLAY_DATE_FORMAT)
+                                                           .to_string()];
+            for next_scenario in &self.scenarios {
+                let completion_date: Option<NaiveDateTime> = next_scenario.result.as_ref().ok()
+                  .and_then(|summary| summary.get_completion_date(&next_completion.recipe));
+                row.push(match completion_date {
+                    Some(x) => format!("{} ({})", x.format(DISPLAY_DATE_FORMAT),
+                                       get_change_string(x - next_completion.completion_date)),
+                    None => String::from("-")
+                });
+            }
+            rows.push(row);
+        }
+
+        let column_count: usize = rows[0].len();
+        let widths: Vec<usize> = (0..column_count).map(|column| {
+            rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0)
+        }).collect();
+
+        let mut builder = Builder::default();
+        for next_row in &rows {
+            let cells: Vec<String> = next_row.iter().enumerate()
+                                             .map(|(column, cell)| format!("{:<width$}", cell,
+                                                                           width = widths[column]))
+                                             .collect();
+            builder.append(format!("{}\n", cells.join("  ").trim_end()));
+        }
+
+        for next_scenario in &self.scenarios {
+            if let Err(e) = &next_scenario.result {
+                builder.append(format!("\nscenario '{}': {}\n", next_scenario.name, e));
+            }
+        }
+
+        builder.string().unwrap()
+    }
+}
+
+fn get_schedule_with_deltas(json_data: &str, scheduler: Option<SchedulerKind>,
+                            deltas: &[ResourceDelta]) -> Result<ProductionSchedule, Error> {
+    let mut schedule: ProductionSchedule = serde_json::from_str(json_data)?;
+    if let Some(x) = scheduler {
+        schedule.timeline.scheduler = x;
+    }
+
+    schedule.apply_resource_deltas(deltas)?;
+    schedule.init()?;
+
+    Ok(schedule)
+}
+
+fn get_days(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 86400.0
+}
+
+fn get_change_string(change: Duration) -> String {
+    // Changes of a few seconds, such as from handing resources over, would otherwise show as
+    // "-0.0d".
+    let days: f64 = (get_days(change) * 10.0).round() / 10.0;
+
+    match days == 0.0 {
+        true => String::from("+0.0d"),
+        false => format!("{:+.1}d", days)
+    }
+}
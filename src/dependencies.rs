@@ -0,0 +1,314 @@
+use chrono::{Duration, NaiveDateTime};
+
+use super::error::Error;
+use super::phases::{DependencyAnchor, PhaseDependencySpec, ProductionPhaseTemplate};
+use super::recipes::RecipeSpec;
+
+/// A resolved dependency of one phase of a recipe upon another phase, either of the same recipe,
+/// or of another recipe in the schedule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseDependency {
+    /// The index, within the schedule's `recipes` block, of the recipe containing the phase
+    /// depended upon, or `None` if the phase is within the same recipe.
+    pub recipe_index: Option<usize>,
+
+    /// The index, within its recipe's `phases` block, of the phase depended upon.
+    pub phase_index: usize,
+    pub from: DependencyAnchor,
+    pub lag: Duration
+}
+
+impl PhaseDependency {
+    /// Retrieve the earliest date at which the dependent phase can start.
+    ///
+    /// # Arguments
+    /// - `start_date`: The date at which the phase depended upon starts.
+    /// - `duration`: The duration of the phase depended upon.
+    pub fn get_earliest_start_date(&self, start_date: NaiveDateTime,
+                                   duration: Duration) -> NaiveDateTime {
+        match self.from {
+            DependencyAnchor::Start => start_date + self.lag,
+            DependencyAnchor::End => start_date + duration + self.lag
+        }
+    }
+}
+
+/// The dependencies between the phases of a single recipe, forming a directed acyclic graph.
+///
+/// Phases are referred to by their index within the recipe's `phases` block. Dependencies upon
+/// phases of other recipes are kept alongside, but don't take part in ordering the phases, since
+/// other recipes are always scheduled first (see [get_recipe_order](get_recipe_order)).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseGraph {
+    dependencies: Vec<Vec<PhaseDependency>>,
+    order: Vec<usize>
+}
+
+impl PhaseGraph {
+    /// Construct a new `PhaseGraph` from a [RecipeSpec](super::recipes::RecipeSpec).
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` whose phases should be placed in the graph.
+    /// - `recipe_specs`: Every `RecipeSpec` of the schedule, against which dependencies upon
+    ///   phases of other recipes are resolved.
+    /// - `templates`: The `ProductionPhaseTemplate`s of the schedule. A phase without an `after`
+    ///   block depends upon the end of the phase before it, when the phases are sorted by the
+    ///   `order` of their templates. Phases with the same `order` stay in the order they are
+    ///   given, and phases with an unknown template are placed last.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `PhaseGraph`, or an [Error](super::error::Error) if a
+    ///   dependency refers to a phase that doesn't exist or can't be identified, has a lag that
+    ///   can't be parsed, or if the dependencies form a cycle.
+    pub fn new(recipe_spec: &RecipeSpec, recipe_specs: &[RecipeSpec],
+               templates: &[ProductionPhaseTemplate]) -> Result<Self, Error> {
+        let phase_specs = &recipe_spec.phase_specs;
+
+        let get_template_order = |index: usize| {
+            templates.iter()
+                     .find(|template| template.id == phase_specs[index].template)
+                     .map_or(usize::MAX, |template| template.order)
+        };
+
+        let mut by_template_order: Vec<usize> = (0..phase_specs.len()).collect();
+        by_template_order.sort_by_key(|index| get_template_order(*index));
+
+        let mut dependencies: Vec<Vec<PhaseDependency>> = vec![vec![]; phase_specs.len()];
+        for (position, phase_index) in by_template_order.iter().enumerate() {
+            let phase_spec = &phase_specs[*phase_index];
+            dependencies[*phase_index] = match &phase_spec.after {
+                Some(after) => {
+                    let mut resolved: Vec<PhaseDependency> = vec![];
+                    for next_dependency in after {
+                        resolved.push(resolve_dependency(recipe_spec, *phase_index,
+                                                         next_dependency, recipe_specs)?);
+                    }
+
+                    resolved
+                },
+                None => match position {
+                    0 => vec![],
+                    _ => vec![PhaseDependency {
+                        recipe_index: None,
+                        phase_index: by_template_order[position - 1],
+                        from: DependencyAnchor::End,
+                        lag: Duration::zero()
+                    }]
+                }
+            };
+        }
+
+        let local_dependencies: Vec<Vec<usize>> = dependencies.iter().map(|deps| {
+            deps.iter()
+                .filter(|dep| dep.recipe_index.is_none())
+                .map(|dep| dep.phase_index)
+                .collect()
+        }).collect();
+
+        let order = match get_topological_order(&local_dependencies, &by_template_order) {
+            Ok(x) => x,
+            Err(phase_index) => return Err(Error::DependencyCycle {
+                recipe: recipe_spec.name.clone(),
+                phase_index
+            })
+        };
+
+        Ok(PhaseGraph {
+            dependencies,
+            order
+        })
+    }
+
+    /// Retrieve the indices of the phases in an order in which they can be scheduled, such that
+    /// every phase comes after all of the phases of the same recipe it depends upon.
+    pub fn get_phase_order(&self) -> &[usize] {
+        &self.order[..]
+    }
+
+    /// Retrieve the dependencies of the phase at a given index.
+    pub fn get_dependencies_of(&self, phase_index: usize) -> &[PhaseDependency] {
+        &self.dependencies[phase_index][..]
+    }
+}
+
+/// Determine the order in which recipes should be scheduled, such that every recipe is scheduled
+/// after all of the recipes whose phases it depends upon.
+///
+/// # Arguments
+/// - `recipe_specs`: Every `RecipeSpec` of the schedule.
+/// - `preferred_order`: The indices of the recipes, in the order they should be scheduled if they
+///   didn't depend upon each other.
+///
+/// # Returns
+/// - A `Result` containing either the indices of the recipes in the order they should be
+///   scheduled, or an [Error](super::error::Error) if a dependency refers to a recipe that doesn't
+///   exist, or if the recipes depend upon each other in a cycle.
+pub fn get_recipe_order(recipe_specs: &[RecipeSpec],
+                        preferred_order: &[usize]) -> Result<Vec<usize>, Error> {
+    let mut recipe_dependencies: Vec<Vec<usize>> = vec![vec![]; recipe_specs.len()];
+    for (recipe_index, recipe_spec) in recipe_specs.iter().enumerate() {
+        for (phase_index, phase_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            for next_dependency in phase_spec.after.iter().flatten() {
+                if let Some(other_index) = find_recipe_index(recipe_spec, phase_index,
+                                                             next_dependency, recipe_specs)? {
+                    recipe_dependencies[recipe_index].push(other_index);
+                }
+            }
+        }
+    }
+
+    match get_topological_order(&recipe_dependencies, preferred_order) {
+        Ok(x) => Ok(x),
+        Err(recipe_index) => {
+            // Report the first phase of the recipe that depends upon another recipe.
+            let recipe_spec = &recipe_specs[recipe_index];
+            let phase_index = recipe_spec.phase_specs.iter().position(|spec| {
+                spec.after.iter().flatten().any(|dep| is_external(recipe_spec, dep))
+            }).unwrap_or(0);
+
+            Err(Error::DependencyCycle {
+                recipe: recipe_spec.name.clone(),
+                phase_index
+            })
+        }
+    }
+}
+
+/// Determine if a dependency refers to a phase of a recipe other than the given recipe.
+fn is_external(recipe_spec: &RecipeSpec, dependency: &PhaseDependencySpec) -> bool {
+    match &dependency.recipe {
+        Some(name) => *name != recipe_spec.name,
+        None => false
+    }
+}
+
+/// Find the index of the recipe a dependency refers to, or `None` if it refers to a phase of the
+/// recipe containing it.
+fn find_recipe_index(recipe_spec: &RecipeSpec, phase_index: usize,
+                     dependency: &PhaseDependencySpec,
+                     recipe_specs: &[RecipeSpec]) -> Result<Option<usize>, Error> {
+    if !is_external(recipe_spec, dependency) {
+        return Ok(None);
+    }
+
+    let other_name = dependency.recipe.as_ref().unwrap();
+    let matching: Vec<usize> = recipe_specs.iter()
+                                           .enumerate()
+                                           .filter(|(_, spec)| spec.name == *other_name)
+                                           .map(|(index, _)| index)
+                                           .collect();
+
+    match matching.len() {
+        1 => Ok(Some(matching[0])),
+        _ => Err(Error::UnresolvedRecipePhase {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            other_recipe: other_name.clone(),
+            phase: dependency.phase.clone()
+        })
+    }
+}
+
+/// Resolve a `PhaseDependencySpec` of the phase at `phase_index` within `recipe_spec`.
+fn resolve_dependency(recipe_spec: &RecipeSpec, phase_index: usize,
+                      dependency: &PhaseDependencySpec,
+                      recipe_specs: &[RecipeSpec]) -> Result<PhaseDependency, Error> {
+    let lag: Duration = match dependency.lag() {
+        Some(x) => x,
+        None => return Err(Error::UnparseableDuration {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            duration: dependency.lag_string.clone()
+        })
+    };
+
+    let recipe_index = find_recipe_index(recipe_spec, phase_index, dependency, recipe_specs)?;
+    let depended_upon_index = match recipe_index {
+        Some(other_index) => {
+            let other_spec = &recipe_specs[other_index];
+            match find_phase_index(other_spec, phase_index, &dependency.phase[..]) {
+                Ok(x) => x,
+                Err(_e) => return Err(Error::UnresolvedRecipePhase {
+                    recipe: recipe_spec.name.clone(),
+                    phase_index,
+                    other_recipe: other_spec.name.clone(),
+                    phase: dependency.phase.clone()
+                })
+            }
+        },
+        None => find_phase_index(recipe_spec, phase_index, &dependency.phase[..])?
+    };
+
+    Ok(PhaseDependency {
+        recipe_index,
+        phase_index: depended_upon_index,
+        from: dependency.from,
+        lag
+    })
+}
+
+/// Find the index of the only phase of a recipe with a given phase id.
+fn find_phase_index(recipe_spec: &RecipeSpec, dependent_index: usize,
+                    phase_id: &str) -> Result<usize, Error> {
+    let matching: Vec<usize> = recipe_spec.phase_specs.iter()
+                                                      .enumerate()
+                                                      .filter(|(_, spec)| spec.phase_id() == phase_id)
+                                                      .map(|(index, _)| index)
+                                                      .collect();
+
+    match matching.len() {
+        1 => Ok(matching[0]),
+        0 => Err(Error::UnknownPhase {
+            recipe: recipe_spec.name.clone(),
+            phase_index: dependent_index,
+            phase: phase_id.to_string()
+        }),
+        _ => Err(Error::AmbiguousPhase {
+            recipe: recipe_spec.name.clone(),
+            phase_index: dependent_index,
+            phase: phase_id.to_string()
+        })
+    }
+}
+
+/// Order the nodes of a graph such that each comes after the nodes it depends upon. Whenever more
+/// than one node is ready, the one earliest in `preferred_order` is taken first.
+///
+/// # Returns
+/// - A `Result` containing either the ordered nodes, or a node within a dependency cycle.
+fn get_topological_order(dependencies: &[Vec<usize>],
+                         preferred_order: &[usize]) -> Result<Vec<usize>, usize> {
+    let mut order: Vec<usize> = vec![];
+    let mut is_placed: Vec<bool> = vec![false; dependencies.len()];
+
+    while order.len() < dependencies.len() {
+        let next_ready = preferred_order.iter().find(|index| {
+            !is_placed[**index] && dependencies[**index].iter().all(|dep| is_placed[*dep])
+        });
+
+        match next_ready {
+            Some(index) => {
+                is_placed[*index] = true;
+                order.push(*index);
+            },
+            None => return Err(find_node_in_cycle(dependencies, &is_placed))
+        }
+    }
+
+    Ok(order)
+}
+
+/// Find a node that lies within a dependency cycle, given that none of the nodes not yet placed
+/// are ready to be placed.
+fn find_node_in_cycle(dependencies: &[Vec<usize>], is_placed: &[bool]) -> usize {
+    // Every node not yet placed depends upon another node not yet placed, so following those
+    // dependencies must eventually revisit a node, and that node is within a cycle.
+    let mut is_visited: Vec<bool> = vec![false; dependencies.len()];
+    let mut current: usize = is_placed.iter().position(|placed| !placed).unwrap();
+    while !is_visited[current] {
+        is_visited[current] = true;
+        current = *dependencies[current].iter().find(|index| !is_placed[**index]).unwrap();
+    }
+
+    current
+}
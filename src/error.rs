@@ -0,0 +1,256 @@
+use std::fmt;
+
+use super::resources::ResourceType;
+
+/// An error that can occur while constructing or scheduling a
+/// [ProductionSchedule](super::ProductionSchedule).
+///
+/// Every variant that arises while scheduling a particular recipe carries the name of the recipe
+/// and the (zero-based) index of the phase within that recipe's `phases` block, so that the
+/// offending part of the BPD file can be located easily.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The input could not be deserialized from JSON. The `String` contains the explanation from
+    /// the deserializer, including the line and column at which the failure occurred.
+    Parse(String),
+
+    /// A phase referenced a phase template id that is not defined in `phaseTemplates`.
+    UnknownTemplate {
+        recipe: String,
+        phase_index: usize,
+        template: String
+    },
+
+    /// A duration (either on a phase, or the default duration of the phase's template) could not
+    /// be converted to a [Duration](chrono::Duration).
+    UnparseableDuration {
+        recipe: String,
+        phase_index: usize,
+        duration: String
+    },
+
+    /// A date could not be parsed. `phase_index` is `None` if the date belongs to the recipe
+    /// itself (or was inherited from the timeline) rather than to one of its phases.
+    UnparseableDate {
+        recipe: String,
+        phase_index: Option<usize>,
+        date: String
+    },
+
+    /// The batch size of a recipe could not be converted to a [Volume](super::volume::Volume).
+    UnparseableBatchSize {
+        recipe: String,
+        batch_size: String
+    },
+
+    /// A date within the `unavailable` block of a `Resource` could not be parsed.
+    UnparseableUnavailablePeriod {
+        resource: String,
+        date: String
+    },
+
+    /// The turnaround of a `Resource`, either its own or that of its `ResourceType`, could not be
+    /// converted to a [Duration](chrono::Duration).
+    UnparseableTurnaround {
+        resource: String,
+        duration: String
+    },
+
+    /// A [ResourceDelta](super::capacity::ResourceDelta) removes a `Resource` id that no
+    /// `Resource` has.
+    UnknownResource {
+        id: usize
+    },
+
+    /// A phase requires a `Resource` of a given `ResourceType` that can never be allocated, either
+    /// because no `Resource` of that type exists, or because none is large enough for the batch.
+    UnsatisfiableResource {
+        recipe: String,
+        phase_index: usize,
+        resource_type: ResourceType
+    },
+
+    /// The `pinnedResources` or `excludedResources` block of a phase refers to a `Resource` id or
+    /// name that no `Resource` has.
+    UnknownResourceReference {
+        recipe: String,
+        phase_index: usize,
+        resource: String
+    },
+
+    /// A phase is pinned to `Resource`s that can never be allocated to it, either because they
+    /// aren't of a type its template needs, or because they can't hold the batch, even together.
+    UnsatisfiablePin {
+        recipe: String,
+        phase_index: usize,
+        resources: Vec<String>
+    },
+
+    /// A phase depends upon a phase id that no phase of the recipe has.
+    UnknownPhase {
+        recipe: String,
+        phase_index: usize,
+        phase: String
+    },
+
+    /// A phase depends upon a phase id that more than one phase of the recipe has, so the phase
+    /// depended upon can't be determined. Such phases need an explicit `id`.
+    AmbiguousPhase {
+        recipe: String,
+        phase_index: usize,
+        phase: String
+    },
+
+    /// A phase depends upon a phase of another recipe, but either no recipe (or more than one) has
+    /// the name `other_recipe`, or no phase (or more than one) of that recipe has the id `phase`.
+    UnresolvedRecipePhase {
+        recipe: String,
+        phase_index: usize,
+        other_recipe: String,
+        phase: String
+    },
+
+    /// The dependencies between phases form a cycle, so none of the phases in the cycle can ever
+    /// start. If the cycle is within a single recipe, `phase_index` is one of the phases within
+    /// the cycle. Otherwise, `recipe` is one of the recipes within the cycle, and `phase_index`
+    /// is a phase of it that depends upon another recipe.
+    DependencyCycle {
+        recipe: String,
+        phase_index: usize
+    },
+
+    /// A hands-on phase can never start, because its working calendar has no working time at all.
+    NoWorkingTime {
+        recipe: String,
+        phase_index: usize
+    },
+
+    /// A recipe can't be ready by its `readyBy` date, even if it starts on its `start` date.
+    /// `bottleneck` is the `ResourceType` whose resources delayed the phases of the recipe the
+    /// most, or `None` if the phases weren't delayed waiting for resources at all.
+    /// `blocking_resources` contains the names of the resources of that type that were held when
+    /// the recipe needed them.
+    DeadlineMissed {
+        recipe: String,
+        ready_by: String,
+        bottleneck: Option<ResourceType>,
+        blocking_resources: Vec<String>
+    },
+
+    /// The first phase of a recipe can't start by its `notAfter` date. `bottleneck` is the
+    /// `ResourceType` whose resources delayed the phases of the recipe the most, or `None` if the
+    /// phases weren't delayed waiting for resources at all.
+    LateStart {
+        recipe: String,
+        not_after: String,
+        bottleneck: Option<ResourceType>,
+        blocking_resources: Vec<String>
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(message) => write!(f, "unable to parse production schedule: {}", message),
+            Error::UnknownTemplate { recipe, phase_index, template } => {
+                write!(f, "recipe '{}', phase {}: unknown phase template '{}'", recipe,
+                       phase_index, template)
+            },
+            Error::UnparseableDuration { recipe, phase_index, duration } => {
+                write!(f, "recipe '{}', phase {}: unable to parse duration '{}'", recipe,
+                       phase_index, duration)
+            },
+            Error::UnparseableDate { recipe, phase_index: Some(index), date } => {
+                write!(f, "recipe '{}', phase {}: unable to parse date '{}'", recipe, index, date)
+            },
+            Error::UnparseableDate { recipe, phase_index: None, date } => {
+                write!(f, "recipe '{}': unable to parse date '{}'", recipe, date)
+            },
+            Error::UnparseableBatchSize { recipe, batch_size } => {
+                write!(f, "recipe '{}': unable to parse batch size '{}'", recipe, batch_size)
+            },
+            Error::UnparseableUnavailablePeriod { resource, date } => {
+                write!(f, "resource '{}': unable to parse date '{}' of unavailable period",
+                       resource, date)
+            },
+            Error::UnparseableTurnaround { resource, duration } => {
+                write!(f, "resource '{}': unable to parse turnaround '{}'", resource, duration)
+            },
+            Error::UnknownResource { id } => {
+                write!(f, "no resource has id {}", id)
+            },
+            Error::UnsatisfiableResource { recipe, phase_index, resource_type } => {
+                write!(f, "recipe '{}', phase {}: no resource of type '{}' can be allocated",
+                       recipe, phase_index, resource_type)
+            },
+            Error::UnknownResourceReference { recipe, phase_index, resource } => {
+                write!(f, "recipe '{}', phase {}: refers to unknown resource '{}'", recipe,
+                       phase_index, resource)
+            },
+            Error::UnsatisfiablePin { recipe, phase_index, resources } => {
+                let names: Vec<String> = resources.iter().map(|x| format!("'{}'", x)).collect();
+                match resources.len() {
+                    1 => write!(f, "recipe '{}', phase {}: pinned resource {} can never be free for this phase",
+                                recipe, phase_index, names[0]),
+                    _ => write!(f, "recipe '{}', phase {}: pinned resources {} can never be free for this phase",
+                                recipe, phase_index, names.join(", "))
+                }
+            },
+            Error::UnknownPhase { recipe, phase_index, phase } => {
+                write!(f, "recipe '{}', phase {}: depends on unknown phase '{}'", recipe,
+                       phase_index, phase)
+            },
+            Error::AmbiguousPhase { recipe, phase_index, phase } => {
+                write!(f, "recipe '{}', phase {}: depends on phase '{}', but more than one phase has that id",
+                       recipe, phase_index, phase)
+            },
+            Error::UnresolvedRecipePhase { recipe, phase_index, other_recipe, phase } => {
+                write!(f, "recipe '{}', phase {}: depends on phase '{}' of recipe '{}', which doesn't identify a single phase",
+                       recipe, phase_index, phase, other_recipe)
+            },
+            Error::DependencyCycle { recipe, phase_index } => {
+                write!(f, "recipe '{}', phase {}: phase dependencies form a cycle", recipe,
+                       phase_index)
+            },
+            Error::NoWorkingTime { recipe, phase_index } => {
+                write!(f, "recipe '{}', phase {}: hands-on phase can't start, because there is no working time",
+                       recipe, phase_index)
+            },
+            Error::DeadlineMissed { recipe, ready_by, bottleneck: Some(resource_type),
+                                    blocking_resources } => {
+                write!(f, "recipe '{}': can't be ready by '{}', because resources of type '{}' aren't free in time{}",
+                       recipe, ready_by, resource_type, get_blocking_suffix(blocking_resources))
+            },
+            Error::DeadlineMissed { recipe, ready_by, bottleneck: None, .. } => {
+                write!(f, "recipe '{}': can't be ready by '{}', even if it starts on its start date",
+                       recipe, ready_by)
+            },
+            Error::LateStart { recipe, not_after, bottleneck: Some(resource_type),
+                               blocking_resources } => {
+                write!(f, "recipe '{}': can't start by '{}', because resources of type '{}' aren't free in time{}",
+                       recipe, not_after, resource_type, get_blocking_suffix(blocking_resources))
+            },
+            Error::LateStart { recipe, not_after, bottleneck: None, .. } => {
+                write!(f, "recipe '{}': can't start by '{}'", recipe, not_after)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+/// Retrieve the end of the message of an error caused by a bottleneck, naming the resources that
+/// were held when they were needed, if any are known.
+fn get_blocking_suffix(blocking_resources: &[String]) -> String {
+    let names: Vec<String> = blocking_resources.iter().map(|x| format!("'{}'", x)).collect();
+    match names.len() {
+        0 => String::new(),
+        _ => format!(" (held: {})", names.join(", "))
+    }
+}
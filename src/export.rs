@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+
+use serde::Serialize;
+
+use super::phases::PhaseInstance;
+use super::recipes::Recipe;
+use super::resources::{Allocation, AllocationHolder, Resource, ResourceType};
+use super::util::DATE_TIME_FORMAT;
+
+/// The version of the schema of a [ScheduleExport](ScheduleExport). It is increased whenever a
+/// field is removed, renamed, or changes meaning, but not when a field is added.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A machine-readable record of everything the scheduler decided for a
+/// [ProductionSchedule](super::ProductionSchedule): when each phase of each recipe runs, and what
+/// occupies each resource. Every date is given in [DATE_TIME_FORMAT](super::util::DATE_TIME_FORMAT).
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ScheduleExport {
+    /// The [SCHEMA_VERSION](SCHEMA_VERSION) this export was written with.
+    #[serde(rename="schemaVersion")]
+    pub schema_version: u32,
+
+    pub id: usize,
+    pub name: String,
+
+    /// The scheduled recipes, in the order they are given.
+    pub recipes: Vec<RecipeExport>,
+
+    /// Every tracked resource, in order of id.
+    pub resources: Vec<ResourceExport>
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct RecipeExport {
+    pub id: usize,
+    pub name: String,
+    pub color: String,
+
+    /// The start of the first phase of the recipe, or the recipe's own start if it has no phases.
+    pub start: String,
+
+    /// The end of the last phase of the recipe, or the recipe's own start if it has no phases.
+    pub end: String,
+
+    pub phases: Vec<PhaseExport>
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct PhaseExport {
+    pub id: usize,
+    pub description: String,
+    pub color: String,
+    pub start: String,
+    pub end: String,
+
+    #[serde(rename="durationSeconds")]
+    pub duration_seconds: i64,
+
+    #[serde(rename="handsOn")]
+    pub hands_on: bool,
+
+    /// The ids of the phases this phase depends upon, in order of id.
+    #[serde(rename="dependsOn")]
+    pub depends_on: Vec<usize>,
+
+    /// The ids of the phases that depend upon this phase, in order of id.
+    pub dependents: Vec<usize>,
+
+    /// The ids of the resources this phase uses.
+    #[serde(rename="resourceIds")]
+    pub resource_ids: Vec<usize>
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ResourceExport {
+    pub id: usize,
+    pub name: String,
+
+    #[serde(rename="type")]
+    pub resource_type: ResourceType,
+
+    pub capacity: String,
+
+    /// The periods over which the resource is allocated, in order of start.
+    pub allocations: Vec<AllocationExport>
+}
+
+/// A period over which a resource is allocated. Periods are inclusive of their `end`, as tracked
+/// by the [ResourceTracker](super::resources::ResourceTracker), so a resource handed from one phase
+/// to the next is allocated to the next a second after the first ends.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct AllocationExport {
+    pub start: String,
+    pub end: String,
+
+    /// What the resource is allocated to: `"phase"`, `"unavailable"` or `"unknown"`.
+    pub kind: String,
+
+    /// The id of the phase holding the resource, if it is held by a phase.
+    #[serde(rename="phaseId", skip_serializing_if="Option::is_none")]
+    pub phase_id: Option<usize>,
+
+    /// The id of the recipe containing the phase holding the resource, if it is held by a phase.
+    #[serde(rename="recipeId", skip_serializing_if="Option::is_none")]
+    pub recipe_id: Option<usize>,
+
+    /// The reason the resource is unavailable, if it is unavailable, and a reason was given.
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub reason: Option<String>
+}
+
+impl ScheduleExport {
+    /// Construct a new `ScheduleExport` from the results of scheduling.
+    ///
+    /// # Arguments
+    /// - `id`: The id of the [ProductionSchedule](super::ProductionSchedule).
+    /// - `name`: The name of the [ProductionSchedule](super::ProductionSchedule).
+    /// - `recipes`: The scheduled `Recipe`s.
+    /// - `resources`: Every tracked `Resource`, along with its allocations (see
+    ///   [get_allocations_of_resource](super::ProductionSchedule::get_allocations_of_resource)).
+    pub fn new(id: usize, name: &str, recipes: &[Recipe],
+               resources: &[(Resource, Vec<Allocation>)]) -> Self {
+        // Each phase lists the phases that depend upon it, so the phases each one depends upon
+        // are found by inverting those lists.
+        let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut recipe_ids: HashMap<usize, usize> = HashMap::new();
+        for next_recipe in recipes {
+            for next_phase in next_recipe.get_phase_iterator() {
+                recipe_ids.insert(next_phase.id, next_recipe.id);
+                for next_dependent in &next_phase.dependencies {
+                    predecessors.entry(*next_dependent).or_default().push(next_phase.id);
+                }
+            }
+        }
+
+        let recipes: Vec<RecipeExport> = recipes.iter().map(|recipe| {
+            let start: NaiveDateTime = recipe.get_phase_iterator()
+                                             .map(|phase| phase.start_date)
+                                             .min()
+                                             .unwrap_or(recipe.start_date);
+            let end: NaiveDateTime = recipe.get_phase_iterator()
+                                           .map(|phase| phase.start_date + phase.duration)
+                                           .max()
+                                           .unwrap_or(recipe.start_date);
+
+            RecipeExport {
+                id: recipe.id,
+                name: recipe.name.clone(),
+                color: recipe.color.clone(),
+                start: format_date(start),
+                end: format_date(end),
+                phases: recipe.get_phase_iterator()
+                              .map(|phase| PhaseExport::new(phase, &predecessors))
+                              .collect()
+            }
+        }).collect();
+
+        let mut resources: Vec<ResourceExport> = resources.iter().map(|(resource, allocations)| {
+            let mut allocations: Vec<Allocation> = allocations.clone();
+            allocations.sort_by_key(|allocation| allocation.period.start);
+
+            ResourceExport {
+                id: resource.id,
+                name: resource.name.clone(),
+                resource_type: resource.resource_type.clone(),
+                capacity: resource.capacity_str.clone(),
+                allocations: allocations.iter()
+                                        .map(|allocation| AllocationExport::new(allocation,
+                                                                                &recipe_ids))
+                                        .collect()
+            }
+        }).collect();
+        resources.sort_by_key(|resource| resource.id);
+
+        ScheduleExport {
+            schema_version: SCHEMA_VERSION,
+            id,
+            name: name.to_string(),
+            recipes,
+            resources
+        }
+    }
+
+    /// Retrieve a `String` containing this `ScheduleExport` as pretty-printed JSON.
+    pub fn get_string_as_json(&self) -> String {
+        // Every field is a string, number, boolean or list of them, so this can't fail.
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl PhaseExport {
+    fn new(phase: &PhaseInstance, predecessors: &HashMap<usize, Vec<usize>>) -> Self {
+        let mut depends_on: Vec<usize> = predecessors.get(&phase.id).cloned().unwrap_or_default();
+        depends_on.sort_unstable();
+
+        let mut dependents: Vec<usize> = phase.dependencies.clone();
+        dependents.sort_unstable();
+
+        PhaseExport {
+            id: phase.id,
+            description: phase.description.clone(),
+            color: phase.color_hex.clone(),
+            start: format_date(phase.start_date),
+            end: format_date(phase.start_date + phase.duration),
+            duration_seconds: phase.duration.num_seconds(),
+            hands_on: phase.is_hands_on,
+            depends_on,
+            dependents,
+            resource_ids: phase.resources_used.iter().map(|resource| resource.id).collect()
+        }
+    }
+}
+
+impl AllocationExport {
+    fn new(allocation: &Allocation, recipe_ids: &HashMap<usize, usize>) -> Self {
+        let (kind, phase_id, reason) = match &allocation.holder {
+            AllocationHolder::Phase { phase_id, .. } => ("phase", Some(*phase_id), None),
+            AllocationHolder::Unavailable { reason } => ("unavailable", None, reason.clone()),
+            AllocationHolder::Unknown => ("unknown", None, None)
+        };
+
+        AllocationExport {
+            start: format_date(allocation.period.start),
+            end: format_date(allocation.period.end),
+            kind: kind.to_string(),
+            phase_id,
+            recipe_id: phase_id.and_then(|id| recipe_ids.get(&id).cloned()),
+            reason
+        }
+    }
+}
+
+fn format_date(date: NaiveDateTime) -> String {
+    date.format(DATE_TIME_FORMAT).to_string()
+}
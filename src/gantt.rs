@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use string_builder::Builder;
+
+use super::phases::PhaseInstance;
+use super::recipes::Recipe;
+use super::util::is_valid_hex_color;
+
+/// Retrieve the phases that each phase depends upon, by the id of the dependent phase.
+///
+/// Each [PhaseInstance](super::phases::PhaseInstance) lists the ids of the phases that depend upon
+/// it (see [add_dependency](super::phases::PhaseInstance::add_dependency)), so this inverts those
+/// lists, across every recipe.
+fn get_predecessors(recipes: &[Recipe]) -> HashMap<usize, Vec<&PhaseInstance>> {
+    let mut predecessors: HashMap<usize, Vec<&PhaseInstance>> = HashMap::new();
+    for next_phase in recipes.iter().flat_map(|recipe| recipe.get_phase_iterator()) {
+        for next_dependent in &next_phase.dependencies {
+            predecessors.entry(*next_dependent).or_default().push(next_phase);
+        }
+    }
+
+    predecessors
+}
+
+/// Retrieve the phases a phase depends upon, if it starts as soon as the last of them ends.
+///
+/// Neither Mermaid nor PlantUML can show a task that depends upon another, but starts later than
+/// it ends, such as when it waited for resources, or has a lag. Those phases are given an explicit
+/// start instead, and their dependencies aren't shown.
+///
+/// # Returns
+/// - A `Vec` of the phases depended upon, in order of id, or an empty `Vec` if there are none, or
+///   the phase doesn't start as soon as they allow. A phase starting a second after its
+///   predecessors end still starts as soon as they allow, since that second is only the hand-over
+///   of a resource between them.
+fn get_followed_phases<'a>(phase: &PhaseInstance,
+                           predecessors: &HashMap<usize, Vec<&'a PhaseInstance>>)
+  -> Vec<&'a PhaseInstance> {
+    let mut followed: Vec<&PhaseInstance> = match predecessors.get(&phase.id) {
+        Some(x) => x.clone(),
+        None => return vec![]
+    };
+    followed.sort_by_key(|x| x.id);
+
+    let last_end: Option<NaiveDateTime> = followed.iter().map(|x| get_end_date(x)).max();
+    match last_end {
+        Some(x) if phase.start_date >= x && phase.start_date - x <= Duration::seconds(1) => followed,
+        _ => vec![]
+    }
+}
+
+fn get_end_date(phase: &PhaseInstance) -> NaiveDateTime {
+    phase.start_date + phase.duration
+}
+
+/// Retrieve the last day on which a phase is in progress. A phase ending at midnight isn't in
+/// progress on the day it ends.
+fn get_last_day(phase: &PhaseInstance) -> NaiveDate {
+    match phase.duration > Duration::zero() {
+        true => (get_end_date(phase) - Duration::seconds(1)).date(),
+        false => phase.start_date.date()
+    }
+}
+
+/// Retrieve a `String` representing a set of scheduled recipes as a Mermaid `gantt` block.
+///
+/// # Arguments
+/// - `title`: The title of the chart.
+/// - `recipes`: The scheduled `Recipe`s, each of which becomes a section, with a task for each of
+///   its phases.
+///
+/// # Returns
+/// - A `String` containing the `gantt` block, without the surrounding code fence. A task that
+///   starts as soon as the phases it depends upon allow is placed `after` them. Any other task is
+///   placed at its start date, to the second. Mermaid doesn't allow tasks to be colored
+///   individually, so the colors of phases aren't included.
+pub fn get_string_in_mermaid_format(title: &str, recipes: &[Recipe]) -> String {
+    let predecessors = get_predecessors(recipes);
+
+    let mut builder = Builder::default();
+    builder.append("gantt\n");
+    builder.append(format!("    title {}\n", get_mermaid_text(title)));
+    builder.append("    dateFormat YYYY-MM-DD HH:mm:ss\n");
+    builder.append("    axisFormat %Y-%m-%d\n");
+
+    for next_recipe in recipes {
+        builder.append(format!("    section {}\n", get_mermaid_text(&next_recipe.name)));
+
+        for next_phase in next_recipe.get_phase_iterator() {
+            let followed: Vec<&PhaseInstance> = get_followed_phases(next_phase, &predecessors);
+            let start: String = match followed.is_empty() {
+                true => next_phase.start_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+                false => {
+                    let ids: Vec<String> = followed.iter().map(|x| format!("p{}", x.id)).collect();
+                    format!("after {}", ids.join(" "))
+                }
+            };
+
+            builder.append(format!("    {} :p{}, {}, {}\n", get_mermaid_text(&next_phase.description),
+                                   next_phase.id, start, get_mermaid_duration(next_phase.duration)));
+        }
+    }
+
+    builder.string().unwrap()
+}
+
+/// Escape the characters that would otherwise end a title, section or task name in Mermaid, using
+/// its entity codes.
+fn get_mermaid_text(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for next_char in text.chars() {
+        match next_char {
+            '#' => escaped.push_str("#35;"),
+            ':' => escaped.push_str("#58;"),
+            ';' => escaped.push_str("#59;"),
+            '\n' => escaped.push(' '),
+            _ => escaped.push(next_char)
+        }
+    }
+
+    escaped
+}
+
+/// Format a [Duration](chrono::Duration) in the largest unit Mermaid understands that gives it
+/// exactly, e.g. `10d`, `4h` or `90m`.
+fn get_mermaid_duration(duration: Duration) -> String {
+    let seconds: i64 = duration.num_seconds();
+    if seconds > 0 && seconds % 86400 == 0 {
+        format!("{}d", seconds / 86400)
+    } else if seconds > 0 && seconds % 3600 == 0 {
+        format!("{}h", seconds / 3600)
+    } else if seconds > 0 && seconds % 60 == 0 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Retrieve a `String` representing a set of scheduled recipes as a PlantUML `@startgantt`
+/// document.
+///
+/// # Arguments
+/// - `title`: The title of the chart.
+/// - `recipes`: The scheduled `Recipe`s, each of which becomes a section, separated by its name,
+///   with a task for each of its phases.
+///
+/// # Returns
+/// - A `String` containing the document. PlantUML schedules tasks by the day, so each task lasts
+///   from the day its phase starts to the last day its phase is in progress. A task is started at
+///   the end of the last phase it depends upon if that phase ends the day before, and the task
+///   starts as soon as the phases it depends upon allow. Any other task starts on the day its
+///   phase starts. Each task is colored in the color of its phase.
+pub fn get_string_in_plantuml_format(title: &str, recipes: &[Recipe]) -> String {
+    let predecessors = get_predecessors(recipes);
+
+    let project_start: Option<NaiveDate> = recipes.iter()
+                                                  .flat_map(|recipe| recipe.get_phase_iterator())
+                                                  .map(|phase| phase.start_date.date())
+                                                  .min();
+
+    let mut builder = Builder::default();
+    builder.append("@startgantt\n");
+    builder.append(format!("title {}\n", title.replace('\n', " ")));
+    if let Some(x) = project_start {
+        builder.append(format!("Project starts {}\n", x.format("%Y-%m-%d")));
+    }
+
+    for next_recipe in recipes {
+        builder.append(format!("-- {} --\n", next_recipe.name.replace('\n', " ")));
+
+        for next_phase in next_recipe.get_phase_iterator() {
+            let last_followed: Option<&PhaseInstance> = get_followed_phases(next_phase, &predecessors)
+              .into_iter()
+              .max_by_key(|x| (get_end_date(x), x.id));
+
+            let start: String = match last_followed {
+                Some(x) if get_last_day(x).succ() == next_phase.start_date.date() => {
+                    format!("starts at [P{}]'s end", x.id)
+                },
+                _ => format!("starts {}", next_phase.start_date.format("%Y-%m-%d"))
+            };
+
+            let days: i64 = (get_last_day(next_phase) - next_phase.start_date.date()).num_days() + 1;
+            let lasts: String = match days {
+                1 => String::from("lasts 1 day"),
+                _ => format!("lasts {} days", days)
+            };
+
+            let mut task: String = format!("[{}] as [P{}] {} and {}",
+                                           get_plantuml_task_name(&next_phase.description),
+                                           next_phase.id, start, lasts);
+            if is_valid_hex_color(&next_phase.color_hex[..]) {
+                task.push_str(&format!(" and is colored in {}", next_phase.color_hex));
+            }
+
+            builder.append(format!("{}\n", task));
+        }
+    }
+
+    builder.append("@endgantt\n");
+
+    builder.string().unwrap()
+}
+
+/// Replace the characters that would otherwise end a task name in PlantUML.
+fn get_plantuml_task_name(name: &str) -> String {
+    name.replace('[', "(")
+        .replace(']', ")")
+        .replace('\n', " ")
+}
@@ -0,0 +1,88 @@
+use chrono::{Duration, NaiveDateTime};
+
+/// The identifier of the product that created an iCalendar document, as given in its `PRODID`.
+pub const PRODUCT_ID: &str = "-//FoamFactory//chronogrog//EN";
+
+/// The longest a content line may be, in octets, before it must be folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escape a `TEXT` value (RFC 5545, section 3.3.11), so that backslashes, semicolons, commas and
+/// newlines within it aren't mistaken for delimiters.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for next_char in text.chars() {
+        match next_char {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            _ => escaped.push(next_char)
+        }
+    }
+
+    escaped
+}
+
+/// Retrieve a content line, terminated by a CRLF, folded (RFC 5545, section 3.1) so that no line is
+/// longer than 75 octets. Each continuation line starts with a single space, and lines are only
+/// split between characters, never within one.
+pub fn get_folded_line(line: &str) -> String {
+    let mut folded: String = String::with_capacity(line.len() + 2);
+    let mut octets: usize = 0;
+    for next_char in line.chars() {
+        if octets + next_char.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+
+        folded.push(next_char);
+        octets += next_char.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+
+    folded
+}
+
+/// Format a [NaiveDateTime](chrono::NaiveDateTime) as a `DATE-TIME` value in local ("floating")
+/// time, e.g. `20200101T090000`, since the dates of a schedule have no time zone.
+pub fn format_date_time(date: NaiveDateTime) -> String {
+    date.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Format a [Duration](chrono::Duration) as a `DURATION` value, e.g. `P10D`, `P1DT12H` or
+/// `-PT30M`. Durations are given in days, hours, minutes and seconds, rather than weeks, and
+/// sub-second parts are dropped.
+pub fn format_duration(duration: Duration) -> String {
+    let sign: &str = match duration < Duration::zero() {
+        true => "-",
+        false => ""
+    };
+
+    let seconds: i64 = duration.num_seconds().abs();
+    let days: i64 = seconds / 86400;
+    let hours: i64 = seconds % 86400 / 3600;
+    let minutes: i64 = seconds % 3600 / 60;
+    let remaining_seconds: i64 = seconds % 60;
+
+    if seconds == 0 {
+        return String::from("PT0S");
+    }
+
+    let mut formatted: String = format!("{}P", sign);
+    if days > 0 {
+        formatted.push_str(&format!("{}D", days));
+    }
+
+    if hours > 0 || minutes > 0 || remaining_seconds > 0 {
+        formatted.push('T');
+        for (amount, designator) in [(hours, 'H'), (minutes, 'M'), (remaining_seconds, 'S')].iter() {
+            if *amount > 0 {
+                formatted.push_str(&format!("{}{}", amount, designator));
+            }
+        }
+    }
+
+    formatted
+}
@@ -14,22 +14,73 @@ use serde::{Deserialize, Serialize};
 extern crate string_builder;
 use string_builder::Builder;
 
+pub mod bottlenecks;
+use bottlenecks::{rank_bottlenecks, Bottleneck, DelayEvent};
+
+pub mod capacity;
+use capacity::ResourceDelta;
+
+pub mod calendar;
+use calendar::{BlackoutSpec, WorkingCalendarSpec};
+
+pub mod dependencies;
+
+pub mod error;
+
+pub mod export;
+use export::ScheduleExport;
+pub use error::Error;
+
+pub mod gantt;
+
+pub mod ical;
+
 pub mod util;
 use util::get_naive_date_time_from_string;
 
 pub mod resources;
-use resources::Resource;
+use resources::{Allocation, AllocationHolder, Resource};
 use resources::ResourceTracker;
 use resources::ResourceType;
+use resources::SelectionPolicy;
+use resources::TurnaroundSpec;
 
 pub mod phases;
-use phases::PhaseInstance;
-use phases::ProductionPhaseTemplate;
+use phases::{PhaseInstance, ProductionPhaseTemplate};
 
 pub mod recipes;
 use recipes::RecipeSpec;
 use recipes::Recipe;
 
+pub mod optimizer;
+use optimizer::{Objective, OptimizerConfig, Optimizing};
+
+pub mod scheduler;
+use scheduler::{Scheduler, SchedulerKind};
+
+pub mod svg;
+use svg::ResourceLane;
+
+pub mod utilization;
+use utilization::UtilizationReport;
+
+pub mod validation;
+
+pub mod volume;
+
+/// The color in which periods during which resources are unavailable are shown, in every format.
+pub(crate) const UNAVAILABLE_COLOR: &str = "#808080";
+
+/// The color in which the turnarounds of resources are shown.
+const TURNAROUND_COLOR: &str = "#B0B0B0";
+
+/// The color of the parent tasks of the resource view.
+const RESOURCE_COLOR: &str = "#4682B4";
+
+/// The format in which dates are shown, to the minute, in charts and tables meant to be read,
+/// rather than parsed.
+pub(crate) const DISPLAY_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
 #[derive(Serialize, Deserialize)]
 /// Configuration options for the timeline of the production schedule.
 ///
@@ -41,7 +92,46 @@ use recipes::Recipe;
 ///
 pub struct ProductionTimeline {
     pub configuration: String,
-    start: String
+    start: String,
+
+    /// The strategy used to schedule the recipes. Defaults to
+    /// [GreedySequential](scheduler::GreedySequential).
+    #[serde(default)]
+    pub scheduler: SchedulerKind,
+
+    /// The configuration of the [Optimizing](optimizer::Optimizing) scheduler, used only if it is
+    /// the selected `scheduler`.
+    #[serde(default)]
+    pub optimizer: OptimizerConfig,
+
+    /// The way in which a resource is chosen when more than one is free for a phase. Defaults to
+    /// [BestFit](resources::SelectionPolicy::BestFit).
+    #[serde(rename="resourceSelection", default)]
+    pub resource_selection: SelectionPolicy,
+
+    /// The earliest date at which any phase of any recipe may start. A recipe may give a later
+    /// `notBefore` date of its own.
+    #[serde(rename="notBefore", default)]
+    pub not_before_string: Option<String>,
+
+    /// The latest date at which the first phase of any recipe may start. A recipe may give an
+    /// earlier `notAfter` date of its own.
+    #[serde(rename="notAfter", default)]
+    pub not_after_string: Option<String>,
+
+    /// Periods during which no phase of any recipe may start.
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutSpec>,
+
+    /// The times at which people are at work, and so at which hands-on phases may start. If not
+    /// given, hands-on phases may start at any time.
+    #[serde(rename="workingCalendar", default)]
+    pub working_calendar: Option<WorkingCalendarSpec>,
+
+    /// Whether the turnaround after each use of a resource should be shown as a task of its own
+    /// in PLA output.
+    #[serde(rename="showTurnarounds", default)]
+    pub show_turnarounds: bool
 }
 
 impl ProductionTimeline {
@@ -55,6 +145,72 @@ impl ProductionTimeline {
     pub fn start_date(&self) -> std::result::Result<NaiveDateTime, ParseError> {
         get_naive_date_time_from_string(&self.start[..])
     }
+
+    /// Retrieve the [Scheduler](scheduler::Scheduler) selected by this timeline, configured as
+    /// given in this timeline.
+    pub fn get_scheduler(&self) -> Box<dyn Scheduler> {
+        match self.scheduler {
+            SchedulerKind::Optimizing => Box::new(Optimizing::new(self.optimizer.clone())),
+            kind => kind.get_scheduler()
+        }
+    }
+}
+
+/// The format in which a scheduled [ProductionSchedule](ProductionSchedule) is written.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutputFormat {
+    /// The format of the PLA tool. See
+    /// [get_string_in_pla_format](ProductionSchedule::get_string_in_pla_format).
+    Pla,
+
+    /// An iCalendar (RFC 5545) document, with an event per phase. See
+    /// [get_string_in_ics_format](ProductionSchedule::get_string_in_ics_format).
+    Ics,
+
+    /// A Mermaid `gantt` block. See
+    /// [get_string_in_mermaid_format](ProductionSchedule::get_string_in_mermaid_format).
+    Mermaid,
+
+    /// A PlantUML `@startgantt` document. See
+    /// [get_string_in_plantuml_format](ProductionSchedule::get_string_in_plantuml_format).
+    PlantUml,
+
+    /// A standalone SVG Gantt chart. See
+    /// [get_string_in_svg_format](ProductionSchedule::get_string_in_svg_format).
+    Svg,
+
+    /// A versioned, machine-readable JSON record of the schedule. See
+    /// [get_string_in_json_format](ProductionSchedule::get_string_in_json_format).
+    Json
+}
+
+impl OutputFormat {
+    /// The names of every `OutputFormat`, as given on the command line.
+    pub const NAMES: [&'static str; 6] = ["pla", "ics", "mermaid", "plantuml", "svg", "json"];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            OutputFormat::Pla => "pla",
+            OutputFormat::Ics => "ics",
+            OutputFormat::Mermaid => "mermaid",
+            OutputFormat::PlantUml => "plantuml",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Json => "json"
+        }
+    }
+
+    /// Retrieve the `OutputFormat` with a given name, if there is one.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "pla" => Some(OutputFormat::Pla),
+            "ics" => Some(OutputFormat::Ics),
+            "mermaid" => Some(OutputFormat::Mermaid),
+            "plantuml" => Some(OutputFormat::PlantUml),
+            "svg" => Some(OutputFormat::Svg),
+            "json" => Some(OutputFormat::Json),
+            _ => None
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,38 +227,127 @@ pub struct ProductionSchedule {
     //            ResourceTracker instance.
     resources: Vec<Resource>,
 
+    /// The turnaround of every `Resource` of a `ResourceType`, for resources that don't give a
+    /// `turnaround` of their own.
+    #[serde(default)]
+    pub turnarounds: Vec<TurnaroundSpec>,
+
     #[serde(skip_serializing, skip_deserializing)]
     recipes: Vec<Recipe>,
 
     #[serde(rename="recipes")]
     pub recipe_specs: Vec<RecipeSpec>,
 
-    #[serde(skip_serializing, skip_deserializing)]
-    last_id_used: usize,
-
     #[serde(skip_serializing, skip_deserializing, default = "ResourceTracker::new")]
     tracker: ResourceTracker
 }
 
 impl ProductionSchedule {
+    /// Create a new `ProductionSchedule` from a string containing BPD-formatted JSON.
+    ///
+    /// # Panics
+    /// If the JSON cannot be parsed, or the schedule cannot be constructed from it. Use
+    /// [try_new](ProductionSchedule::try_new) to handle these cases without panicking.
     pub fn new(json_data: &str) -> Self {
-        let result: serde_json::Result<ProductionSchedule> = serde_json::from_str(json_data);
-        match result {
-            Ok(mut x) => {
-                x.init();
-                x
-            },
+        match ProductionSchedule::try_new(json_data) {
+            Ok(x) => x,
             Err(e) => {
                 panic!("Unable to parse due to: {}", e);
             }
         }
     }
 
-    pub fn init(&mut self) {
-        self.last_id_used = 0;
-        self.track_resources();
+    /// Try to create a new `ProductionSchedule` from a string containing BPD-formatted JSON.
+    ///
+    /// # Arguments
+    /// - `json_data`: A string slice containing the JSON representation of the schedule.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the fully scheduled `ProductionSchedule`, or an
+    ///   [Error](error::Error) describing why it could not be constructed.
+    pub fn try_new(json_data: &str) -> std::result::Result<Self, Error> {
+        let mut schedule: ProductionSchedule = serde_json::from_str(json_data)?;
+        schedule.init()?;
+
+        Ok(schedule)
+    }
+
+    /// Try to create a new `ProductionSchedule` from a string containing BPD-formatted JSON,
+    /// scheduling it with a given [SchedulerKind](scheduler::SchedulerKind), rather than the one
+    /// given in the `timeline` block.
+    ///
+    /// # Arguments
+    /// - `json_data`: A string slice containing the JSON representation of the schedule.
+    /// - `scheduler`: The `SchedulerKind` to schedule the recipes with.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the fully scheduled `ProductionSchedule`, or an
+    ///   [Error](error::Error) describing why it could not be constructed.
+    pub fn try_new_with_scheduler(json_data: &str,
+                                  scheduler: SchedulerKind) -> std::result::Result<Self, Error> {
+        let mut schedule: ProductionSchedule = serde_json::from_str(json_data)?;
+        schedule.timeline.scheduler = scheduler;
+        schedule.init()?;
+
+        Ok(schedule)
+    }
+
+    /// Initialize this `ProductionSchedule` after deserialization, tracking all resources and
+    /// scheduling every recipe.
+    ///
+    /// # Returns
+    /// - An empty `Result` if scheduling succeeded, or an [Error](error::Error) describing the
+    ///   first recipe phase that could not be scheduled.
+    pub fn init(&mut self) -> std::result::Result<(), Error> {
+        self.tracker = ResourceTracker::with_selection_policy(self.timeline.resource_selection);
+        self.track_resources()?;
         self.verify_recipe_start_dates();
-        self.rebuild_recipes_from_specs();
+        self.apply_timeline_windows();
+
+        let scheduler: Box<dyn Scheduler> = self.timeline.get_scheduler();
+        self.recipes = scheduler.schedule(&self.recipe_specs, &self.phase_templates,
+                                          &mut self.tracker)?;
+
+        Ok(())
+    }
+
+    /// Apply a set of hypothetical [ResourceDelta](capacity::ResourceDelta)s to the resources of
+    /// this `ProductionSchedule`, as given in its BPD document.
+    ///
+    /// # Notes
+    /// Only the resources as deserialized are changed, so this must be called before
+    /// [init](ProductionSchedule::init), which tracks them.
+    ///
+    /// # Returns
+    /// - An empty `Result` if every delta could be applied, or an [Error](error::Error) if a
+    ///   delta removes a resource that doesn't exist.
+    pub fn apply_resource_deltas(&mut self, deltas: &[ResourceDelta])
+      -> std::result::Result<(), Error> {
+        for next_delta in deltas {
+            match next_delta {
+                ResourceDelta::Add { resource_type, name, capacity, count } => {
+                    for index in 0..*count {
+                        let next_id: usize = self.resources.iter().map(|x| x.id).max().unwrap_or(0) + 1;
+                        let next_name: String = match name {
+                            Some(x) if *count == 1 => x.clone(),
+                            Some(x) => format!("{} {}", x, index + 1),
+                            None => format!("Extra {} {}", resource_type, index + 1)
+                        };
+
+                        self.resources.push(Resource::new(next_id, &next_name[..],
+                                                          resource_type.clone(), &capacity[..]));
+                    }
+                },
+                ResourceDelta::Remove { id } => {
+                    match self.resources.iter().position(|x| x.id == *id) {
+                        Some(index) => { self.resources.remove(index); },
+                        None => return Err(Error::UnknownResource { id: *id })
+                    }
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn resources(&self) -> Vec<Resource> {
@@ -119,25 +364,52 @@ impl ProductionSchedule {
         None
     }
 
+    /// Retrieve the `Resource` with a given `id`, as tracked while scheduling, so that its
+    /// `allocated_periods` include every phase it was allocated to.
     pub fn get_resource_by_id(&self, id: usize) -> Option<Resource> {
-        for next_res in &self.resources {
-            if next_res.id == id {
-                return Some(next_res.clone());
-            }
-        }
+        self.tracker.get_resource(id)
+    }
 
-        None
+    /// Retrieve a `Resource` of a specific `ResourceType` that is free at a given date.
+    ///
+    /// # Arguments
+    /// - `resource_type`: The `ResourceType` of the `Resource`.
+    /// - `date`: The [NaiveDateTime](chrono::NaiveDateTime) at which the `Resource` must be free.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Resource` of type `resource_type` with the minimum `id` that
+    ///   is free at `date`, or `None` if every one of them is allocated then.
+    pub fn get_available_resource_by_type(&self, resource_type: ResourceType,
+                                          date: NaiveDateTime) -> Option<Resource> {
+        self.tracker.get_resources_free_at(date)
+                    .into_iter()
+                    .find(|x| x.resource_type == resource_type)
     }
 
-    pub fn get_available_resource_by_type(&self, resource_type: ResourceType) -> Option<Resource> {
-        let resources: Vec<Resource> = self.resources.clone();
-        if resources.iter().any(|x| x.resource_type == resource_type) {
-            return resources.into_iter()
-                            .filter(|x| x.resource_type == resource_type)
-                            .next();
-        }
+    /// Retrieve every `Resource` that is free at a given date. See
+    /// [get_resources_free_at](resources::ResourceTracker::get_resources_free_at).
+    pub fn get_resources_free_at(&self, date: NaiveDateTime) -> Vec<Resource> {
+        self.tracker.get_resources_free_at(date)
+    }
 
-        None
+    /// Retrieve every `Resource` that is free over a given period. See
+    /// [get_resources_free_over_period](resources::ResourceTracker::get_resources_free_over_period).
+    pub fn get_resources_free_over_period(&self, period: NaivePeriod) -> Vec<Resource> {
+        self.tracker.get_resources_free_over_period(period)
+    }
+
+    /// Retrieve what holds the `Resource` with a given `id` at a given date, if anything. See
+    /// [get_allocation_of_resource_at](resources::ResourceTracker::get_allocation_of_resource_at).
+    pub fn get_allocation_of_resource_at(&self, id: usize,
+                                         date: NaiveDateTime) -> Option<Allocation> {
+        self.tracker.get_allocation_of_resource_at(id, date)
+    }
+
+    /// Retrieve every allocation of the `Resource` with a given `id`, along with the phase and
+    /// recipe holding each one. See
+    /// [get_allocations_of_resource](resources::ResourceTracker::get_allocations_of_resource).
+    pub fn get_allocations_of_resource(&self, id: usize) -> Vec<Allocation> {
+        self.tracker.get_allocations_of_resource(id)
     }
 
     // pub fn get_recipe_by_id(&mut self, id: usize) -> Option<Recipe> {
@@ -171,13 +443,159 @@ impl ProductionSchedule {
         None
     }
 
+    /// Evaluate an [Objective](optimizer::Objective) for the scheduled recipes.
+    ///
+    /// # Returns
+    /// - The value of the objective, in hours, where lower is better.
+    pub fn get_objective_value(&self, objective: Objective) -> f64 {
+        objective.evaluate(&self.recipes, &self.resources())
+    }
+
+    /// Retrieve every time a phase had to wait for resources while the recipes were scheduled,
+    /// in the order it happened.
+    pub fn get_delay_events(&self) -> Vec<DelayEvent> {
+        self.tracker.get_delays().to_vec()
+    }
+
+    /// Rank the `ResourceType`s that delayed phases by the total delay each caused, so that the
+    /// most limiting equipment comes first. See
+    /// [rank_bottlenecks](bottlenecks::rank_bottlenecks).
+    pub fn get_bottlenecks(&self) -> Vec<Bottleneck> {
+        rank_bottlenecks(self.tracker.get_delays())
+    }
+
+    /// Retrieve a [UtilizationReport](utilization::UtilizationReport) of how busy each `Resource`
+    /// is, from the start of the timeline to the end of the last phase.
+    pub fn get_utilization_report(&self) -> UtilizationReport {
+        UtilizationReport::new(&self.resources(), self.get_timeline_period())
+    }
+
+    /// Retrieve the period covered by the scheduled recipes: from the start of the timeline (or
+    /// of the first phase, if the timeline's start can't be parsed) to the end of the last phase.
+    fn get_timeline_period(&self) -> NaivePeriod {
+        let phases: Vec<&PhaseInstance> = self.recipes.iter()
+                                              .flat_map(|recipe| recipe.get_phase_iterator())
+                                              .collect();
+
+        let first_start: Option<NaiveDateTime> = phases.iter().map(|phase| phase.start_date).min();
+        let start: NaiveDateTime = match (self.timeline.start_date(), first_start) {
+            (Ok(x), _) => x,
+            (Err(_e), Some(x)) => x,
+            (Err(_e), None) => NaiveDateTime::from_timestamp(0, 0)
+        };
+
+        let end: NaiveDateTime = phases.iter()
+                                       .map(|phase| phase.start_date + phase.duration)
+                                       .max()
+                                       .map_or(start, |x| x.max(start));
+
+        NaivePeriod::new(start, end)
+    }
+
     pub fn get_recipe_iterator(&self) -> std::slice::Iter<Recipe> {
         self.recipes.iter()
     }
 
+    /// Retrieve the periods during which resources are unavailable, as the phases of a single
+    /// `Recipe`, so that they can be shown as blocked time on those resources.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Recipe`, whose ids follow on from those of the scheduled
+    ///   recipes and their phases, or `None` if no resource has an unavailable period.
+    pub fn get_unavailable_periods_as_recipe(&self) -> Option<Recipe> {
+        let mut resources: Vec<Resource> = self.resources();
+        resources.sort_by_key(|resource| resource.id);
+
+        let recipe_id: usize = get_last_id_used(&self.recipes) + 1;
+        let mut last_id_used: usize = recipe_id;
+
+        let mut phases: Vec<PhaseInstance> = vec![];
+        for next_resource in &resources {
+            for next_unavailable in &next_resource.unavailable_periods {
+                let period = match next_unavailable.period() {
+                    Ok(x) => x,
+                    Err(_e) => continue
+                };
+
+                let description: String = match &next_unavailable.reason {
+                    Some(reason) => format!("{} unavailable: {}", next_resource.name, reason),
+                    None => format!("{} unavailable", next_resource.name)
+                };
+
+                last_id_used += 1;
+                phases.push(PhaseInstance::new(last_id_used, description,
+                                               UNAVAILABLE_COLOR.to_string(), period.duration(),
+                                               period.start, vec![next_resource.clone()]));
+            }
+        }
+
+        let start_date: NaiveDateTime = phases.iter().map(|phase| phase.start_date).min()?;
+
+        Some(Recipe {
+            id: recipe_id,
+            name: String::from("Resource Downtime"),
+            color: UNAVAILABLE_COLOR.to_string(),
+            phases,
+            start_date,
+            batch_size: None
+        })
+    }
+
+    /// Retrieve the turnaround after each use of a resource by a scheduled phase, as the phases of
+    /// a single `Recipe`, so that they can be shown as tasks of their own.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Recipe`, whose ids follow on from those of the scheduled
+    ///   recipes, their phases, and the unavailable periods of resources, or `None` if no
+    ///   resource used by a phase has a turnaround.
+    pub fn get_turnarounds_as_recipe(&self) -> Option<Recipe> {
+        let mut previous_recipes: Vec<Recipe> = self.recipes.clone();
+        previous_recipes.extend(self.get_unavailable_periods_as_recipe());
+
+        let recipe_id: usize = get_last_id_used(&previous_recipes) + 1;
+        let mut last_id_used: usize = recipe_id;
+
+        let mut phases: Vec<PhaseInstance> = vec![];
+        for next_recipe in self.get_recipe_iterator() {
+            for next_phase in next_recipe.get_phase_iterator() {
+                for next_resource in &next_phase.resources_used {
+                    let turnaround: Duration = match next_resource.turnaround() {
+                        Some(x) if x > Duration::zero() => x,
+                        _ => continue
+                    };
+
+                    last_id_used += 1;
+                    phases.push(PhaseInstance::new(last_id_used,
+                                                   format!("{} turnaround", next_resource.name),
+                                                   TURNAROUND_COLOR.to_string(), turnaround,
+                                                   next_phase.start_date + next_phase.duration,
+                                                   vec![next_resource.clone()]));
+                }
+            }
+        }
+
+        let start_date: NaiveDateTime = phases.iter().map(|phase| phase.start_date).min()?;
+
+        Some(Recipe {
+            id: recipe_id,
+            name: String::from("Resource Turnaround"),
+            color: TURNAROUND_COLOR.to_string(),
+            phases,
+            start_date,
+            batch_size: None
+        })
+    }
+
     pub fn get_string_in_pla_format(&self) -> String {
         let mut builder = Builder::default();
-        for next_recipe in self.get_recipe_iterator() {
+        let unavailable: Option<Recipe> = self.get_unavailable_periods_as_recipe();
+        let turnarounds: Option<Recipe> = match self.timeline.show_turnarounds {
+            true => self.get_turnarounds_as_recipe(),
+            false => None
+        };
+
+        for next_recipe in self.get_recipe_iterator().chain(unavailable.iter())
+                                                     .chain(turnarounds.iter()) {
             builder.append(next_recipe.get_string_in_pla_format(1));
 
             for next_phase in next_recipe.get_phase_iterator() {
@@ -187,8 +605,9 @@ impl ProductionSchedule {
 
         let final_pla: String = builder.string().unwrap();
 
-        // Remove the last newline at the end of the file, as it's unnecessary
-        final_pla[..final_pla.len() - 1].to_string()
+        // Remove the last newline at the end of the file, as it's unnecessary. An empty schedule
+        // has none.
+        final_pla.strip_suffix('\n').unwrap_or(&final_pla).to_string()
     }
 
     pub fn write_pla_file(&self, mut output_stream: Box<dyn Write>) -> std::io::Result<()> {
@@ -197,188 +616,300 @@ impl ProductionSchedule {
         output_stream.write_all(pla_data.as_bytes())
     }
 
-    fn get_next_id(&mut self) -> usize {
-        self.last_id_used += 1;
-
-        self.last_id_used
-    }
-
-    fn verify_recipe_start_dates(&mut self) {
-        let mut new_recipe_vec: Vec<RecipeSpec> = vec![];
-
-        self.recipe_specs.clone().into_iter().for_each(|recipe_spec| {
-            match recipe_spec.start_string.clone() {
-                Some(_x) => new_recipe_vec.push(recipe_spec),
-                None => {
-                    let new_recipe_spec = RecipeSpec {
-                        name: recipe_spec.name,
-                        color_hex: recipe_spec.color_hex,
-                        phase_specs: recipe_spec.phase_specs,
-                        start_string: Some(self.timeline.start.clone())
-                    };
+    /// Retrieve what occupies each resource, as a `Recipe` per resource, so that the schedule can
+    /// be viewed by resource, rather than by recipe.
+    ///
+    /// # Returns
+    /// - A `Vec` containing a `Recipe` for every tracked `Resource`, in order of id, named after
+    ///   the `Resource`. Its phases are copies of the scheduled phases allocated to the `Resource`,
+    ///   named after their recipe, followed by the periods during which the `Resource` is
+    ///   unavailable, all in order of start date. A `Resource` that is never used has no phases.
+    ///   The ids of the recipes and phases are numbered from 1, independent of those of the
+    ///   scheduled recipes, since a phase using several resources appears once for each of them.
+    pub fn get_resources_as_recipes(&self) -> Vec<Recipe> {
+        let mut resources: Vec<Resource> = self.resources();
+        resources.sort_by_key(|resource| resource.id);
+
+        let start_date: NaiveDateTime = self.get_timeline_period().start;
+        let mut last_id_used: usize = 0;
+
+        let mut recipes: Vec<Recipe> = vec![];
+        for next_resource in resources {
+            last_id_used += 1;
+            let recipe_id: usize = last_id_used;
+
+            let mut allocations: Vec<Allocation> = self.get_allocations_of_resource(next_resource.id);
+            allocations.sort_by_key(|allocation| allocation.period.start);
+
+            let mut phases: Vec<PhaseInstance> = vec![];
+            for next_allocation in allocations {
+                let (description, color_hex, duration, start) = match &next_allocation.holder {
+                    AllocationHolder::Phase { recipe, phase_id, description } => {
+                        let phase: &PhaseInstance = match self.recipes.iter()
+                          .flat_map(|recipe| recipe.get_phase_iterator())
+                          .find(|phase| phase.id == *phase_id) {
+                            Some(x) => x,
+                            None => continue
+                        };
+
+                        (format!("{}: {}", recipe, description), phase.color_hex.clone(),
+                         phase.duration, phase.start_date)
+                    },
+                    AllocationHolder::Unavailable { reason } => {
+                        let description: String = match reason {
+                            Some(x) => format!("Unavailable: {}", x),
+                            None => String::from("Unavailable")
+                        };
+
+                        (description, UNAVAILABLE_COLOR.to_string(),
+                         next_allocation.period.duration(), next_allocation.period.start)
+                    },
+                    AllocationHolder::Unknown => continue
+                };
 
-                    new_recipe_vec.push(new_recipe_spec);
-                }
+                last_id_used += 1;
+                phases.push(PhaseInstance::new(last_id_used, description, color_hex, duration,
+                                               start, vec![next_resource.clone()]));
             }
 
-            self.recipe_specs = new_recipe_vec.clone();
-        })
+            recipes.push(Recipe {
+                id: recipe_id,
+                name: next_resource.name.clone(),
+                color: RESOURCE_COLOR.to_string(),
+                start_date: phases.first().map_or(start_date, |phase| phase.start_date),
+                phases,
+                batch_size: None
+            });
+        }
+
+        recipes
     }
 
-    fn rebuild_recipes_from_specs(&mut self) {
-        let mut recipes_vec = vec![];
-        let recipes = self.recipe_specs.clone();
-        for next_recipe_spec in recipes {
-            let recipe_start_date: NaiveDateTime = match next_recipe_spec.start_date() {
-                Ok(x) => x,
+    /// Retrieve a `String` representing the schedule in the format of the PLA tool, organized by
+    /// resource: a parent task for each `Resource`, whose children are the phases occupying it, in
+    /// time order. See [get_resources_as_recipes](ProductionSchedule::get_resources_as_recipes).
+    pub fn get_resource_view_in_pla_format(&self) -> String {
+        let mut builder = Builder::default();
 
-                // If the next line causes an error, there's no telling what we can do...
-                Err(_e) => self.timeline.start_date().unwrap()
-            };
+        for next_recipe in self.get_resources_as_recipes() {
+            builder.append(next_recipe.get_string_in_pla_format(1));
 
-            let mut recipe_template: Recipe = Recipe {
-                id: self.get_next_id(),
-                name: next_recipe_spec.name.clone(),
-                color: next_recipe_spec.color_hex.clone(),
-                phases: vec![],
-                start_date: recipe_start_date
-            };
+            for next_phase in next_recipe.get_phase_iterator() {
+                builder.append(next_phase.get_string_in_pla_format(1));
+            }
+        }
 
-            recipe_template.phases = self.rebuild_phases_from_specs(&next_recipe_spec);
+        let final_pla: String = builder.string().unwrap();
 
-            recipes_vec.push(recipe_template);
-        }
+        // Remove the last newline at the end of the file, as it's unnecessary. An empty schedule
+        // has none.
+        final_pla.strip_suffix('\n').unwrap_or(&final_pla).to_string()
+    }
+
+    pub fn write_resource_view_pla_file(&self, mut output_stream: Box<dyn Write>) -> std::io::Result<()> {
+        let pla_data = self.get_resource_view_in_pla_format();
 
-        self.recipes = recipes_vec;
+        output_stream.write_all(pla_data.as_bytes())
     }
 
-    /// Rebuild all `PhaseInstance's from a set of [RecipeSpec](chronogrog::recipes::RecipeSpec)
-    /// and  a [ProductionPhaseTemplate](chrono::phases::ProductionPhaseTemplate).
-    ///
-    /// This places all the necessary data into the data structures necessary to convert the
-    /// JSON specification to a PLA formatted file.
+    /// Retrieve a `String` representing the scheduled recipes as an iCalendar (RFC 5545) document,
+    /// suitable for importing into a calendar application.
     ///
     /// # Arguments
-    /// - `self` : A _mutable_ reference to this
-    ///   [ProductionSchedule](chronogrog::ProductionSchedule). It needs to be mutable because
-    ///   internal references are updated as part of this function call.
-    /// - `recipe_spec` : A borrowed reference to a [RecipeSpec](chronogrog::recipes::RecipeSpec)
-    ///    defining the containing recipe specification that will be used to construct the
-    ///    instances of production phases.
+    /// - `reminder`: How long before each hands-on phase a reminder should go off, or `None` for
+    ///   no reminders.
+    /// - `timestamp`: The [NaiveDateTime](chrono::NaiveDateTime), in UTC, at which the document was
+    ///   created, which is given as the `DTSTAMP` of every event.
     ///
     /// # Returns
-    /// - A `Vec` of `PhaseInstance` objects.
-    fn rebuild_phases_from_specs(&mut self, recipe_spec: &RecipeSpec) -> Vec<PhaseInstance> {
-        let mut phases: Vec<PhaseInstance> = vec![];
+    /// - A `String` containing a `VCALENDAR`, named after this `ProductionSchedule`, with a
+    ///   `VEVENT` for every phase of every recipe, in the order of the recipes. Each event gives
+    ///   the recipe, the phase, and the resources it uses. The periods during which resources are
+    ///   unavailable, and their turnarounds, aren't included.
+    pub fn get_string_in_ics_format(&self, reminder: Option<Duration>,
+                                    timestamp: NaiveDateTime) -> String {
+        let mut builder = Builder::default();
+        builder.append("BEGIN:VCALENDAR\r\n");
+        builder.append("VERSION:2.0\r\n");
+        builder.append(format!("PRODID:{}\r\n", ical::PRODUCT_ID));
+        builder.append("CALSCALE:GREGORIAN\r\n");
+        builder.append(ical::get_folded_line(&format!("X-WR-CALNAME:{}",
+                                                      ical::escape_text(&self.name))));
 
-        // The start date of the next phase
-        let mut next_start_date: NaiveDateTime = recipe_spec.start_date().unwrap();
+        for next_recipe in self.get_recipe_iterator() {
+            for next_phase in next_recipe.get_phase_iterator() {
+                builder.append(next_phase.get_string_in_ics_format(&next_recipe.name, self.id,
+                                                                   reminder, timestamp));
+            }
+        }
 
-        for next_spec in recipe_spec.phase_specs.iter() {
-            let id: usize = self.get_next_id();
+        builder.append("END:VCALENDAR\r\n");
 
-            // If the duration is specified in the spec, use that duration.
-            // Otherwise, use the default duration by looking up from the template.
-            let mut dur: Option<Duration> = next_spec.duration();
-            dur = match dur {
-                Some(x) => Some(x),
-                None => {
-                    let template: ProductionPhaseTemplate = self.get_phase_by_id(&next_spec.template[..]).unwrap();
-                    template.default_duration()
-                }
-            };
+        builder.string().unwrap()
+    }
 
-            // The production phase template we're going to use to construct this instance.
-            let template: ProductionPhaseTemplate = self.get_phase_by_id(&next_spec.template[..]).unwrap();
+    pub fn write_ics_file(&self, reminder: Option<Duration>, timestamp: NaiveDateTime,
+                          mut output_stream: Box<dyn Write>) -> std::io::Result<()> {
+        let ics_data = self.get_string_in_ics_format(reminder, timestamp);
 
-            // If the description is specified in the spec, use that description.
-            // Otherwise, use the description by looking up from the template.
-            let mut description: String = next_spec.description.clone();
+        output_stream.write_all(ics_data.as_bytes())
+    }
 
-            description = match description.is_empty()  {
-                true => {
-                    template.description.clone()
-                },
-                false => description
-            };
+    /// Retrieve a `String` representing the scheduled recipes as a Mermaid `gantt` block, titled
+    /// with the name of this `ProductionSchedule`. See
+    /// [get_string_in_mermaid_format](gantt::get_string_in_mermaid_format).
+    pub fn get_string_in_mermaid_format(&self) -> String {
+        gantt::get_string_in_mermaid_format(&self.name, &self.recipes)
+    }
 
-            let duration: Duration = match dur {
-                Some(x) => x,
+    /// Retrieve a `String` representing the scheduled recipes as a PlantUML `@startgantt`
+    /// document, titled with the name of this `ProductionSchedule`. See
+    /// [get_string_in_plantuml_format](gantt::get_string_in_plantuml_format).
+    pub fn get_string_in_plantuml_format(&self) -> String {
+        gantt::get_string_in_plantuml_format(&self.name, &self.recipes)
+    }
 
-                // Default to a single day if nothing else works
-                None => Duration::days(1)
-            };
+    /// Retrieve a `String` containing a standalone SVG Gantt chart of the scheduled recipes, titled
+    /// with the name of this `ProductionSchedule`, whose date axis runs from the start of the
+    /// timeline to the end of the last phase. See
+    /// [get_string_in_svg_format](svg::get_string_in_svg_format).
+    ///
+    /// # Arguments
+    /// - `show_resource_lanes`: Whether a second view should be drawn below the recipes, with a
+    ///   swimlane for each resource showing the periods over which it is allocated, including
+    ///   those during which it is unavailable.
+    pub fn get_string_in_svg_format(&self, show_resource_lanes: bool) -> String {
+        let lanes: Vec<ResourceLane> = match show_resource_lanes {
+            true => self.resources().into_iter().map(|resource| ResourceLane {
+                allocations: self.get_allocations_of_resource(resource.id),
+                resource
+            }).collect(),
+            false => vec![]
+        };
+
+        svg::get_string_in_svg_format(&self.name, &self.recipes, self.get_timeline_period(),
+                                      &lanes)
+    }
 
-            let mut resources_used : Vec<Resource> = vec![];
-            let mut result_start_date;
+    /// Retrieve a [ScheduleExport](export::ScheduleExport) of what the scheduler decided: each
+    /// scheduled recipe and phase, and every allocation of every resource.
+    pub fn get_schedule_export(&self) -> ScheduleExport {
+        let resources: Vec<(Resource, Vec<Allocation>)> = self.resources().into_iter().map(|resource| {
+            let allocations: Vec<Allocation> = self.get_allocations_of_resource(resource.id);
 
-            // We need to look through all the resources to determine if we have to push the start
-            // date back due to resource allocation constraints.
-            for next_resource_type in template.resources_needed.clone() {
-                let requested_start_date = next_start_date.clone();
+            (resource, allocations)
+        }).collect();
 
-                let requested_period = NaivePeriod::from_start_duration(requested_start_date,
-                                                                        duration);
+        ScheduleExport::new(self.id, &self.name, &self.recipes, &resources)
+    }
 
-                result_start_date = match self.tracker
-                  .get_next_available_resource_date_for_type_over_period(&next_resource_type,
-                                                                         requested_period) {
-                  Some(date) => date,
-                  None => panic!("{} {:?}, {} {:?}", "No resources of type", next_resource_type,
-                                 "which is required by phase", template.description.clone())
-                };
+    /// Retrieve a `String` containing the [ScheduleExport](export::ScheduleExport) of this
+    /// `ProductionSchedule` as pretty-printed JSON.
+    pub fn get_string_in_json_format(&self) -> String {
+        self.get_schedule_export().get_string_as_json()
+    }
+
+    fn verify_recipe_start_dates(&mut self) {
+        let mut new_recipe_vec: Vec<RecipeSpec> = vec![];
 
-                // If we can't allocate a resource in the given timeframe, we need to push back
-                // the start date of the phase.
-                if result_start_date.timestamp() > next_start_date.timestamp() {
-                    next_start_date = result_start_date;
+        self.recipe_specs.clone().into_iter().for_each(|recipe_spec| {
+            match recipe_spec.start_string.clone() {
+                Some(_x) => new_recipe_vec.push(recipe_spec),
+                None => {
+                    let new_recipe_spec = RecipeSpec {
+                        start_string: Some(self.timeline.start.clone()),
+                        ..recipe_spec
+                    };
+
+                    new_recipe_vec.push(new_recipe_spec);
                 }
             }
 
-            for next_resource_type in template.resources_needed {
-                // Allocate the resource
-                let allocation_period = NaivePeriod::from_start_duration(next_start_date,
-                                                                         duration);
+            self.recipe_specs = new_recipe_vec.clone();
+        })
+    }
 
-                let allocated_resource
-                  = self.tracker.allocate_resource_of_type_for_period(&next_resource_type,
-                                                                      allocation_period);
+    /// Apply the `notBefore`, `notAfter`, `blackouts` and `workingCalendar` of the timeline to
+    /// every recipe, so that each `RecipeSpec` carries every window it has to be scheduled within.
+    /// A recipe with a working calendar of its own keeps it.
+    fn apply_timeline_windows(&mut self) {
+        let timeline: &ProductionTimeline = &self.timeline;
+        for next_recipe_spec in self.recipe_specs.iter_mut() {
+            next_recipe_spec.not_before_string = get_tighter_date(&next_recipe_spec.not_before_string,
+                                                                  &timeline.not_before_string,
+                                                                  true);
+            next_recipe_spec.not_after_string = get_tighter_date(&next_recipe_spec.not_after_string,
+                                                                 &timeline.not_after_string,
+                                                                 false);
+            next_recipe_spec.blackouts.extend(timeline.blackouts.iter().cloned());
+
+            if next_recipe_spec.working_calendar.is_none() {
+                next_recipe_spec.working_calendar = timeline.working_calendar.clone();
+            }
+        }
+    }
 
-                if (allocated_resource).is_none() {
-                    panic!("Unable to allocate a resource of type {:?}", next_resource_type);
+    fn track_resources(&mut self) -> std::result::Result<(), Error> {
+        for next_resource in &self.resources {
+            for next_period in &next_resource.unavailable_periods {
+                if let Err(date) = next_period.period() {
+                    return Err(Error::UnparseableUnavailablePeriod {
+                        resource: next_resource.name.clone(),
+                        date
+                    });
                 }
-
-                // Put the allocated resource into the vector
-                resources_used.push(allocated_resource.unwrap().clone());
             }
-
-            phases.push(PhaseInstance::new(id, description, recipe_spec.color_hex.clone(),
-                                           duration, next_start_date, resources_used));
-            next_start_date = next_start_date + duration;
         }
 
-        // We have to actally run through the phases from the back and add the next phase id
-        // as a dependency to the previous phase id. This is a weird nuance of pla that tasks X
-        // that are dependent on some task Y are actually defined in the definition of Y, not X. It
-        // basically means you have to specify that there will be defined a task with id X, but
-        // that task hasn't been defined yet.
-        let mut phases_new : Vec<PhaseInstance> = vec![];
-        let mut prev_phase_id : usize = 0;
-        for next_phase in phases.iter().rev() {
-            let mut phase = next_phase.clone();
-            if prev_phase_id > 0 {
-                phase.add_dependency(prev_phase_id);
+        for next_resource in self.resources.iter_mut() {
+            if next_resource.turnaround_string.is_none() {
+                next_resource.turnaround_string = self.turnarounds.iter()
+                  .find(|turnaround| turnaround.resource_type == next_resource.resource_type)
+                  .map(|turnaround| turnaround.duration_string.clone());
             }
 
-            prev_phase_id = phase.id;
-
-            phases_new.push(phase);
+            if next_resource.turnaround().is_none() {
+                return Err(Error::UnparseableTurnaround {
+                    resource: next_resource.name.clone(),
+                    duration: next_resource.turnaround_string.clone().unwrap_or_default()
+                });
+            }
         }
 
-        phases_new.into_iter().rev().collect()
+        self.resources.clone().into_iter().for_each(|e| self.tracker.track_resource(e));
+
+        Ok(())
     }
+}
 
-    fn track_resources(&mut self) {
-        self.resources.clone().into_iter().for_each(|e| self.tracker.track_resource(e));
+/// Choose the tighter of two optional date strings: the later, if `is_later_tighter`, or the
+/// earlier, otherwise. A date that can't be parsed is always chosen, so that the error is reported
+/// when the recipe is scheduled.
+fn get_tighter_date(recipe_date: &Option<String>, timeline_date: &Option<String>,
+                    is_later_tighter: bool) -> Option<String> {
+    match (recipe_date, timeline_date) {
+        (Some(recipe_string), Some(timeline_string)) => {
+            match (get_naive_date_time_from_string(&recipe_string[..]),
+                   get_naive_date_time_from_string(&timeline_string[..])) {
+                (Ok(recipe), Ok(timeline)) if (timeline > recipe) == is_later_tighter => {
+                    Some(timeline_string.clone())
+                },
+                (Ok(_), Err(_)) => Some(timeline_string.clone()),
+                _ => Some(recipe_string.clone())
+            }
+        },
+        (None, date) | (date, None) => date.clone()
     }
 }
+
+/// Retrieve the largest id used by a set of `Recipe`s, or any of their phases.
+fn get_last_id_used(recipes: &[Recipe]) -> usize {
+    recipes.iter()
+           .flat_map(|recipe| {
+               recipe.get_phase_iterator()
+                     .map(|phase| phase.id)
+                     .chain(std::iter::once(recipe.id))
+           })
+           .max()
+           .unwrap_or(0)
+}
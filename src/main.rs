@@ -1,67 +1,297 @@
 use std::io::{BufReader, Read, Write};
 use std::fs::File;
 
+use chrono::{Duration, Utc};
+
 extern crate chronogrog;
-use chronogrog::ProductionSchedule;
+use chronogrog::{OutputFormat, ProductionSchedule};
+use chronogrog::capacity::{CapacityComparison, CapacityScenario};
+use chronogrog::scheduler::SchedulerKind;
+use chronogrog::util::convert_string_to_duration;
+use chronogrog::utilization::ReportFormat;
+use chronogrog::validation::{validate, Diagnostic};
+
+use std::fmt::Display;
+use std::process::exit;
 
 #[macro_use]
 extern crate clap;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
-fn main() {
-    let app_name = format!("{}", env!("CARGO_PKG_NAME"));
-    let app_description = format!("{}", env!("CARGO_PKG_DESCRIPTION"));
-    let authors = format!("{}", env!("CARGO_PKG_AUTHORS"));
+/// Print an error to standard error, and exit the process with a non-zero exit code.
+fn exit_with_error(error: impl Display) -> ! {
+    eprintln!("error: {}", error);
+    exit(1);
+}
 
-    let matches = App::new(app_name)
-      .version(crate_version!())
-      .about(&app_description[..])
-      .author(&authors[..])
-      .arg(
-            Arg::with_name("input")
-                .short("i")
-                .long("input")
-                .value_name("INPUT")
-                .help("Specify an input file to read from. Defaults to standard input.")
-                .takes_value(true),
-      )
-      .arg(
-            Arg::with_name("output")
-                .short("o")
-                .long("output")
-                .value_name("OUTPUT")
-                .help("Specify an output file to write to. Defaults to standard output.")
-                .takes_value(true),
-      )
-      .get_matches();
+fn get_input_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("input")
+        .short("i")
+        .long("input")
+        .value_name("INPUT")
+        .help("Specify an input file to read from. Defaults to standard input.")
+        .takes_value(true)
+}
+
+fn get_output_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output")
+        .short("o")
+        .long("output")
+        .value_name("OUTPUT")
+        .help("Specify an output file to write to. Defaults to standard output.")
+        .takes_value(true)
+}
+
+fn get_scheduler_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("scheduler")
+        .short("s")
+        .long("scheduler")
+        .value_name("SCHEDULER")
+        .help("Specify the strategy used to schedule recipes. Defaults to the scheduler given in the timeline block, or greedy-sequential.")
+        .possible_values(&SchedulerKind::NAMES)
+        .takes_value(true)
+}
 
+fn get_output_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("format")
+        .short("f")
+        .long("format")
+        .value_name("FORMAT")
+        .help("Specify the format of the schedule written. Defaults to pla.")
+        .possible_values(&OutputFormat::NAMES)
+        .takes_value(true)
+}
+
+fn get_reminder_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("reminder")
+        .long("reminder")
+        .value_name("DURATION")
+        .help("Specify how long before each hands-on phase a reminder should go off, e.g. 2h, when writing ics. Defaults to no reminders.")
+        .takes_value(true)
+}
+
+fn get_resource_lanes_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("resource-lanes")
+        .long("resource-lanes")
+        .help("Add a swimlane for each resource, showing the periods over which it is allocated, when writing svg.")
+}
+
+fn get_resource_view_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("resource-view")
+        .long("resource-view")
+        .help("Organize the output by resource, with the phases occupying each resource as its children, when writing pla.")
+}
+
+fn get_report_format_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("format")
+        .short("f")
+        .long("format")
+        .value_name("FORMAT")
+        .help("Specify the format of the report. Defaults to table.")
+        .possible_values(&ReportFormat::NAMES)
+        .takes_value(true)
+}
+
+/// Read the entire input, either from the file given by the `input` argument, or from standard
+/// input.
+fn read_input(matches: &ArgMatches) -> String {
     let input_file: Box<dyn Read> = match matches.value_of("input") {
         Some(in_file) => match File::open(in_file) {
                 Ok(f) => Box::new(f),
-                Err(e) => {
-                    panic!(format!("{}: {}", e, in_file));
-                }
+                Err(e) => exit_with_error(format!("{}: {}", e, in_file))
         },
         None => Box::new(std::io::stdin())
     };
 
     let mut buf_reader = BufReader::new(input_file);
     let mut json_data: String = String::new();
-    buf_reader.read_to_string(&mut json_data).unwrap();
+    if let Err(e) = buf_reader.read_to_string(&mut json_data) {
+        exit_with_error(e);
+    }
 
-    let production_schedule: ProductionSchedule = ProductionSchedule::new(&json_data[..]);
+    json_data
+}
 
-    let output_file: Box<dyn Write> = match matches.value_of("output") {
+/// Open the output, either the file given by the `output` argument, or standard output.
+fn open_output(matches: &ArgMatches) -> Box<dyn Write> {
+    match matches.value_of("output") {
         Some(out_file) => match File::create(out_file) {
             Ok(f) => Box::new(f),
-            Err(e) => panic!(format!("{}: {}", e, out_file))
+            Err(e) => exit_with_error(format!("{}: {}", e, out_file))
         },
         None => Box::new(std::io::stdout())
+    }
+}
+
+/// Read and schedule the input, using the scheduler given by the `scheduler` argument, if any.
+fn read_production_schedule(matches: &ArgMatches) -> ProductionSchedule {
+    let json_data = read_input(matches);
+
+    let result = match matches.value_of("scheduler").and_then(SchedulerKind::from_name) {
+        Some(scheduler) => ProductionSchedule::try_new_with_scheduler(&json_data[..], scheduler),
+        None => ProductionSchedule::try_new(&json_data[..])
     };
 
-    match production_schedule.write_pla_file(output_file) {
-        Ok(_x) => _x,
-        Err(e) => panic!("{}", e)
+    match result {
+        Ok(x) => x,
+        Err(e) => exit_with_error(e)
+    }
+}
+
+fn run_convert(matches: &ArgMatches) {
+    let reminder: Option<Duration> = matches.value_of("reminder").map(|reminder| {
+        match convert_string_to_duration(reminder) {
+            Some(x) => x,
+            None => exit_with_error(format!("unable to parse reminder '{}'", reminder))
+        }
+    });
+
+    let production_schedule: ProductionSchedule = read_production_schedule(matches);
+
+    let format: OutputFormat = matches.value_of("format")
+                                      .and_then(OutputFormat::from_name)
+                                      .unwrap_or(OutputFormat::Pla);
+    let result = match format {
+        OutputFormat::Pla => match matches.is_present("resource-view") {
+            true => production_schedule.write_resource_view_pla_file(open_output(matches)),
+            false => production_schedule.write_pla_file(open_output(matches))
+        },
+        OutputFormat::Ics => {
+            production_schedule.write_ics_file(reminder, Utc::now().naive_utc(),
+                                               open_output(matches))
+        },
+        OutputFormat::Mermaid => {
+            open_output(matches).write_all(production_schedule.get_string_in_mermaid_format()
+                                                              .as_bytes())
+        },
+        OutputFormat::PlantUml => {
+            open_output(matches).write_all(production_schedule.get_string_in_plantuml_format()
+                                                              .as_bytes())
+        },
+        OutputFormat::Svg => {
+            let show_resource_lanes: bool = matches.is_present("resource-lanes");
+            open_output(matches).write_all(production_schedule.get_string_in_svg_format(show_resource_lanes)
+                                                              .as_bytes())
+        },
+        OutputFormat::Json => {
+            open_output(matches).write_all(production_schedule.get_string_in_json_format()
+                                                              .as_bytes())
+        }
+    };
+
+    if let Err(e) = result {
+        exit_with_error(e);
+    }
+
+    // The output may be standard output, so the objective is reported separately.
+    if production_schedule.timeline.scheduler == SchedulerKind::Optimizing {
+        let objective = production_schedule.timeline.optimizer.objective;
+        eprintln!("{}: {:.2} hours", objective,
+                  production_schedule.get_objective_value(objective));
+    }
+}
+
+fn run_utilization(matches: &ArgMatches) {
+    let production_schedule: ProductionSchedule = read_production_schedule(matches);
+
+    let format: ReportFormat = matches.value_of("format")
+                                      .and_then(ReportFormat::from_name)
+                                      .unwrap_or(ReportFormat::Table);
+    let report: String = production_schedule.get_utilization_report().get_string_in_format(format);
+
+    if let Err(e) = open_output(matches).write_all(report.as_bytes()) {
+        exit_with_error(e);
+    }
+}
+
+fn run_capacity(matches: &ArgMatches) {
+    let json_data = read_input(matches);
+
+    // The scenarios file is required, so it's always given.
+    let scenarios_file: &str = matches.value_of("scenarios").unwrap();
+    let scenarios_data: String = match std::fs::read_to_string(scenarios_file) {
+        Ok(x) => x,
+        Err(e) => exit_with_error(format!("{}: {}", e, scenarios_file))
+    };
+
+    let scenarios: Vec<CapacityScenario> = match serde_json::from_str(&scenarios_data[..]) {
+        Ok(x) => x,
+        Err(e) => exit_with_error(format!("unable to parse scenarios: {}", e))
+    };
+
+    let scheduler = matches.value_of("scheduler").and_then(SchedulerKind::from_name);
+    let comparison = match CapacityComparison::new(&json_data[..], scheduler, &scenarios) {
+        Ok(x) => x,
+        Err(e) => exit_with_error(e)
+    };
+
+    if let Err(e) = open_output(matches).write_all(comparison.get_string_as_table().as_bytes()) {
+        exit_with_error(e);
+    }
+}
+
+fn run_check(matches: &ArgMatches) {
+    let json_data = read_input(matches);
+
+    let diagnostics: Vec<Diagnostic> = validate(&json_data[..]);
+    for next_diagnostic in &diagnostics {
+        println!("{}", next_diagnostic);
+    }
+
+    if diagnostics.iter().any(|d| d.is_error()) {
+        exit(1);
+    }
+}
+
+fn main() {
+    let app_name = env!("CARGO_PKG_NAME").to_string();
+    let app_description = env!("CARGO_PKG_DESCRIPTION").to_string();
+    let authors = env!("CARGO_PKG_AUTHORS").to_string();
+
+    let matches = App::new(app_name)
+      .version(crate_version!())
+      .about(&app_description[..])
+      .author(&authors[..])
+      .arg(get_input_arg())
+      .arg(get_output_arg())
+      .arg(get_scheduler_arg())
+      .arg(get_output_format_arg())
+      .arg(get_reminder_arg())
+      .arg(get_resource_lanes_arg())
+      .arg(get_resource_view_arg())
+      .subcommand(
+            SubCommand::with_name("check")
+                .about("Validate a BPD file, reporting every problem found, without scheduling it.")
+                .arg(get_input_arg()),
+      )
+      .subcommand(
+            SubCommand::with_name("utilization")
+                .about("Schedule a BPD file, and report how busy each resource is over the timeline.")
+                .arg(get_input_arg())
+                .arg(get_output_arg())
+                .arg(get_scheduler_arg())
+                .arg(get_report_format_arg()),
+      )
+      .subcommand(
+            SubCommand::with_name("capacity")
+                .about("Schedule a BPD file as given, and again with each of a set of hypothetical changes to its resources, comparing the makespan and the completion of each recipe.")
+                .arg(get_input_arg())
+                .arg(get_output_arg())
+                .arg(get_scheduler_arg())
+                .arg(Arg::with_name("scenarios")
+                        .long("scenarios")
+                        .value_name("SCENARIOS")
+                        .help("Specify a JSON file containing the scenarios to compare, each with a name and a list of resource deltas.")
+                        .required(true)
+                        .takes_value(true)),
+      )
+      .get_matches();
+
+    match matches.subcommand() {
+        ("check", Some(check_matches)) => run_check(check_matches),
+        ("utilization", Some(utilization_matches)) => run_utilization(utilization_matches),
+        ("capacity", Some(capacity_matches)) => run_capacity(capacity_matches),
+        _ => run_convert(&matches)
     }
 }
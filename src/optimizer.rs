@@ -0,0 +1,244 @@
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime};
+
+use serde::{Serialize, Deserialize};
+
+use super::error::Error;
+use super::phases::ProductionPhaseTemplate;
+use super::recipes::{Recipe, RecipeSpec};
+use super::resources::{Resource, ResourceTracker, ResourceType};
+use super::scheduler::{get_shortest_first_order, schedule_in_order, Scheduler};
+
+/// A measure of the quality of a schedule, where lower values are better.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all="kebab-case")]
+pub enum Objective {
+    /// The number of hours from the start of the earliest phase to the end of the latest phase.
+    #[default]
+    Makespan,
+
+    /// The total number of hours, across all fermentors, that a fermentor sits empty between the
+    /// start of the earliest phase and the end of the latest phase.
+    FermentorIdle
+}
+
+impl Objective {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Objective::Makespan => "makespan",
+            Objective::FermentorIdle => "fermentor-idle"
+        }
+    }
+
+    /// Evaluate this `Objective` for a set of scheduled `Recipe`s.
+    ///
+    /// # Arguments
+    /// - `recipes`: The scheduled `Recipe`s.
+    /// - `resources`: Every `Resource` that could have been allocated to the phases of the
+    ///   `Recipe`s, including those that weren't.
+    ///
+    /// # Returns
+    /// - The value of the objective, in hours. If there are no phases, this is `0.0`.
+    pub fn evaluate(&self, recipes: &[Recipe], resources: &[Resource]) -> f64 {
+        let phases: Vec<_> = recipes.iter().flat_map(|recipe| recipe.get_phase_iterator()).collect();
+
+        let first_start: NaiveDateTime = match phases.iter().map(|phase| phase.start_date).min() {
+            Some(x) => x,
+            None => return 0.0
+        };
+
+        let last_end: NaiveDateTime = phases.iter()
+                                            .map(|phase| phase.start_date + phase.duration)
+                                            .max()
+                                            .unwrap();
+        let makespan: Duration = last_end - first_start;
+
+        match self {
+            Objective::Makespan => get_hours(makespan),
+            Objective::FermentorIdle => {
+                resources.iter()
+                         .filter(|res| res.resource_type == ResourceType::Fermentor)
+                         .map(|res| {
+                             let busy = phases.iter()
+                                              .filter(|phase| phase.resources_used.iter()
+                                                                   .any(|used| used.id == res.id))
+                                              .fold(Duration::zero(), |total, phase| total + phase.duration);
+                             get_hours(makespan - busy)
+                         })
+                         .sum()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+fn get_hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+fn get_default_iterations() -> usize {
+    200
+}
+
+/// Configuration for the [Optimizing](Optimizing) scheduler, given as the `optimizer` entry of
+/// the `timeline` block of a BPD file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OptimizerConfig {
+    /// The `Objective` to minimize. Defaults to `makespan`.
+    #[serde(default)]
+    pub objective: Objective,
+
+    /// The seed for the random choices made while searching. The same seed always produces the
+    /// same schedule from the same input.
+    #[serde(default)]
+    pub seed: u64,
+
+    /// The number of candidate schedules to try, beyond the initial ones. This budget is counted
+    /// in candidates, rather than time, so that the result doesn't depend on the speed of the
+    /// machine.
+    #[serde(default="get_default_iterations")]
+    pub iterations: usize
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig {
+            objective: Objective::default(),
+            seed: 0,
+            iterations: get_default_iterations()
+        }
+    }
+}
+
+/// The result of an [Optimizing](Optimizing) scheduler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimizedSchedule {
+    /// The scheduled `Recipe`s, in the same order as the `RecipeSpec`s they were scheduled from.
+    pub recipes: Vec<Recipe>,
+
+    /// The value of the `Objective` achieved by `recipes`.
+    pub objective_value: f64
+}
+
+/// Schedules recipes so as to minimize an [Objective](Objective), by searching for the best order
+/// in which to schedule them.
+///
+/// Each candidate order is scheduled in the same way as by
+/// [GreedySequential](super::scheduler::GreedySequential), with recipes given earlier getting
+/// first choice of resources. The search starts from the order the recipes are given, and the
+/// order of shortest total duration first, then repeatedly moves a single recipe to another
+/// position in the best order found, keeping the result if it's at least as good. No recipe ever
+/// starts before its `start` date, and a recipe that depends upon a phase of another recipe is
+/// always scheduled after it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Optimizing {
+    pub config: OptimizerConfig
+}
+
+impl Optimizing {
+    pub fn new(config: OptimizerConfig) -> Self {
+        Optimizing {
+            config
+        }
+    }
+
+    /// Search for the schedule that minimizes the configured `Objective`.
+    ///
+    /// # Arguments
+    /// - `recipe_specs`: The `RecipeSpec`s to schedule. Each must already have a start date.
+    /// - `templates`: The `ProductionPhaseTemplate`s that the phases of the recipes refer to.
+    /// - `tracker`: The `ResourceTracker` from which `Resource`s are allocated. Candidate
+    ///   schedules are tried against copies of it, and only the best is allocated.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the best schedule found, or an
+    ///   [Error](super::error::Error) if the recipes can't be scheduled in the order given.
+    pub fn optimize(&self, recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                    tracker: &mut ResourceTracker) -> Result<OptimizedSchedule, Error> {
+        let resources: Vec<Resource> = tracker.get_all_tracked_resources();
+        let evaluate_order = |order: &[usize]| -> Result<f64, Error> {
+            let recipes = schedule_in_order(recipe_specs, templates, &mut tracker.clone(), order)?;
+            Ok(self.config.objective.evaluate(&recipes, &resources))
+        };
+
+        // Any error in scheduling that doesn't depend on the order is reported from here.
+        let mut best_order: Vec<usize> = (0..recipe_specs.len()).collect();
+        let mut best_value: f64 = evaluate_order(&best_order)?;
+
+        let shortest_first = get_shortest_first_order(recipe_specs, templates)?;
+        if let Ok(value) = evaluate_order(&shortest_first) {
+            if value < best_value {
+                best_order = shortest_first;
+                best_value = value;
+            }
+        }
+
+        let mut random = SplitMix64::new(self.config.seed);
+        if recipe_specs.len() > 1 {
+            for _ in 0..self.config.iterations {
+                let from = random.next_index(best_order.len());
+                let to = random.next_index(best_order.len() - 1);
+
+                let mut candidate = best_order.clone();
+                let moved = candidate.remove(from);
+                candidate.insert(to, moved);
+
+                // Candidates that can't be scheduled are simply skipped. Candidates that are just
+                // as good are kept, so that the search can move across plateaus.
+                if let Ok(value) = evaluate_order(&candidate) {
+                    if value <= best_value {
+                        best_order = candidate;
+                        best_value = value;
+                    }
+                }
+            }
+        }
+
+        let recipes = schedule_in_order(recipe_specs, templates, tracker, &best_order)?;
+
+        Ok(OptimizedSchedule {
+            recipes,
+            objective_value: best_value
+        })
+    }
+}
+
+impl Scheduler for Optimizing {
+    fn schedule(&self, recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                tracker: &mut ResourceTracker) -> Result<Vec<Recipe>, Error> {
+        self.optimize(recipe_specs, templates, tracker).map(|result| result.recipes)
+    }
+}
+
+/// A small, seedable pseudo-random number generator (SplitMix64), so that the search is
+/// reproducible for a given seed on any platform.
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 {
+            state: seed
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Retrieve a value in the range `[0, bound)`. `bound` must be greater than zero.
+    fn next_index(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
@@ -2,38 +2,214 @@ use chrono::{Duration, NaiveDateTime, NaiveTime};
 
 use string_builder::Builder;
 
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Deserializer};
+use serde::de::Error as DeError;
 
-use super::resources::{Resource, ResourceType};
+use super::ical::{escape_text, format_date_time, format_duration, get_folded_line};
+use super::resources::{Resource, ResourceReference, ResourceType};
 use super::util::{get_space_indent, get_duration_in_hours, convert_string_to_duration};
 
+/// An entry within the `resourcesNeeded` block of a `ProductionPhaseTemplate`.
+///
+/// Each entry is either the name of a `ResourceType` (e.g. `"fermentor"`), denoting a single
+/// `Resource` of that type, or an object containing a `type` and a `count` (e.g.
+/// `{ "type": "gastank", "count": 2 }`), denoting that many distinct `Resource`s of that type.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum ResourceNeed {
+    Single(ResourceType),
+    Counted {
+        #[serde(rename="type")]
+        resource_type: ResourceType,
+
+        #[serde(default="get_default_resource_count")]
+        count: usize
+    }
+}
+
+fn get_default_resource_count() -> usize {
+    1
+}
+
+impl ResourceNeed {
+    pub fn resource_type(&self) -> &ResourceType {
+        match self {
+            ResourceNeed::Single(resource_type) => resource_type,
+            ResourceNeed::Counted { resource_type, .. } => resource_type
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            ResourceNeed::Single(_) => 1,
+            ResourceNeed::Counted { count, .. } => *count
+        }
+    }
+}
+
+/// Deserialize a `resourcesNeeded` block into a `Vec` of `ResourceType`s, in which a
+/// [ResourceNeed](ResourceNeed) with a `count` of `n` appears as `n` consecutive copies of its
+/// `ResourceType`.
+fn deserialize_resources_needed<'de, D>(deserializer: D) -> Result<Vec<ResourceType>, D::Error>
+  where D: Deserializer<'de> {
+    let needs: Vec<ResourceNeed> = Vec::deserialize(deserializer)?;
+
+    let mut resources_needed: Vec<ResourceType> = vec![];
+    for next_need in needs {
+        if next_need.count() == 0 {
+            return Err(D::Error::custom(format!("count for resource type '{}' must be at least 1",
+                                                next_need.resource_type())));
+        }
+
+        for _ in 0..next_need.count() {
+            resources_needed.push(next_need.resource_type().clone());
+        }
+    }
+
+    Ok(resources_needed)
+}
+
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
 pub struct ProductionPhaseTemplate {
     pub description: String,
     pub id: String,
     pub order: usize,
 
+    /// The `ResourceType`s needed by this phase, with one entry per `Resource` needed. A type
+    /// needed more than once (e.g. via `{ "type": "keg", "count": 2 }`) appears once per
+    /// `Resource`, and all of them will be allocated at the same time.
     #[serde(rename="resourcesNeeded")]
-    #[serde(default="Vec::new")]
+    #[serde(default="Vec::new", deserialize_with="deserialize_resources_needed")]
     pub resources_needed: Vec<ResourceType>,
 
     #[serde(rename="color")]
     #[serde(default = "String::new")]
-    color_hex: String,
+    pub color_hex: String,
 
     #[serde(rename="defaultDuration")]
     #[serde(default = "String::new")]
-    default_duration: String
+    pub default_duration_string: String,
+
+    /// Whether phases of this template need people to work on them, such as brewing, rather than
+    /// running on their own, such as fermentation. Hands-on phases only start within the working
+    /// calendar of the timeline.
+    #[serde(rename="handsOn", default)]
+    pub is_hands_on: bool
 }
 
 impl ProductionPhaseTemplate {
     pub fn default_duration(&self) -> Option<Duration> {
-        convert_string_to_duration(&self.default_duration[..])
+        convert_string_to_duration(&self.default_duration_string[..])
+    }
+}
+
+/// The point of a phase, either its start or its end, from which a dependent phase is measured.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all="lowercase")]
+pub enum DependencyAnchor {
+    Start,
+
+    #[default]
+    End
+}
+
+/// A dependency of a phase upon another phase, given in the `after` block of a
+/// `PhaseInstanceSpec`.
+///
+/// Within a BPD file, a dependency is either the id of the phase depended upon (e.g.
+/// `"primary"`), meaning the dependent phase can't start until that phase ends, or an object such
+/// as `{ "phase": "primary", "from": "start", "lag": "7d" }`, meaning the dependent phase can't
+/// start until seven days after that phase starts. The phase depended upon may belong to another
+/// recipe, given by its name, as in `{ "recipe": "Damned Squirrel", "phase": "primary" }`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(from="PhaseDependencyEntry")]
+pub struct PhaseDependencySpec {
+    /// The name of the recipe containing the phase depended upon, if it isn't the recipe
+    /// containing the dependent phase.
+    #[serde(default)]
+    pub recipe: Option<String>,
+
+    pub phase: String,
+
+    #[serde(default)]
+    pub from: DependencyAnchor,
+
+    #[serde(rename="lag")]
+    #[serde(default = "String::new")]
+    pub lag_string: String
+}
+
+impl PhaseDependencySpec {
+    /// Retrieve the time that must pass after the `from` point of the phase depended upon before
+    /// the dependent phase can start, if it can be parsed. An empty `lag` is no time at all.
+    pub fn lag(&self) -> Option<Duration> {
+        match self.lag_string.is_empty() {
+            true => Some(Duration::zero()),
+            false => convert_string_to_duration(&self.lag_string[..])
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PhaseDependencyEntry {
+    Phase(String),
+    Detailed {
+        #[serde(default)]
+        recipe: Option<String>,
+
+        phase: String,
+
+        #[serde(default)]
+        from: DependencyAnchor,
+
+        #[serde(default = "String::new")]
+        lag: String
+    }
+}
+
+impl From<PhaseDependencyEntry> for PhaseDependencySpec {
+    fn from(entry: PhaseDependencyEntry) -> Self {
+        match entry {
+            PhaseDependencyEntry::Phase(phase) => PhaseDependencySpec {
+                recipe: None,
+                phase,
+                from: DependencyAnchor::End,
+                lag_string: String::new()
+            },
+            PhaseDependencyEntry::Detailed { recipe, phase, from, lag } => PhaseDependencySpec {
+                recipe,
+                phase,
+                from,
+                lag_string: lag
+            }
+        }
+    }
+}
+
+/// Deserialize an `after` block, which is either a single dependency or an array of them.
+fn deserialize_dependencies<'de, D>(deserializer: D)
+  -> Result<Option<Vec<PhaseDependencySpec>>, D::Error> where D: Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PhaseDependencySpec),
+        Many(Vec<PhaseDependencySpec>)
     }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(x) => Some(vec![x]),
+        OneOrMany::Many(x) => Some(x)
+    })
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct PhaseInstanceSpec {
+    /// The id by which other phases of the recipe refer to this phase in their `after` blocks.
+    /// Defaults to the id of the phase's template.
+    #[serde(default)]
+    pub id: Option<String>,
+
     #[serde(default = "String::new")]
     pub description: String,
 
@@ -41,10 +217,35 @@ pub struct PhaseInstanceSpec {
 
     #[serde(rename = "duration")]
     #[serde(default = "String::new")]
-    pub duration_string: String
+    pub duration_string: String,
+
+    /// The phases that must be reached before this phase can start. If not given, this phase
+    /// follows the phase before it, in order of the phases' templates. If given, but empty, this
+    /// phase can start as soon as the recipe starts.
+    #[serde(default, deserialize_with="deserialize_dependencies")]
+    pub after: Option<Vec<PhaseDependencySpec>>,
+
+    /// The `Resource`s this phase must use, given by id or name, e.g. `["Big Bertha"]`. For each
+    /// `ResourceType` with a pinned `Resource`, only the pinned `Resource`s of that type are
+    /// allocated to the phase, choosing among them if more are pinned than are needed. Every
+    /// pinned `Resource` must be of a type the phase's template needs.
+    #[serde(rename="pinnedResources", default, skip_serializing_if="Vec::is_empty")]
+    pub pinned_resources: Vec<ResourceReference>,
+
+    /// The `Resource`s this phase must not use, given by id or name.
+    #[serde(rename="excludedResources", default, skip_serializing_if="Vec::is_empty")]
+    pub excluded_resources: Vec<ResourceReference>
 }
 
 impl PhaseInstanceSpec {
+    /// Retrieve the id by which other phases refer to this phase.
+    pub fn phase_id(&self) -> &str {
+        match &self.id {
+            Some(x) => &x[..],
+            None => &self.template[..]
+        }
+    }
+
     pub fn duration(&self) -> Option<Duration> {
         match self.duration_string.is_empty() {
             true => None,
@@ -61,7 +262,11 @@ pub struct PhaseInstance {
     pub duration: Duration,
     pub dependencies: Vec<usize>,
     pub start_date: NaiveDateTime,
-    pub resources_used: Vec<Resource>
+    pub resources_used: Vec<Resource>,
+
+    /// Whether the template of this phase is hands-on (see
+    /// [ProductionPhaseTemplate](ProductionPhaseTemplate)).
+    pub is_hands_on: bool
 }
 
 impl PhaseInstance {
@@ -93,7 +298,8 @@ impl PhaseInstance {
             duration: duration,
             dependencies: vec![],
             start_date: start_date,
-            resources_used: resources
+            resources_used: resources,
+            is_hands_on: false
         }
     }
 
@@ -141,4 +347,65 @@ impl PhaseInstance {
 
         builder.string().unwrap()
     }
+
+    /// Retrieve a `String` representing this `PhaseInstance` as an iCalendar `VEVENT` component.
+    ///
+    /// # Arguments
+    /// - `self`: A borrowed reference to this `PhaseInstance`.
+    /// - `recipe_name`: The name of the recipe containing this `PhaseInstance`.
+    /// - `schedule_id`: The id of the [ProductionSchedule](super::ProductionSchedule), which,
+    ///   together with the id of this `PhaseInstance`, identifies the event.
+    /// - `reminder`: How long before the start of this `PhaseInstance` a reminder should go off,
+    ///   if it is hands-on. If `None`, no reminder is given.
+    /// - `timestamp`: The [NaiveDateTime](chrono::NaiveDateTime), in UTC, at which the event was
+    ///   created.
+    ///
+    /// # Returns
+    /// - A `String` containing the `VEVENT`, with CRLF line endings, and long lines folded.
+    ///
+    pub fn get_string_in_ics_format(&self, recipe_name: &str, schedule_id: usize,
+                                    reminder: Option<Duration>, timestamp: NaiveDateTime) -> String {
+        let summary: String = escape_text(&format!("{}: {}", recipe_name, self.description));
+        let resource_names: Vec<String> = self.resources_used.iter()
+                                                             .map(|res| res.name.clone())
+                                                             .collect();
+
+        let mut description: String = format!("Recipe: {}\nPhase: {}", recipe_name,
+                                              self.description);
+        if !resource_names.is_empty() {
+            description.push_str(&format!("\nResources: {}", resource_names.join(", ")));
+        }
+
+        let mut lines: Vec<String> = vec![
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}-{}@chronogrog", schedule_id, self.id),
+            format!("DTSTAMP:{}Z", format_date_time(timestamp)),
+            format!("DTSTART:{}", format_date_time(self.start_date)),
+            format!("DURATION:{}", format_duration(self.duration)),
+            format!("SUMMARY:{}", summary),
+            format!("DESCRIPTION:{}", escape_text(&description))
+        ];
+
+        if !resource_names.is_empty() {
+            let resources: Vec<String> = resource_names.iter().map(|x| escape_text(x)).collect();
+            lines.push(format!("RESOURCES:{}", resources.join(",")));
+        }
+
+        if let (true, Some(before)) = (self.is_hands_on, reminder) {
+            lines.push(String::from("BEGIN:VALARM"));
+            lines.push(String::from("ACTION:DISPLAY"));
+            lines.push(format!("TRIGGER:{}", format_duration(-before)));
+            lines.push(format!("DESCRIPTION:{}", summary));
+            lines.push(String::from("END:VALARM"));
+        }
+
+        lines.push(String::from("END:VEVENT"));
+
+        let mut builder = Builder::default();
+        for next_line in &lines {
+            builder.append(get_folded_line(next_line));
+        }
+
+        builder.string().unwrap()
+    }
 }
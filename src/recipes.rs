@@ -4,10 +4,12 @@ use serde::{Serialize, Deserialize};
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime, ParseError};
 
+use super::calendar::{BlackoutSpec, WorkingCalendarSpec};
 use super::phases::PhaseInstanceSpec;
 use super::phases::PhaseInstance;
 
 use super::util::{get_space_indent, get_naive_date_time_from_string};
+use super::volume::Volume;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 
@@ -32,7 +34,35 @@ pub struct RecipeSpec {
     pub phase_specs: Vec<PhaseInstanceSpec>,
 
     #[serde(rename="start")]
-    pub start_string: Option<String>
+    pub start_string: Option<String>,
+
+    #[serde(rename="batchSize", default)]
+    pub batch_size_string: Option<String>,
+
+    /// The date by which every phase of this recipe must have ended, if the recipe has to be ready
+    /// at a particular time. May also be given as `deadline`. If given, the recipe starts as late
+    /// as it can while still being ready in time, but never before its `start` date.
+    #[serde(rename="readyBy", alias="deadline", default)]
+    pub ready_by_string: Option<String>,
+
+    /// The earliest date at which any phase of this recipe may start, such as when an ingredient
+    /// arrives.
+    #[serde(rename="notBefore", default)]
+    pub not_before_string: Option<String>,
+
+    /// The latest date at which the first phase of this recipe may start.
+    #[serde(rename="notAfter", default)]
+    pub not_after_string: Option<String>,
+
+    /// Periods during which no phase of this recipe may start. The blackouts of the timeline
+    /// apply as well.
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutSpec>,
+
+    /// The times at which the hands-on phases of this recipe may start, if they differ from the
+    /// working calendar of the timeline.
+    #[serde(rename="workingCalendar", default)]
+    pub working_calendar: Option<WorkingCalendarSpec>
 }
 
 impl RecipeSpec {
@@ -50,6 +80,62 @@ impl RecipeSpec {
             None => Ok(NaiveDateTime::new(NaiveDate::from_ymd(1970, 1, 1), NaiveTime::from_hms(0, 0, 0)))
         }
     }
+
+    /// Retrieve the date by which this `Recipe` must be ready, as a `NaiveDateTime`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the date, or `None` if the recipe has no
+    ///   `readyBy` date, or a `ParseError` that lets the client know why the parsing failed.
+    ///
+    pub fn ready_by_date(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        get_optional_date(&self.ready_by_string)
+    }
+
+    /// Retrieve the earliest date at which any phase of this `Recipe` may start, as a
+    /// `NaiveDateTime`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the date, or `None` if the recipe has no
+    ///   `notBefore` date, or a `ParseError` that lets the client know why the parsing failed.
+    ///
+    pub fn not_before_date(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        get_optional_date(&self.not_before_string)
+    }
+
+    /// Retrieve the latest date at which the first phase of this `Recipe` may start, as a
+    /// `NaiveDateTime`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the date, or `None` if the recipe has no
+    ///   `notAfter` date, or a `ParseError` that lets the client know why the parsing failed.
+    ///
+    pub fn not_after_date(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        get_optional_date(&self.not_after_string)
+    }
+
+    /// Retrieve the size of a batch of this `Recipe`, as a `Volume`.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the batch size, or `None` if no batch size
+    ///   was given, or an `Err` containing the batch size string, if it could not be parsed as a
+    ///   [Volume](super::volume::Volume).
+    ///
+    pub fn batch_size(&self) -> Result<Option<Volume>, String> {
+        match &self.batch_size_string {
+            Some(x) => match Volume::parse(&x[..]) {
+                Some(volume) => Ok(Some(volume)),
+                None => Err(x.clone())
+            },
+            None => Ok(None)
+        }
+    }
+}
+
+fn get_optional_date(date_string: &Option<String>) -> Result<Option<NaiveDateTime>, ParseError> {
+    match date_string {
+        Some(x) => get_naive_date_time_from_string(&x[..]).map(Some),
+        None => Ok(None)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -67,7 +153,8 @@ pub struct Recipe {
     pub name: String,
     pub color: String,
     pub phases: Vec<PhaseInstance>,
-    pub start_date: NaiveDateTime
+    pub start_date: NaiveDateTime,
+    pub batch_size: Option<Volume>
 }
 
 impl Recipe {
@@ -5,7 +5,13 @@ use chrono_period::NaivePeriod;
 
 use serde::{Serialize, Deserialize, Serializer, Deserializer};
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+
+use super::bottlenecks::DelayEvent;
+use super::util::{convert_string_to_duration, get_naive_date_time_from_string};
+use super::volume::{Volume, VolumeUnit};
 
 #[derive(Clone, Debug, PartialEq)]
 
@@ -39,6 +45,55 @@ pub enum ResourceType {
     Other(String)
 }
 
+impl ResourceType {
+    /// Retrieve the name of this `ResourceType`, as it appears in a BPD file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chronogrog::resources::ResourceType;
+    /// assert_eq!("mashtun", ResourceType::MashTun.as_str());
+    /// assert_eq!("fancythingy", ResourceType::Other("fancythingy".to_string()).as_str());
+    /// ```
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ResourceType::Fermentor => "fermentor",
+            ResourceType::Kettle => "kettle",
+            ResourceType::MashTun => "mashtun",
+            ResourceType::LauterTun => "lautertun",
+            ResourceType::Keg => "keg",
+            ResourceType::Kegerator => "kegerator",
+            ResourceType::GasTank => "gastank",
+            ResourceType::Other(ref other) => other
+        }
+    }
+
+    /// Determine if a `Resource` of this `ResourceType` holds the batch itself while in use, and
+    /// thus must have a capacity large enough for the batch.
+    ///
+    /// # Returns
+    /// - `true` for vessels that hold the wort or beer, as well as for `Other` resource types, since
+    ///   nothing is known about them; `false` for `Kegerator` and `GasTank`, whose capacity is not
+    ///   related to the size of a batch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chronogrog::resources::ResourceType;
+    /// assert!(ResourceType::Fermentor.holds_batch());
+    /// assert!(!ResourceType::GasTank.holds_batch());
+    /// ```
+    pub fn holds_batch(&self) -> bool {
+        !matches!(*self, ResourceType::Kegerator | ResourceType::GasTank)
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 impl From<&str> for ResourceType {
     /// Convert from a string slice (`&str`) to a `ResourceType`.
     ///
@@ -136,16 +191,7 @@ impl Serialize for ResourceType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer
     {
-        serializer.serialize_str(match *self {
-            ResourceType::Fermentor => "fermentor",
-            ResourceType::Kettle => "kettle",
-            ResourceType::MashTun => "mashtun",
-            ResourceType::LauterTun => "lautertun",
-            ResourceType::Keg => "keg",
-            ResourceType::Kegerator => "kegerator",
-            ResourceType::GasTank => "gastank",
-            ResourceType::Other(ref other) => other
-        })
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -161,16 +207,149 @@ pub struct Resource {
     /// A `String` denoting the capacity for this `Resource`.
     ///
     /// # Notes
-    /// This is currently unused, but will likely be used in the future in the form of an `enum` so
-    /// that we can check to see if a particular `Recipe` requires more than one `Resource` of a
-    /// particular type.
+    /// For resources that hold the batch (see [holds_batch](ResourceType::holds_batch)), this is
+    /// parsed as a [Volume](super::volume::Volume) and used to determine whether a batch fits
+    /// within the `Resource`. See [capacity](Resource::capacity).
     #[serde(rename="capacity")]
     pub capacity_str: String,
 
+    /// Periods during which this `Resource` can't be used at all, such as while it's being
+    /// cleaned or repaired, given as its `unavailable` block. These are allocated as soon as the
+    /// `Resource` is tracked, so that no phase is scheduled over them.
+    #[serde(rename="unavailable", default, skip_serializing_if="Vec::is_empty")]
+    pub unavailable_periods: Vec<UnavailablePeriod>,
+
+    /// A `String` denoting how long this `Resource` needs after each use before it can be used
+    /// again, e.g. for cleaning in place. If not given, the turnaround for its `ResourceType` is
+    /// used, if there is one. See [turnaround](Resource::turnaround).
+    #[serde(rename="turnaround", default, skip_serializing_if="Option::is_none")]
+    pub turnaround_string: Option<String>,
+
+    /// How strongly this `Resource` is preferred over others of its type, when resources are
+    /// selected by [Priority](SelectionPolicy::Priority). Higher priorities are preferred, and
+    /// `Resource`s without one have a priority of zero.
+    #[serde(default, skip_serializing_if="Option::is_none")]
+    pub priority: Option<i64>,
+
     #[serde(skip_serializing, skip_deserializing, default="Vec::new")]
     pub allocated_periods: Vec<NaivePeriod>
 }
 
+/// The way in which a `Resource` is chosen when more than one is free to satisfy a
+/// `ResourceRequest`, given as the `resourceSelection` entry of the `timeline` block.
+///
+/// Whichever policy is used, `Resource`s it can't tell apart are chosen in order of `id`, so that
+/// the same input always produces the same schedule.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all="kebab-case")]
+pub enum SelectionPolicy {
+    /// Prefer the `Resource` with the minimum `id`.
+    LowestId,
+
+    /// Prefer the smallest `Resource` that can hold the batch, if the batch size is known, so that
+    /// larger ones are left for larger batches.
+    #[default]
+    BestFit,
+
+    /// Prefer the `Resource` whose last use ended longest ago, or that hasn't been used at all, so
+    /// that wear is spread evenly.
+    LeastRecentlyUsed,
+
+    /// Prefer the `Resource` whose last use ended most recently, e.g. to keep vessels warm.
+    MostRecentlyUsed,
+
+    /// Prefer the `Resource` with the highest `priority`.
+    Priority
+}
+
+impl SelectionPolicy {
+    /// The names of every `SelectionPolicy`, as written within a BPD file.
+    pub const NAMES: [&'static str; 5] = ["lowest-id", "best-fit", "least-recently-used",
+                                          "most-recently-used", "priority"];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            SelectionPolicy::LowestId => SelectionPolicy::NAMES[0],
+            SelectionPolicy::BestFit => SelectionPolicy::NAMES[1],
+            SelectionPolicy::LeastRecentlyUsed => SelectionPolicy::NAMES[2],
+            SelectionPolicy::MostRecentlyUsed => SelectionPolicy::NAMES[3],
+            SelectionPolicy::Priority => SelectionPolicy::NAMES[4]
+        }
+    }
+}
+
+/// The turnaround of every `Resource` of a `ResourceType`, given within the `turnarounds` block of
+/// a BPD file. A `Resource` with a `turnaround` of its own uses that instead.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TurnaroundSpec {
+    #[serde(rename="type")]
+    pub resource_type: ResourceType,
+
+    #[serde(rename="duration")]
+    pub duration_string: String
+}
+
+/// A period during which a `Resource` can't be used, such as for maintenance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnavailablePeriod {
+    #[serde(rename="start")]
+    pub start_string: String,
+
+    #[serde(rename="end")]
+    pub end_string: String,
+
+    /// Why the `Resource` can't be used, e.g. `"passivation"`.
+    #[serde(default)]
+    pub reason: Option<String>
+}
+
+impl UnavailablePeriod {
+    /// Retrieve the period during which the `Resource` can't be used, as a `NaivePeriod`.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the [NaivePeriod](chrono_period::NaivePeriod), or an `Err`
+    ///   containing the first date string that could not be parsed.
+    pub fn period(&self) -> Result<NaivePeriod, String> {
+        let start = match get_naive_date_time_from_string(&self.start_string[..]) {
+            Ok(x) => x,
+            Err(_e) => return Err(self.start_string.clone())
+        };
+
+        match get_naive_date_time_from_string(&self.end_string[..]) {
+            Ok(end) => Ok(NaivePeriod::new(start, end)),
+            Err(_e) => Err(self.end_string.clone())
+        }
+    }
+}
+
+/// A reference to a `Resource` from within a BPD file, given either as its `id` (e.g. `3`), or as
+/// its `name` (e.g. `"Big Bertha"`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum ResourceReference {
+    Id(usize),
+    Name(String)
+}
+
+impl ResourceReference {
+    /// Determine if a `Resource` is the one referred to.
+    pub fn refers_to(&self, resource: &Resource) -> bool {
+        match self {
+            ResourceReference::Id(id) => resource.id == *id,
+            ResourceReference::Name(name) => resource.name == *name
+        }
+    }
+}
+
+impl fmt::Display for ResourceReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceReference::Id(id) => write!(f, "{}", id),
+            ResourceReference::Name(name) => write!(f, "{}", name)
+        }
+    }
+}
+
 impl Resource {
     /// Create a new instance of `Resource`, given an id, a name, a `ResourceType`, and a capacity.
     ///
@@ -192,10 +371,86 @@ impl Resource {
             name: name.to_string(),
             resource_type: resource_type,
             capacity_str: capacity_str.to_string(),
+            unavailable_periods: vec![],
+            turnaround_string: None,
+            priority: None,
             allocated_periods: vec![]
         }
     }
 
+    /// Retrieve the capacity of this `Resource`, as a `Volume`.
+    ///
+    /// # Returns
+    /// - An `Option` containing the capacity of this `Resource`, if `capacity_str` could be
+    ///   parsed as a [Volume](super::volume::Volume); `None`, otherwise.
+    pub fn capacity(&self) -> Option<Volume> {
+        Volume::parse(&self.capacity_str[..])
+    }
+
+    /// Retrieve the time this `Resource` needs after each use before it can be used again.
+    ///
+    /// # Returns
+    /// - An `Option` containing the turnaround, which is zero if none was given, or `None` if
+    ///   `turnaround_string` could not be parsed.
+    pub fn turnaround(&self) -> Option<Duration> {
+        match &self.turnaround_string {
+            Some(x) => convert_string_to_duration(&x[..]),
+            None => Some(Duration::zero())
+        }
+    }
+
+    /// Retrieve the date at which this `Resource` was last in use before a given date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the end of the last allocated period that ends before `date`, or
+    ///   `None` if there is none. Periods during which the `Resource` is unavailable aren't uses.
+    pub fn get_last_use_before(&self, date: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.allocated_periods.iter()
+                              .filter(|period| period.end < date && !self.is_downtime(period))
+                              .map(|period| period.end)
+                              .max()
+    }
+
+    /// Determine if an allocated period of this `Resource` is one of its `unavailable_periods`,
+    /// rather than a use.
+    fn is_downtime(&self, period: &NaivePeriod) -> bool {
+        self.unavailable_periods.iter()
+                                .filter_map(|x| x.period().ok())
+                                .any(|x| x == *period)
+    }
+
+    /// Retrieve the turnaround that follows an allocated period of this `Resource`.
+    ///
+    /// # Returns
+    /// - The turnaround of this `Resource`, if `period` is a use, or zero, if `period` is one of
+    ///   its `unavailable_periods`, after which it is ready straight away. A turnaround that
+    ///   can't be parsed is treated as zero.
+    pub fn get_turnaround_after(&self, period: &NaivePeriod) -> Duration {
+        match self.is_downtime(period) {
+            true => Duration::zero(),
+            false => self.turnaround().unwrap_or_else(Duration::zero)
+        }
+    }
+
+    /// Determine if a batch of a given `Volume` fits within this `Resource`.
+    ///
+    /// # Arguments
+    /// - `volume`: The [Volume](super::volume::Volume) of the batch.
+    ///
+    /// # Returns
+    /// - `true`, if this `Resource` doesn't hold the batch, if its capacity is unknown, or if its
+    ///   capacity is at least `volume`; `false`, otherwise.
+    pub fn fits_volume(&self, volume: &Volume) -> bool {
+        if !self.resource_type.holds_batch() {
+            return true;
+        }
+
+        match self.capacity() {
+            Some(capacity) => capacity >= *volume,
+            None => true
+        }
+    }
+
     /// Determine if this `Resource` is allocated at any time during a specific `Duration` starting
     /// at a specific `NaiveDateTime`.
     ///
@@ -209,10 +464,18 @@ impl Resource {
     ///   period comprising `start` - `end`, including `end`; `false`, otherwise.
     pub fn is_allocated_over_start_duration(&self, start: NaiveDateTime,
                                             duration: Duration) -> bool {
-        let intersection_period = NaivePeriod::from_start_duration(start, duration);
+        // Both the requested period and every use are followed by the turnaround, so that
+        // consecutive uses are always at least the turnaround apart. Periods during which the
+        // resource is unavailable aren't uses, so no turnaround is needed before or after them.
+        let turnaround: Duration = self.turnaround().unwrap_or_else(Duration::zero);
+        let requested_period = NaivePeriod::from_start_duration(start, duration);
+        let requested_use = NaivePeriod::from_start_duration(start, duration + turnaround);
 
         self.allocated_periods.iter().any(|period| {
-            period.intersects_with(intersection_period)
+            match self.is_downtime(period) {
+                true => period.intersects_with(requested_period),
+                false => NaivePeriod::new(period.start, period.end + turnaround).intersects_with(requested_use)
+            }
         })
     }
 
@@ -229,6 +492,20 @@ impl Resource {
         self.is_allocated_over_start_duration(period.start, period.duration())
     }
 
+    /// Determine if this `Resource` is allocated at a specific `NaiveDateTime`.
+    ///
+    /// # Arguments
+    /// - `date`: The [NaiveDateTime](chrono::NaiveDateTime) to check.
+    ///
+    /// # Returns
+    /// - `true`, if `date` falls within one of the allocated periods of this `Resource`, or within
+    ///   the turnaround that follows a use; `false`, otherwise.
+    pub fn is_allocated_at(&self, date: NaiveDateTime) -> bool {
+        self.allocated_periods.iter().any(|period| {
+            period.start <= date && date <= period.end + self.get_turnaround_after(period)
+        })
+    }
+
     pub fn allocate_over_start_duration(&mut self, start: NaiveDateTime,
                                         duration: Duration) -> Option<&Resource> {
         if self.is_allocated_over_start_duration(start, duration) {
@@ -253,20 +530,243 @@ impl Resource {
         }
 
         self.allocated_periods.iter().filter(|needle| {
-            // Discard any where the end date is before the desired start date.
-            if needle.end.timestamp() < period.start.timestamp() {
+            let turnaround: Duration = self.get_turnaround_after(needle);
+
+            // Discard any where the end date, and its turnaround, is before the desired start date.
+            if (needle.end + turnaround).timestamp() < period.start.timestamp() {
                 return false;
             }
 
-            let end_date_plus_one_second = needle.end + Duration::seconds(1);
+            let end_date_plus_one_second = needle.end + turnaround + Duration::seconds(1);
             let is_alloc = !self.is_allocated_over_start_duration(end_date_plus_one_second, period.duration());
             is_alloc
         }).map(|needle| {
-            needle.end + Duration::seconds(1)
+            needle.end + self.get_turnaround_after(needle) + Duration::seconds(1)
         }).take(1).next().unwrap()
     }
 }
 
+/// A request for one or more `Resource`s needed by a phase.
+///
+/// A `ResourceRequest` is satisfied by `count` distinct `Resource`s of the requested
+/// `ResourceType`, each large enough for its share of the batch being produced, if the `volume` of
+/// the batch is known. If there aren't enough `Resource`s that are large enough, the batch may be
+/// split across more `Resource`s of the requested type, so long as their combined capacity is
+/// large enough (see
+/// [allocate_resources_for_request_over_period](ResourceTracker::allocate_resources_for_request_over_period)).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceRequest {
+    pub resource_type: ResourceType,
+
+    /// The `Volume` of the batch that the `Resource`s must hold, if known.
+    pub volume: Option<Volume>,
+
+    /// The minimum number of distinct `Resource`s that must be allocated.
+    pub count: usize,
+
+    /// The `id`s of the only `Resource`s that may be allocated, if the phase is pinned to
+    /// particular `Resource`s of the requested type. If empty, any `Resource` of the type may be.
+    pub pinned_ids: Vec<usize>,
+
+    /// The `id`s of `Resource`s of the requested type that must not be allocated.
+    pub excluded_ids: Vec<usize>
+}
+
+impl ResourceRequest {
+    pub fn new(resource_type: ResourceType, volume: Option<Volume>) -> Self {
+        ResourceRequest {
+            resource_type,
+            volume,
+            count: 1,
+            pinned_ids: vec![],
+            excluded_ids: vec![]
+        }
+    }
+
+    /// Group a list of needed `ResourceType`s into `ResourceRequest`s, such that a type appearing
+    /// `n` times results in a single `ResourceRequest` with a `count` of `n`.
+    ///
+    /// # Arguments
+    /// - `resources_needed`: The `ResourceType`s needed, with one entry per `Resource`, as in
+    ///   [ProductionPhaseTemplate](super::phases::ProductionPhaseTemplate).
+    /// - `volume`: The `Volume` of the batch, if known.
+    ///
+    /// # Returns
+    /// - A `Vec` of `ResourceRequest`s, in the order each `ResourceType` first appears.
+    pub fn group(resources_needed: &[ResourceType], volume: Option<Volume>) -> Vec<ResourceRequest> {
+        let mut requests: Vec<ResourceRequest> = vec![];
+        for next_type in resources_needed {
+            match requests.iter_mut().find(|request| request.resource_type == *next_type) {
+                Some(request) => request.count += 1,
+                None => requests.push(ResourceRequest::new(next_type.clone(), volume))
+            }
+        }
+
+        requests
+    }
+
+    /// Retrieve the `Volume` of the batch that each of the `count` `Resource`s must hold, if the
+    /// `volume` of the batch is known.
+    fn get_share(&self) -> Option<Volume> {
+        self.volume.map(|volume| Volume::new(volume.amount / self.count as f64, volume.unit))
+    }
+
+    /// Determine if a `Resource` may be allocated to this `ResourceRequest`, because it is of the
+    /// requested type, is pinned (if any `Resource`s are), and isn't excluded, without regard to
+    /// its capacity, or whether it is currently allocated.
+    pub fn is_permitted(&self, resource: &Resource) -> bool {
+        resource.resource_type == self.resource_type
+          && (self.pinned_ids.is_empty() || self.pinned_ids.contains(&resource.id))
+          && !self.excluded_ids.contains(&resource.id)
+    }
+
+    /// Determine if a `Resource` can hold its share of this `ResourceRequest`, without regard to
+    /// whether it is currently allocated.
+    pub fn is_satisfied_by(&self, resource: &Resource) -> bool {
+        self.is_permitted(resource)
+          && match &self.get_share() {
+              Some(share) => resource.fits_volume(share),
+              None => true
+          }
+    }
+
+    /// Compare two `Resource`s that both satisfy this `ResourceRequest`, in order of preference.
+    ///
+    /// # Arguments
+    /// - `a`, `b`: The `Resource`s to compare.
+    /// - `policy`: The [SelectionPolicy](SelectionPolicy) giving the preference. For
+    ///   [BestFit](SelectionPolicy::BestFit), if the `volume` of the batch is known, the `Resource`
+    ///   with the smallest capacity is preferred, with `Resource`s of unknown capacity coming
+    ///   last.
+    /// - `date`: The date from which the `Resource` would be used, against which recent uses are
+    ///   measured.
+    ///
+    /// # Returns
+    /// - `Ordering::Less` if `a` is preferred. Ties are broken by preferring the `Resource` with
+    ///   the minimum `id`.
+    fn compare_preference(&self, a: &Resource, b: &Resource, policy: SelectionPolicy,
+                          date: NaiveDateTime) -> Ordering {
+        let by_policy = match policy {
+            SelectionPolicy::LowestId => Ordering::Equal,
+            SelectionPolicy::BestFit => match (&self.volume, a.capacity(), b.capacity()) {
+                (None, _, _) => Ordering::Equal,
+                (Some(_), Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                (Some(_), Some(_), None) => Ordering::Less,
+                (Some(_), None, Some(_)) => Ordering::Greater,
+                (Some(_), None, None) => Ordering::Equal
+            },
+
+            // A `Resource` that has never been used sorts before any that has, which is what
+            // spreading wear wants, and is the reverse of what keeping vessels warm wants.
+            SelectionPolicy::LeastRecentlyUsed => {
+                a.get_last_use_before(date).cmp(&b.get_last_use_before(date))
+            },
+            SelectionPolicy::MostRecentlyUsed => {
+                b.get_last_use_before(date).cmp(&a.get_last_use_before(date))
+            },
+            SelectionPolicy::Priority => {
+                b.priority.unwrap_or(0).cmp(&a.priority.unwrap_or(0))
+            }
+        };
+
+        by_policy.then(a.id.cmp(&b.id))
+    }
+
+    /// Select the `Resource`s that should be used to satisfy this `ResourceRequest`, from a set of
+    /// candidate `Resource`s of the requested type.
+    ///
+    /// # Arguments
+    /// - `candidates`: The `Resource`s that may be used, each of which must be
+    ///   [permitted](ResourceRequest::is_permitted).
+    /// - `policy`: The [SelectionPolicy](SelectionPolicy) by which candidates are preferred.
+    /// - `date`: The date from which the selected `Resource`s would be used.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` contains the `count` most preferred candidates that can each hold
+    ///     their share of the batch, if there are enough of them. Otherwise, `x` contains the
+    ///     fewest candidates (but at least `count`), largest first, whose combined capacity can
+    ///     hold the batch.
+    ///   - `None`, if the candidates can't satisfy this `ResourceRequest`, even when combined.
+    fn select_from<'a>(&self, candidates: &[&'a Resource], policy: SelectionPolicy,
+                       date: NaiveDateTime) -> Option<Vec<&'a Resource>> {
+        let mut fitting: Vec<&Resource> = candidates.iter()
+          .filter(|res| self.is_satisfied_by(res))
+          .cloned()
+          .collect();
+
+        if fitting.len() >= self.count {
+            fitting.sort_by(|a, b| self.compare_preference(a, b, policy, date));
+            fitting.truncate(self.count);
+            return Some(fitting);
+        }
+
+        // There aren't enough resources that can each hold their share of the batch, so split it
+        // across as few resources as possible. Since not every resource fits, the volume is known.
+        let volume: &Volume = match &self.volume {
+            Some(x) if self.resource_type.holds_batch() => x,
+            _ => return None
+        };
+
+        let get_liters = |res: &Resource| res.capacity().map_or(f64::INFINITY, |x| x.in_liters());
+
+        let mut by_capacity: Vec<&Resource> = candidates.to_vec();
+        by_capacity.sort_by(|a, b| {
+            get_liters(b).partial_cmp(&get_liters(a)).unwrap_or(Ordering::Equal)
+                         .then(a.id.cmp(&b.id))
+        });
+
+        let mut selected: Vec<&Resource> = vec![];
+        let mut total_liters: f64 = 0.0;
+        for next_resource in by_capacity {
+            selected.push(next_resource);
+            total_liters += get_liters(next_resource);
+
+            if selected.len() >= self.count
+                 && Volume::new(total_liters, VolumeUnit::Liters) >= *volume {
+                return Some(selected);
+            }
+        }
+
+        None
+    }
+}
+
+impl From<ResourceType> for ResourceRequest {
+    fn from(resource_type: ResourceType) -> Self {
+        ResourceRequest::new(resource_type, None)
+    }
+}
+
+/// What a `Resource` is allocated to, over one of its allocated periods.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllocationHolder {
+    /// A phase of a recipe, given by the name of the recipe, and the id and description of the
+    /// phase, as they appear in the scheduled [Recipe](super::recipes::Recipe).
+    Phase {
+        recipe: String,
+        phase_id: usize,
+        description: String
+    },
+
+    /// One of the `unavailable_periods` of the `Resource`, with the reason given for it, if any.
+    Unavailable {
+        reason: Option<String>
+    },
+
+    /// Something that wasn't recorded, such as an allocation made without a holder, or a period
+    /// that was already allocated when the `Resource` was tracked.
+    Unknown
+}
+
+/// A period over which a `Resource` is allocated, along with what it is allocated to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Allocation {
+    pub resource_id: usize,
+    pub period: NaivePeriod,
+    pub holder: AllocationHolder
+}
+
 #[derive(Clone)]
 /// A `Resource` that may be allocated (and thus not usable).
 ///
@@ -295,9 +795,17 @@ pub struct PossiblyAllocatedResource {
 /// Thus, it is assumed that `id` fields will be unique within this instance of `ResourceTracker`.
 /// If you have an `id` that is duplicated, the behavior is undefined, but likely will result in
 /// unwanted behavior.
-#[derive(Debug)]
+///
+/// Alongside the allocated periods of each `Resource`, the `ResourceTracker` records what each one
+/// was allocated to, as an [Allocation](Allocation), so that it can be queried afterwards.
+#[derive(Clone, Debug)]
 pub struct ResourceTracker {
-    resources: HashMap<usize, Resource>
+    resources: HashMap<usize, Resource>,
+    selection_policy: SelectionPolicy,
+    allocations: Vec<Allocation>,
+
+    /// Every time a phase had to wait for `Resource`s tracked here, in the order it happened.
+    delays: Vec<DelayEvent>
 }
 
 impl ResourceTracker {
@@ -318,8 +826,17 @@ impl ResourceTracker {
     ///   dropped from the tracker.
     ///
     pub fn new() -> Self {
+        ResourceTracker::with_selection_policy(SelectionPolicy::default())
+    }
+
+    /// Create a new, empty `ResourceTracker`, as with [new](ResourceTracker::new), that chooses
+    /// between free `Resource`s using a given [SelectionPolicy](SelectionPolicy).
+    pub fn with_selection_policy(selection_policy: SelectionPolicy) -> Self {
         ResourceTracker {
-            resources: HashMap::new()
+            resources: HashMap::new(),
+            selection_policy,
+            allocations: vec![],
+            delays: vec![]
         }
     }
 
@@ -331,9 +848,36 @@ impl ResourceTracker {
     ///
     /// # Arguments
     ///
-    /// * `res`: A  `Resource` object to track within this `ResourceTracker`.
+    /// * `res`: A  `Resource` object to track within this `ResourceTracker`. Its
+    ///   `unavailable_periods` are allocated straight away.
     ///
-    pub fn track_resource(&mut self, res: Resource) {
+    pub fn track_resource(&mut self, mut res: Resource) {
+        // Anything recorded for a previously tracked resource with the same id is dropped along
+        // with it.
+        self.allocations.retain(|allocation| allocation.resource_id != res.id);
+
+        // Periods during which the resource is unavailable are allocated up front. Those that
+        // can't be parsed are ignored here, and reported when the schedule is initialized.
+        for next_unavailable in res.unavailable_periods.iter() {
+            let next_period: NaivePeriod = match next_unavailable.period() {
+                Ok(x) => x,
+                Err(_e) => continue
+            };
+
+            if !res.allocated_periods.contains(&next_period) {
+                res.allocated_periods.push(next_period);
+            }
+
+            self.allocations.push(Allocation {
+                resource_id: res.id,
+                period: next_period,
+                holder: AllocationHolder::Unavailable {
+                    reason: next_unavailable.reason.clone()
+                }
+            });
+        }
+
+        res.allocated_periods.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
         self.resources.insert(res.id, res);
     }
 
@@ -349,9 +893,25 @@ impl ResourceTracker {
     ///   otherwise.
     pub fn is_resource_of_type_free_for_period(&self, resource_type: &ResourceType,
                                                period: NaivePeriod) -> bool {
-      self.resources.iter()
-        .filter(|res| res.1.resource_type == *resource_type)
-        .any(|res| !res.1.is_allocated_over_period(period))
+      self.is_resource_free_for_request_over_period(&ResourceRequest::from(resource_type.clone()),
+                                                    period)
+    }
+
+    /// Determine if a `Resource` satisfying a `ResourceRequest` is free during a `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `request`: A borrowed reference to the `ResourceRequest` to check for.
+    /// - `period`: An instance of [NaivePeriod](chrono_period::NaivePeriod) for which to check
+    ///   against.
+    ///
+    /// # Returns
+    /// - `true`, if a `Resource` satisfying `request` is free for the period `period`; `false`,
+    ///   otherwise.
+    pub fn is_resource_free_for_request_over_period(&self, request: &ResourceRequest,
+                                                    period: NaivePeriod) -> bool {
+      self.resources.values()
+        .filter(|res| request.is_satisfied_by(res))
+        .any(|res| !res.is_allocated_over_period(period))
     }
 
     /// Retrieve the next [NaiveDateTime](chrono::NaiveDateTime) at which a `Resource` of a
@@ -373,20 +933,77 @@ impl ResourceTracker {
                                                                  resource_type: &ResourceType,
                                                                  period: NaivePeriod)
       -> Option<NaiveDateTime> {
+      self.get_next_available_resource_date_for_request_over_period(&ResourceRequest::from(resource_type.clone()),
+                                                                    period)
+    }
+
+    /// Retrieve the next [NaiveDateTime](chrono::NaiveDateTime) at which a `ResourceRequest` can
+    /// be satisfied for the duration of a `NaivePeriod`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request`: The [ResourceRequest](ResourceRequest) to query for.
+    /// * `period`: The [NaivePeriod](chrono_period::NaivePeriod) that is desired. Only its
+    ///   duration is fixed; the returned date is on or after its start.
+    ///
+    /// # Returns
+    ///
+    /// * An `Option` containing one of the following values:
+    ///   * `Some`: Contains the closest [NaiveDateTime](chrono::NaiveDateTime) at which enough
+    ///     `Resource`s to satisfy `request` will all be free at the same time.
+    ///   * `None`: If `request` can never be satisfied, even if every `Resource` were free.
+    ///
+    /// # Notes
+    /// If the batch has to be split across several `Resource`s, they must all be free over the
+    /// same period, so the returned date may be later than the date at which any one of them
+    /// becomes free.
+    ///
+    pub fn get_next_available_resource_date_for_request_over_period(&self,
+                                                                    request: &ResourceRequest,
+                                                                    period: NaivePeriod)
+      -> Option<NaiveDateTime> {
+      let mut candidates: Vec<&Resource> = self.resources.values()
+        .filter(|res| request.is_permitted(res))
+        .collect();
+      candidates.sort_by_key(|res| res.id);
 
-      let mut free_dates: Vec<NaiveDateTime> = self.resources.iter()
-        .filter(|res| res.1.resource_type == *resource_type)
-        .map(|res| {
-            res.1.get_earliest_free_date_for_period(period)
-        }).collect();
+      request.select_from(&candidates, self.selection_policy, period.start)?;
 
-        free_dates.sort_by(|a, b| a.partial_cmp(b).unwrap());
+      // The request can only become satisfiable at the requested start, or just after one of the
+      // candidates becomes free, once the turnaround after a use has passed.
+      let mut possible_dates: Vec<NaiveDateTime> = vec![period.start];
+      for next_resource in candidates.iter() {
+          for next_period in next_resource.allocated_periods.iter() {
+              let free_date = next_period.end + next_resource.get_turnaround_after(next_period)
+                              + Duration::seconds(1);
+              if free_date > period.start {
+                  possible_dates.push(free_date);
+              }
+          }
+      }
 
-        if free_dates.is_empty() {
-            return None;
-        }
+      possible_dates.sort();
+      possible_dates.dedup();
+
+      // Once every candidate is free, the request is guaranteed to be satisfiable, so this always
+      // finds a date.
+      possible_dates.into_iter().find(|date| {
+          let possible_period = NaivePeriod::from_start_duration(*date, period.duration());
+          self.select_resources_for_request_over_period(request, possible_period).is_some()
+      })
+    }
+
+    /// Select the `Resource`s that would be allocated to satisfy a `ResourceRequest` over a given
+    /// `NaivePeriod`, returning their `id`s, or `None` if the request can't be satisfied then.
+    fn select_resources_for_request_over_period(&self, request: &ResourceRequest,
+                                                period: NaivePeriod) -> Option<Vec<usize>> {
+      let mut free: Vec<&Resource> = self.resources.values()
+        .filter(|res| request.is_permitted(res) && !res.is_allocated_over_period(period))
+        .collect();
+      free.sort_by_key(|res| res.id);
 
-        Some(free_dates[0])
+      request.select_from(&free, self.selection_policy, period.start)
+             .map(|selected| selected.iter().map(|res| res.id).collect())
     }
 
     /// Allocate a `Resource` of a specific type for a given `NaivePeriod`.
@@ -397,9 +1014,10 @@ impl ResourceTracker {
     ///   should happen.
     ///
     /// # Notes
-    /// `Resource` objects are checked in `id` order, so if multiple `Resource`s with the requested
-    /// `ResourceType` are free for the requested `NaivePeriod`, then the one with the minimum `id`
-    /// will be allocated and returned.
+    /// If multiple `Resource`s with the requested `ResourceType` are free for the requested
+    /// `NaivePeriod`, the one preferred by the [SelectionPolicy](SelectionPolicy) of this
+    /// `ResourceTracker` is allocated. Since no batch size is given, by default this is the one
+    /// with the minimum `id`.
     ///
     /// # Returns
     /// - An `Option` containing either:
@@ -408,37 +1026,262 @@ impl ResourceTracker {
     ///   - None, if no `Resource` with type `resource_type` is free during the given `NaivePeriod`
     pub fn allocate_resource_of_type_for_period(&mut self, resource_type: &ResourceType,
                                                 period: NaivePeriod) -> Option<&Resource> {
-      // println!("Requesting resource of type {:?} for period starting {:?}", resource_type,
-      //          period.start);
+      self.allocate_resource_for_request_over_period(&ResourceRequest::from(resource_type.clone()),
+                                                     period)
+    }
+
+    /// Allocate a `Resource` satisfying a `ResourceRequest` for a given `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `request`: The `ResourceRequest` to allocate a `Resource` for.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    ///
+    /// # Notes
+    /// If multiple `Resource`s satisfying `request` are free for the requested `NaivePeriod`, the
+    /// one preferred by the [SelectionPolicy](SelectionPolicy) of this `ResourceTracker` is
+    /// allocated, with ties broken by minimum `id`. By default, this is the smallest one that fits
+    /// the batch.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` is a `Resource` that satisfies `request` and which is free during
+    ///     the given `NaivePeriod`
+    ///   - None, if no `Resource` satisfying `request` is free during the given `NaivePeriod`
+    pub fn allocate_resource_for_request_over_period(&mut self, request: &ResourceRequest,
+                                                     period: NaivePeriod) -> Option<&Resource> {
+      let mut candidates: Vec<&Resource> = self.resources.values()
+        .filter(|res| request.is_satisfied_by(res) && !res.is_allocated_over_period(period))
+        .collect();
+      candidates.sort_by(|a, b| request.compare_preference(a, b, self.selection_policy,
+                                                           period.start));
+
+      let id: usize = candidates.first()?.id;
+
+      self.record_allocation(id, period, AllocationHolder::Unknown);
+
+      // The id was just retrieved from the map, so this entry always exists.
+      self.resources.get_mut(&id).unwrap().allocate_over_period(period)
+    }
 
-      if !self.is_resource_of_type_free_for_period(resource_type, period) {
-          return None
+    /// Allocate enough `Resource`s to satisfy a `ResourceRequest` for a given `NaivePeriod`,
+    /// splitting the batch across several `Resource`s of the requested type if no single one is
+    /// large enough.
+    ///
+    /// # Arguments
+    /// - `request`: The `ResourceRequest` to allocate `Resource`s for.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    ///
+    /// # Notes
+    /// If a single free `Resource` can hold the batch, only that `Resource` is allocated, as with
+    /// [allocate_resource_for_request_over_period](ResourceTracker::allocate_resource_for_request_over_period).
+    /// Otherwise, the fewest free `Resource`s whose combined capacity can hold the batch are
+    /// allocated, largest first.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` contains copies of every `Resource` allocated, in the order they
+    ///     were selected
+    ///   - None, if `request` can't be satisfied during the given `NaivePeriod`, in which case
+    ///     nothing is allocated
+    pub fn allocate_resources_for_request_over_period(&mut self, request: &ResourceRequest,
+                                                      period: NaivePeriod)
+      -> Option<Vec<Resource>> {
+      self.allocate_resources_for_request_over_period_to_holder(request, period,
+                                                                AllocationHolder::Unknown)
+    }
+
+    /// Allocate enough `Resource`s to satisfy a `ResourceRequest` for a given `NaivePeriod`, as
+    /// with [allocate_resources_for_request_over_period](ResourceTracker::allocate_resources_for_request_over_period),
+    /// recording what they are allocated to.
+    ///
+    /// # Arguments
+    /// - `request`: The `ResourceRequest` to allocate `Resource`s for.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    /// - `holder`: The [AllocationHolder](AllocationHolder) the `Resource`s are allocated to.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` contains copies of every `Resource` allocated, in the order they
+    ///     were selected
+    ///   - None, if `request` can't be satisfied during the given `NaivePeriod`, in which case
+    ///     nothing is allocated
+    pub fn allocate_resources_for_request_over_period_to_holder(&mut self,
+                                                                request: &ResourceRequest,
+                                                                period: NaivePeriod,
+                                                                holder: AllocationHolder)
+      -> Option<Vec<Resource>> {
+      let ids: Vec<usize> = self.select_resources_for_request_over_period(request, period)?;
+
+      for next_id in ids.iter() {
+          self.record_allocation(*next_id, period, holder.clone());
       }
 
-      // Because of the above check, we know this will return an element, so the unwrap() should
-      // never panic here.
-      let mut resources: Vec<(&usize, &mut Resource)> = self.resources.iter_mut().collect::<Vec<(&usize, &mut Resource)> >();
-      resources.sort_by(|a, b| a.0.cmp(b.0));
+      // The ids were just retrieved from the map, and were all free, so these allocations always
+      // succeed.
+      Some(ids.into_iter().map(|id| {
+          self.resources.get_mut(&id).unwrap().allocate_over_period(period).unwrap().clone()
+      }).collect())
+    }
 
-      let resource_tuple: (&usize, &mut Resource) = resources.into_iter()
-          .filter(|hash_entry| {
-            hash_entry.1.resource_type == *resource_type
-              && !hash_entry.1.is_allocated_over_period(period)
-          }).next().unwrap();
+    fn record_allocation(&mut self, resource_id: usize, period: NaivePeriod,
+                         holder: AllocationHolder) {
+      self.allocations.push(Allocation {
+          resource_id,
+          period,
+          holder
+      });
+    }
 
-      let ret_val = resource_tuple.1.allocate_over_period(period);
+    /// Retrieve the allocations that stop a `ResourceRequest` from being satisfied over a
+    /// `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `request`: The `ResourceRequest` that can't be satisfied.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) over which it was requested.
+    ///
+    /// # Returns
+    /// - A `Vec` containing every [Allocation](Allocation) of a `Resource` that may be allocated
+    ///   to `request` that overlaps `period`, allowing for the turnaround of the `Resource`, in
+    ///   order of `id`, then start date.
+    pub fn get_allocations_blocking_request_over_period(&self, request: &ResourceRequest,
+                                                        period: NaivePeriod) -> Vec<Allocation> {
+        let mut blocking_ids: Vec<usize> = self.resources.values()
+          .filter(|res| request.is_permitted(res))
+          .map(|res| res.id)
+          .collect();
+        blocking_ids.sort();
+
+        blocking_ids.into_iter().flat_map(|id| {
+            let res: &Resource = &self.resources[&id];
+            let turnaround: Duration = res.turnaround().unwrap_or_else(Duration::zero);
+            let requested_use = NaivePeriod::from_start_duration(period.start,
+                                                                 period.duration() + turnaround);
 
-      // println!("Allocated resource of type: {:?}", ret_val.unwrap().resource_type);
+            // Only uses by phases are followed by a turnaround, and need one before them.
+            self.get_allocations_of_resource(id).into_iter().filter(move |allocation| {
+                match allocation.holder {
+                    AllocationHolder::Unavailable { .. } => allocation.period.intersects_with(period),
+                    _ => NaivePeriod::new(allocation.period.start, allocation.period.end + turnaround)
+                           .intersects_with(requested_use)
+                }
+            })
+        }).collect()
+    }
 
-      ret_val
+    /// Record that a phase had to wait for `Resource`s tracked by this `ResourceTracker`.
+    pub fn record_delay(&mut self, event: DelayEvent) {
+        self.delays.push(event);
+    }
+
+    /// Retrieve every [DelayEvent](super::bottlenecks::DelayEvent) recorded so far, in the order
+    /// they happened.
+    pub fn get_delays(&self) -> &[DelayEvent] {
+        &self.delays
+    }
+
+    /// Retrieve a copy of the `Resource` with a given `id`, including its allocated periods.
+    pub fn get_resource(&self, id: usize) -> Option<Resource> {
+        self.resources.get(&id).cloned()
+    }
+
+    /// Retrieve every `Resource` referred to by a `ResourceReference`.
+    ///
+    /// # Returns
+    /// - A `Vec` containing a copy of the `Resource` with the referenced `id`, or of each
+    ///   `Resource` with the referenced `name`, in order of `id`. It is empty if no `Resource` is
+    ///   referred to.
+    pub fn find_resources(&self, reference: &ResourceReference) -> Vec<Resource> {
+        self.get_resources_matching(|res| reference.refers_to(res))
+    }
+
+    /// Retrieve every `Resource` that isn't allocated at a specific `NaiveDateTime`.
+    ///
+    /// # Arguments
+    /// - `date`: The [NaiveDateTime](chrono::NaiveDateTime) to check.
+    ///
+    /// # Returns
+    /// - A `Vec` containing a copy of each `Resource` that is neither allocated, nor within its
+    ///   turnaround, at `date`, in order of `id`.
+    pub fn get_resources_free_at(&self, date: NaiveDateTime) -> Vec<Resource> {
+        self.get_resources_matching(|res| !res.is_allocated_at(date))
+    }
+
+    /// Retrieve every `Resource` that could be allocated over a `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) to check.
+    ///
+    /// # Returns
+    /// - A `Vec` containing a copy of each `Resource` that isn't allocated at any point during
+    ///   `period`, allowing for its turnaround, in order of `id`.
+    pub fn get_resources_free_over_period(&self, period: NaivePeriod) -> Vec<Resource> {
+        self.get_resources_matching(|res| !res.is_allocated_over_period(period))
+    }
+
+    fn get_resources_matching<P>(&self, predicate: P) -> Vec<Resource>
+      where P: Fn(&Resource) -> bool {
+        let mut matching: Vec<Resource> = self.resources.values()
+          .filter(|res| predicate(res))
+          .cloned()
+          .collect();
+        matching.sort_by_key(|res| res.id);
+
+        matching
+    }
+
+    /// Retrieve every allocation of a `Resource`, along with what it is allocated to.
+    ///
+    /// # Arguments
+    /// - `id`: The `id` of the `Resource`.
+    ///
+    /// # Returns
+    /// - A `Vec` containing an [Allocation](Allocation) for each allocated period of the
+    ///   `Resource`, in order of start date. It is empty if the `Resource` isn't tracked.
+    pub fn get_allocations_of_resource(&self, id: usize) -> Vec<Allocation> {
+        let res: &Resource = match self.resources.get(&id) {
+            Some(x) => x,
+            None => return vec![]
+        };
+
+        res.allocated_periods.iter().map(|period| {
+            let holder: AllocationHolder = self.allocations.iter()
+              .find(|allocation| allocation.resource_id == id && allocation.period == *period)
+              .map_or(AllocationHolder::Unknown, |allocation| allocation.holder.clone());
+
+            Allocation {
+                resource_id: id,
+                period: *period,
+                holder
+            }
+        }).collect()
+    }
+
+    /// Retrieve the allocation that holds a `Resource` at a specific `NaiveDateTime`.
+    ///
+    /// # Arguments
+    /// - `id`: The `id` of the `Resource`.
+    /// - `date`: The [NaiveDateTime](chrono::NaiveDateTime) to check.
+    ///
+    /// # Returns
+    /// - An `Option` containing the [Allocation](Allocation) whose period includes `date`, or
+    ///   `None` if the `Resource` isn't allocated then. A `Resource` within its turnaround isn't
+    ///   held by anything, even though it can't be allocated.
+    pub fn get_allocation_of_resource_at(&self, id: usize,
+                                         date: NaiveDateTime) -> Option<Allocation> {
+        self.get_allocations_of_resource(id).into_iter().find(|allocation| {
+            allocation.period.start <= date && date <= allocation.period.end
+        })
     }
 
     /// Retrieve all `Resource` objects tracked by this `ResourceTracker`.
     ///
     /// # Returns
     /// - A `Vec` containing a copy of all `Resource` objects that are tracked by this
-    ///   `ResourceTracker`.
+    ///   `ResourceTracker`, in order of `id`.
     pub fn get_all_tracked_resources(&self) -> Vec<Resource> {
-        self.resources.clone().into_iter().map(|tuple| tuple.1).collect()
+        self.get_resources_matching(|_res| true)
     }
 }
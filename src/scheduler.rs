@@ -0,0 +1,851 @@
+use chrono::{Duration, NaiveDateTime, Timelike};
+
+use chrono_period::NaivePeriod;
+
+use serde::{Serialize, Deserialize};
+
+use super::bottlenecks::{rank_bottlenecks, DelayEvent};
+use super::calendar::{get_earliest_start_date_outside_blackouts, Blackout, WorkingCalendar};
+use super::dependencies::{get_recipe_order, PhaseGraph};
+use super::error::Error;
+use super::optimizer::Optimizing;
+use super::phases::{PhaseInstance, PhaseInstanceSpec, ProductionPhaseTemplate};
+use super::recipes::{Recipe, RecipeSpec};
+use super::resources::{AllocationHolder, Resource, ResourceReference, ResourceRequest, ResourceTracker,
+                       ResourceType};
+use super::util::convert_string_to_duration;
+use super::volume::Volume;
+
+/// A strategy for turning the [RecipeSpec](super::recipes::RecipeSpec)s of a schedule into
+/// scheduled [Recipe](super::recipes::Recipe)s.
+pub trait Scheduler {
+    /// Schedule every recipe, allocating the `Resource`s needed by each phase.
+    ///
+    /// # Arguments
+    /// - `recipe_specs`: The `RecipeSpec`s to schedule. Each must already have a start date.
+    /// - `templates`: The `ProductionPhaseTemplate`s that the phases of the recipes refer to.
+    /// - `tracker`: The `ResourceTracker` from which `Resource`s are allocated.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the scheduled `Recipe`s, in the same order as
+    ///   `recipe_specs`, or an [Error](super::error::Error) describing the first phase that could
+    ///   not be scheduled.
+    fn schedule(&self, recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                tracker: &mut ResourceTracker) -> Result<Vec<Recipe>, Error>;
+}
+
+/// The [Scheduler](Scheduler)s that can be selected, either from the `scheduler` entry of the
+/// `timeline` block of a BPD file, or from the command line.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all="kebab-case")]
+pub enum SchedulerKind {
+    /// See [GreedySequential](GreedySequential).
+    #[default]
+    GreedySequential,
+
+    /// See [ShortestTotalDurationFirst](ShortestTotalDurationFirst).
+    ShortestTotalDurationFirst,
+
+    /// See [Optimizing](super::optimizer::Optimizing).
+    Optimizing
+}
+
+impl SchedulerKind {
+    /// The names of every `SchedulerKind`, as written within a BPD file.
+    pub const NAMES: [&'static str; 3] = ["greedy-sequential", "shortest-total-duration-first",
+                                          "optimizing"];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            SchedulerKind::GreedySequential => SchedulerKind::NAMES[0],
+            SchedulerKind::ShortestTotalDurationFirst => SchedulerKind::NAMES[1],
+            SchedulerKind::Optimizing => SchedulerKind::NAMES[2]
+        }
+    }
+
+    /// Retrieve the `SchedulerKind` with a given name, if there is one.
+    pub fn from_name(name: &str) -> Option<SchedulerKind> {
+        match name {
+            "greedy-sequential" => Some(SchedulerKind::GreedySequential),
+            "shortest-total-duration-first" => Some(SchedulerKind::ShortestTotalDurationFirst),
+            "optimizing" => Some(SchedulerKind::Optimizing),
+            _ => None
+        }
+    }
+
+    /// Retrieve the [Scheduler](Scheduler) implementing this `SchedulerKind`, with its default
+    /// configuration.
+    pub fn get_scheduler(&self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::GreedySequential => Box::new(GreedySequential),
+            SchedulerKind::ShortestTotalDurationFirst => Box::new(ShortestTotalDurationFirst),
+            SchedulerKind::Optimizing => Box::new(Optimizing::default())
+        }
+    }
+}
+
+/// Schedules recipes one at a time, in the order they are given, placing each phase as early as
+/// its dependencies and resources allow.
+///
+/// A recipe that depends upon a phase of a later recipe is scheduled after that recipe instead.
+pub struct GreedySequential;
+
+impl Scheduler for GreedySequential {
+    fn schedule(&self, recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                tracker: &mut ResourceTracker) -> Result<Vec<Recipe>, Error> {
+        let given_order: Vec<usize> = (0..recipe_specs.len()).collect();
+
+        schedule_in_order(recipe_specs, templates, tracker, &given_order)
+    }
+}
+
+/// Schedules recipes one at a time, in order of the total duration of their phases, shortest
+/// first, placing each phase as early as its dependencies and resources allow.
+///
+/// Recipes with the same total duration are scheduled in the order they are given. As with
+/// [GreedySequential](GreedySequential), a recipe that depends upon a phase of another recipe is
+/// always scheduled after it.
+pub struct ShortestTotalDurationFirst;
+
+impl Scheduler for ShortestTotalDurationFirst {
+    fn schedule(&self, recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                tracker: &mut ResourceTracker) -> Result<Vec<Recipe>, Error> {
+        let preferred_order: Vec<usize> = get_shortest_first_order(recipe_specs, templates)?;
+
+        schedule_in_order(recipe_specs, templates, tracker, &preferred_order)
+    }
+}
+
+/// Schedule every recipe, one at a time, placing each phase as early as its dependencies and
+/// resources allow.
+///
+/// # Arguments
+/// - `recipe_specs`: The `RecipeSpec`s to schedule. Each must already have a start date.
+/// - `templates`: The `ProductionPhaseTemplate`s that the phases of the recipes refer to.
+/// - `tracker`: The `ResourceTracker` from which `Resource`s are allocated.
+/// - `preferred_order`: The indices of the recipes, in the order they should be scheduled. A
+///   recipe that depends upon a phase of another recipe is always scheduled after it, regardless.
+///
+/// # Returns
+/// - A `Result` containing either the scheduled `Recipe`s, in the same order as `recipe_specs`,
+///   or an [Error](super::error::Error) describing the first phase that could not be scheduled.
+pub fn schedule_in_order(recipe_specs: &[RecipeSpec], templates: &[ProductionPhaseTemplate],
+                         tracker: &mut ResourceTracker,
+                         preferred_order: &[usize]) -> Result<Vec<Recipe>, Error> {
+    let mut state = SchedulingState {
+        recipe_specs,
+        templates,
+        tracker,
+        last_id_used: 0,
+        recipes: vec![None; recipe_specs.len()],
+        graphs: vec![None; recipe_specs.len()]
+    };
+
+    for recipe_index in get_recipe_order(recipe_specs, preferred_order)? {
+        state.schedule_recipe(recipe_index)?;
+    }
+
+    Ok(state.finish())
+}
+
+/// Retrieve the indices of the recipes, in order of the total duration of their phases, shortest
+/// first.
+pub fn get_shortest_first_order(recipe_specs: &[RecipeSpec],
+                                templates: &[ProductionPhaseTemplate]) -> Result<Vec<usize>, Error> {
+    let mut total_durations: Vec<Duration> = vec![];
+    for next_recipe_spec in recipe_specs {
+        let mut total_duration: Duration = Duration::zero();
+        for phase_index in 0..next_recipe_spec.phase_specs.len() {
+            let template = find_template(next_recipe_spec, phase_index, templates)?;
+            total_duration = total_duration
+                             + get_phase_duration(next_recipe_spec, phase_index, template)?;
+        }
+
+        total_durations.push(total_duration);
+    }
+
+    let mut order: Vec<usize> = (0..recipe_specs.len()).collect();
+    order.sort_by_key(|index| total_durations[*index]);
+
+    Ok(order)
+}
+
+/// Find the template of a phase of a recipe.
+pub fn find_template<'a>(recipe_spec: &RecipeSpec, phase_index: usize,
+                         templates: &'a [ProductionPhaseTemplate])
+  -> Result<&'a ProductionPhaseTemplate, Error> {
+    let template_id: &str = &recipe_spec.phase_specs[phase_index].template[..];
+    match templates.iter().find(|template| template.id == template_id) {
+        Some(x) => Ok(x),
+        None => Err(Error::UnknownTemplate {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            template: template_id.to_string()
+        })
+    }
+}
+
+/// Retrieve the duration of a phase of a recipe.
+///
+/// If the duration is specified in the phase, that duration is used. Otherwise, the default
+/// duration of its template is used. If neither is given, the phase lasts for a single day.
+///
+/// # Returns
+/// - A `Result` containing either the [Duration](chrono::Duration) of the phase, or an
+///   [Error](super::error::Error) if a duration is given, but can't be parsed.
+pub fn get_phase_duration(recipe_spec: &RecipeSpec, phase_index: usize,
+                          template: &ProductionPhaseTemplate) -> Result<Duration, Error> {
+    let phase_spec: &PhaseInstanceSpec = &recipe_spec.phase_specs[phase_index];
+    let duration_string: &str = match phase_spec.duration_string.is_empty() {
+        true => &template.default_duration_string[..],
+        false => &phase_spec.duration_string[..]
+    };
+
+    match convert_string_to_duration(duration_string) {
+        Some(x) => Ok(x),
+
+        // Default to a single day if nothing else works
+        None if duration_string.is_empty() => Ok(Duration::days(1)),
+        None => Err(Error::UnparseableDuration {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            duration: duration_string.to_string()
+        })
+    }
+}
+
+/// Retrieve the `ResourceRequest`s of a phase of a recipe, restricted to the `Resource`s the phase
+/// is pinned to, and excluding those it excludes.
+///
+/// # Arguments
+/// - `recipe_spec`: The `RecipeSpec` containing the phase.
+/// - `phase_index`: The index of the phase within the recipe's `phases` block.
+/// - `template`: The `ProductionPhaseTemplate` of the phase, giving the `ResourceType`s needed.
+/// - `batch_size`: The `Volume` of the recipe's batch, if known.
+/// - `tracker`: The `ResourceTracker` whose `Resource`s the pins and exclusions refer to.
+///
+/// # Returns
+/// - A `Result` containing either the `ResourceRequest`s, grouped as with
+///   [group](super::resources::ResourceRequest::group), or an [Error](super::error::Error) if
+///   the phase refers to a `Resource` that isn't tracked, or is pinned to a `Resource` of a type
+///   its template doesn't need.
+fn get_resource_requests(recipe_spec: &RecipeSpec, phase_index: usize,
+                         template: &ProductionPhaseTemplate, batch_size: Option<Volume>,
+                         tracker: &ResourceTracker) -> Result<Vec<ResourceRequest>, Error> {
+    let phase_spec: &PhaseInstanceSpec = &recipe_spec.phase_specs[phase_index];
+    let find_resources = |reference: &ResourceReference| -> Result<Vec<Resource>, Error> {
+        let found: Vec<Resource> = tracker.find_resources(reference);
+        match found.is_empty() {
+            true => Err(Error::UnknownResourceReference {
+                recipe: recipe_spec.name.clone(),
+                phase_index,
+                resource: reference.to_string()
+            }),
+            false => Ok(found)
+        }
+    };
+
+    let mut pinned: Vec<Resource> = vec![];
+    for next_reference in &phase_spec.pinned_resources {
+        pinned.extend(find_resources(next_reference)?);
+    }
+
+    let mut excluded_ids: Vec<usize> = vec![];
+    for next_reference in &phase_spec.excluded_resources {
+        excluded_ids.extend(find_resources(next_reference)?.into_iter().map(|res| res.id));
+    }
+
+    let mut requests: Vec<ResourceRequest> = ResourceRequest::group(&template.resources_needed,
+                                                                    batch_size);
+
+    let unneeded: Option<&Resource> = pinned.iter().find(|res| {
+        !requests.iter().any(|request| request.resource_type == res.resource_type)
+    });
+    if let Some(res) = unneeded {
+        return Err(Error::UnsatisfiablePin {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            resources: vec![res.name.clone()]
+        });
+    }
+
+    for next_request in requests.iter_mut() {
+        next_request.pinned_ids = pinned.iter()
+                                        .filter(|res| res.resource_type == next_request.resource_type)
+                                        .map(|res| res.id)
+                                        .collect();
+        next_request.excluded_ids = excluded_ids.clone();
+    }
+
+    Ok(requests)
+}
+
+/// Retrieve the [Error](super::error::Error) describing why a `ResourceRequest` of a phase can
+/// never be satisfied: either the `Resource`s it is pinned to can't satisfy it, or no `Resource`
+/// of its type can.
+fn get_unsatisfiable_request_error(recipe_spec: &RecipeSpec, phase_index: usize,
+                                   request: &ResourceRequest, tracker: &ResourceTracker) -> Error {
+    match request.pinned_ids.is_empty() {
+        true => Error::UnsatisfiableResource {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            resource_type: request.resource_type.clone()
+        },
+        false => Error::UnsatisfiablePin {
+            recipe: recipe_spec.name.clone(),
+            phase_index,
+            resources: request.pinned_ids.iter()
+                                         .filter_map(|id| tracker.get_resource(*id))
+                                         .map(|res| res.name)
+                                         .collect()
+        }
+    }
+}
+
+/// The constraints, beyond the dependencies between phases, within which a recipe is scheduled.
+struct RecipeConstraints {
+    batch_size: Option<Volume>,
+
+    /// The earliest date at which any phase may start: the later of the `start` and `notBefore`
+    /// dates of the recipe.
+    earliest_start_date: NaiveDateTime,
+
+    /// The latest date at which the first phase may start, from the `notAfter` date.
+    latest_start_date: Option<NaiveDateTime>,
+
+    /// The date by which every phase must have ended, from the `readyBy` date.
+    ready_by_date: Option<NaiveDateTime>,
+    blackouts: Vec<Blackout>,
+
+    /// The times at which hands-on phases may start, or `None` if they may start at any time.
+    working_calendar: Option<WorkingCalendar>
+}
+
+impl RecipeConstraints {
+    fn new(recipe_spec: &RecipeSpec) -> Result<Self, Error> {
+        let get_unparseable_date_error = |date: &Option<String>| Error::UnparseableDate {
+            recipe: recipe_spec.name.clone(),
+            phase_index: None,
+            date: date.clone().unwrap_or_default()
+        };
+
+        let start_date = recipe_spec.start_date()
+                                    .map_err(|_e| get_unparseable_date_error(&recipe_spec.start_string))?;
+        let not_before_date = recipe_spec.not_before_date()
+                                         .map_err(|_e| get_unparseable_date_error(&recipe_spec.not_before_string))?;
+        let latest_start_date = recipe_spec.not_after_date()
+                                           .map_err(|_e| get_unparseable_date_error(&recipe_spec.not_after_string))?;
+        let ready_by_date = recipe_spec.ready_by_date()
+                                       .map_err(|_e| get_unparseable_date_error(&recipe_spec.ready_by_string))?;
+
+        let batch_size: Option<Volume> = match recipe_spec.batch_size() {
+            Ok(x) => x,
+            Err(batch_size) => return Err(Error::UnparseableBatchSize {
+                recipe: recipe_spec.name.clone(),
+                batch_size
+            })
+        };
+
+        let mut blackouts: Vec<Blackout> = vec![];
+        for next_blackout_spec in &recipe_spec.blackouts {
+            match next_blackout_spec.blackout() {
+                Ok(x) => blackouts.push(x),
+                Err(date) => return Err(get_unparseable_date_error(&Some(date)))
+            }
+        }
+
+        let working_calendar: Option<WorkingCalendar> = match &recipe_spec.working_calendar {
+            Some(calendar_spec) => match calendar_spec.calendar() {
+                Ok(x) => Some(x),
+                Err(date) => return Err(get_unparseable_date_error(&Some(date)))
+            },
+            None => None
+        };
+
+        Ok(RecipeConstraints {
+            batch_size,
+            earliest_start_date: match not_before_date {
+                Some(x) if x > start_date => x,
+                _ => start_date
+            },
+            latest_start_date,
+            ready_by_date,
+            blackouts,
+            working_calendar
+        })
+    }
+}
+
+/// The outcome of scheduling the phases of a recipe from a particular start date.
+struct TrialOutcome {
+    first_start_date: NaiveDateTime,
+    end_date: NaiveDateTime,
+
+    /// The delays the phases met waiting for resources.
+    delays: Vec<DelayEvent>
+}
+
+impl TrialOutcome {
+    fn new(phases: &[PhaseInstance], start_date: NaiveDateTime,
+           delays: Vec<DelayEvent>) -> Self {
+        TrialOutcome {
+            first_start_date: phases.iter()
+                                    .map(|phase| phase.start_date)
+                                    .min()
+                                    .unwrap_or(start_date),
+            end_date: phases.iter()
+                            .map(|phase| phase.start_date + phase.duration)
+                            .max()
+                            .unwrap_or(start_date),
+            delays
+        }
+    }
+
+    /// Retrieve how much earlier the recipe would need to start to be within its windows, or
+    /// zero (or less) if it's within them already.
+    fn get_overrun(&self, constraints: &RecipeConstraints) -> Duration {
+        let past_ready_by: Duration = match constraints.ready_by_date {
+            Some(x) => self.end_date - x,
+            None => Duration::zero()
+        };
+
+        let past_latest_start: Duration = match constraints.latest_start_date {
+            Some(x) => self.first_start_date - x,
+            None => Duration::zero()
+        };
+
+        std::cmp::max(past_ready_by, past_latest_start)
+    }
+
+    /// Retrieve the [Error](super::error::Error) describing the window this outcome misses.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` of the recipe.
+    /// - `constraints`: The `RecipeConstraints` of the recipe.
+    /// - `tracker`: The `ResourceTracker` the recipe was scheduled against, from which the names
+    ///   of the resources that blocked it are retrieved.
+    fn get_error(&self, recipe_spec: &RecipeSpec, constraints: &RecipeConstraints,
+                 tracker: &ResourceTracker) -> Error {
+        let bottleneck: Option<ResourceType> = get_bottleneck(&self.delays);
+        let blocking_resources: Vec<String> = match &bottleneck {
+            Some(x) => get_blocking_resources(&self.delays, x, tracker),
+            None => vec![]
+        };
+
+        match constraints.ready_by_date {
+            Some(x) if self.end_date > x => Error::DeadlineMissed {
+                recipe: recipe_spec.name.clone(),
+                ready_by: recipe_spec.ready_by_string.clone().unwrap_or_default(),
+                bottleneck,
+                blocking_resources
+            },
+            _ => Error::LateStart {
+                recipe: recipe_spec.name.clone(),
+                not_after: recipe_spec.not_after_string.clone().unwrap_or_default(),
+                bottleneck,
+                blocking_resources
+            }
+        }
+    }
+}
+
+/// Retrieve the `ResourceType` responsible for the longest total delay, if there were any delays.
+/// The first type to delay the recipe wins a tie.
+fn get_bottleneck(delays: &[DelayEvent]) -> Option<ResourceType> {
+    rank_bottlenecks(delays).into_iter().next().map(|bottleneck| bottleneck.resource_type)
+}
+
+/// Retrieve the names of the resources of a `ResourceType` whose allocations delayed a recipe, in
+/// order of id. A resource no longer tracked is given by its id instead.
+fn get_blocking_resources(delays: &[DelayEvent], resource_type: &ResourceType,
+                          tracker: &ResourceTracker) -> Vec<String> {
+    let mut ids: Vec<usize> = delays.iter()
+                                    .filter(|delay| delay.resource_type == *resource_type)
+                                    .flat_map(|delay| delay.blocking.iter())
+                                    .map(|allocation| allocation.resource_id)
+                                    .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+       .map(|id| tracker.get_resource(id).map_or(id.to_string(), |resource| resource.name))
+       .collect()
+}
+
+/// The state shared while scheduling the recipes of a schedule, one recipe at a time.
+struct SchedulingState<'a> {
+    recipe_specs: &'a [RecipeSpec],
+    templates: &'a [ProductionPhaseTemplate],
+    tracker: &'a mut ResourceTracker,
+    last_id_used: usize,
+
+    /// The recipes scheduled so far, indexed by their position within `recipe_specs`.
+    recipes: Vec<Option<Recipe>>,
+
+    /// The `PhaseGraph`s of the recipes scheduled so far, indexed in the same way as `recipes`.
+    graphs: Vec<Option<PhaseGraph>>
+}
+
+impl<'a> SchedulingState<'a> {
+    fn get_next_id(&mut self) -> usize {
+        self.last_id_used += 1;
+
+        self.last_id_used
+    }
+
+    /// Schedule the recipe at a given index within `recipe_specs`. Every recipe it depends upon
+    /// must already have been scheduled.
+    fn schedule_recipe(&mut self, recipe_index: usize) -> Result<(), Error> {
+        let recipe_spec: &RecipeSpec = &self.recipe_specs[recipe_index];
+        let constraints: RecipeConstraints = RecipeConstraints::new(recipe_spec)?;
+        let graph: PhaseGraph = PhaseGraph::new(recipe_spec, self.recipe_specs, self.templates)?;
+
+        let recipe_start_date: NaiveDateTime = match constraints.ready_by_date {
+            Some(_) => self.find_latest_start_date(recipe_spec, &graph, &constraints)?,
+            None => constraints.earliest_start_date
+        };
+
+        let mut recipe_template: Recipe = Recipe {
+            id: self.get_next_id(),
+            name: recipe_spec.name.clone(),
+            color: recipe_spec.color_hex.clone(),
+            phases: vec![],
+            start_date: recipe_start_date,
+            batch_size: constraints.batch_size
+        };
+
+        let delay_count: usize = self.tracker.get_delays().len();
+        recipe_template.phases = self.schedule_phases(recipe_spec, &graph, recipe_start_date,
+                                                      &constraints)?;
+
+        // A recipe with a `readyBy` date has already been placed within its windows, if it can be.
+        // Otherwise, it starts as early as it can, which might still be too late.
+        let outcome = TrialOutcome::new(&recipe_template.phases, recipe_start_date,
+                                        self.tracker.get_delays()[delay_count..].to_vec());
+        if outcome.get_overrun(&constraints) > Duration::zero() {
+            return Err(outcome.get_error(recipe_spec, &constraints, self.tracker));
+        }
+
+        self.recipes[recipe_index] = Some(recipe_template);
+        self.graphs[recipe_index] = Some(graph);
+
+        Ok(())
+    }
+
+    /// Find the latest date at which a recipe with a `readyBy` date can start, such that all of its
+    /// phases have ended by that date, and its first phase starts by its `notAfter` date, if any.
+    ///
+    /// Starting from the date the recipe must be ready by, the start date is moved earlier by
+    /// however long the recipe overruns, until it's ready in time. The gap between the latest
+    /// start found to be too late and the earliest found to be in time is then narrowed down to
+    /// the hour.
+    ///
+    /// # Arguments
+    /// - `recipe_spec` : The `RecipeSpec` whose phases should be scheduled.
+    /// - `graph` : The [PhaseGraph](super::dependencies::PhaseGraph) of the recipe.
+    /// - `constraints` : The `RecipeConstraints` of the recipe.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the latest start date found, or an
+    ///   [Error](super::error::Error) if the recipe can't be ready in time, even when starting on
+    ///   its earliest start date.
+    ///
+    /// # Notes
+    /// - The search assumes that if a start date leaves the recipe ready in time, so does any
+    ///   earlier one. Every phase starts as soon as its dependencies, resources, blackouts,
+    ///   working time and downtime allow, and none of those can be later for an earlier start. A
+    ///   start within a blackout is moved to its end, so the starts before a blackout and those
+    ///   within it are no more likely to be late than those after it.
+    /// - Should a choice of resources ever break that assumption, the start date returned has
+    ///   still been tried, and is in time, but a later one might also have been.
+    fn find_latest_start_date(&mut self, recipe_spec: &RecipeSpec, graph: &PhaseGraph,
+                              constraints: &RecipeConstraints) -> Result<NaiveDateTime, Error> {
+        let earliest_start_date: NaiveDateTime = constraints.earliest_start_date;
+        let ready_by_date: NaiveDateTime = constraints.ready_by_date.unwrap();
+
+        let mut too_late_start_date: Option<NaiveDateTime> = None;
+        let mut start_date: NaiveDateTime = match constraints.latest_start_date {
+            Some(x) if x < ready_by_date => x,
+            _ => ready_by_date
+        };
+
+        let mut in_time_start_date: NaiveDateTime = loop {
+            if start_date < earliest_start_date {
+                start_date = earliest_start_date;
+            }
+
+            let outcome = self.try_schedule_phases(recipe_spec, graph, start_date, constraints)?;
+            let overrun: Duration = outcome.get_overrun(constraints);
+            if overrun <= Duration::zero() {
+                break start_date;
+            }
+
+            if start_date == earliest_start_date {
+                return Err(outcome.get_error(recipe_spec, constraints, self.tracker));
+            }
+
+            too_late_start_date = Some(start_date);
+            start_date -= overrun;
+        };
+
+        if let Some(mut too_late) = too_late_start_date {
+            while too_late - in_time_start_date > Duration::hours(1) {
+                let middle: NaiveDateTime = in_time_start_date + (too_late - in_time_start_date) / 2;
+                match self.is_in_time(recipe_spec, graph, middle, constraints)? {
+                    true => in_time_start_date = middle,
+                    false => too_late = middle
+                }
+            }
+        }
+
+        // Prefer to start on the hour, if that still leaves the recipe ready in time.
+        let on_the_hour: Option<NaiveDateTime> =
+          in_time_start_date.date().and_hms_opt(in_time_start_date.hour(), 0, 0);
+        if let Some(x) = on_the_hour {
+            if x >= earliest_start_date && x < in_time_start_date
+                 && self.is_in_time(recipe_spec, graph, x, constraints)? {
+                in_time_start_date = x;
+            }
+        }
+
+        Ok(in_time_start_date)
+    }
+
+    /// Determine if a recipe starting at a given date would be scheduled within its windows.
+    fn is_in_time(&mut self, recipe_spec: &RecipeSpec, graph: &PhaseGraph,
+                  start_date: NaiveDateTime, constraints: &RecipeConstraints) -> Result<bool, Error> {
+        let outcome = self.try_schedule_phases(recipe_spec, graph, start_date, constraints)?;
+
+        Ok(outcome.get_overrun(constraints) <= Duration::zero())
+    }
+
+    /// Schedule the phases of a recipe in the same way as `schedule_phases`, but without
+    /// allocating any resources, or using up any ids.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `TrialOutcome` of scheduling the phases, or an
+    ///   [Error](super::error::Error) describing the first phase that could not be scheduled.
+    fn try_schedule_phases(&mut self, recipe_spec: &RecipeSpec, graph: &PhaseGraph,
+                           start_date: NaiveDateTime, constraints: &RecipeConstraints)
+      -> Result<TrialOutcome, Error> {
+        let saved_tracker: ResourceTracker = self.tracker.clone();
+        let saved_last_id_used: usize = self.last_id_used;
+
+        let result = self.schedule_phases(recipe_spec, graph, start_date, constraints);
+
+        let delays: Vec<DelayEvent> = self.tracker.get_delays()[saved_tracker.get_delays().len()..]
+                                          .to_vec();
+        *self.tracker = saved_tracker;
+        self.last_id_used = saved_last_id_used;
+
+        Ok(TrialOutcome::new(&result?, start_date, delays))
+    }
+
+    /// Schedule all `PhaseInstance`s of a recipe.
+    ///
+    /// # Arguments
+    /// - `recipe_spec` : The `RecipeSpec` whose phases should be scheduled.
+    /// - `graph` : The [PhaseGraph](super::dependencies::PhaseGraph) of the recipe, giving the
+    ///   order in which its phases are scheduled, and the phases each depends upon.
+    /// - `start_date` : The [NaiveDateTime](chrono::NaiveDateTime) at which the recipe should
+    ///   begin. Phases that don't depend upon any other phase start on this date, or as soon
+    ///   after it as their resources are available, and their blackouts have passed.
+    /// - `constraints` : The `RecipeConstraints` of the recipe. Only resources large enough to
+    ///   hold its batch will be allocated. Its windows aren't checked here.
+    ///
+    /// # Returns
+    /// - A `Result` containing either a `Vec` of `PhaseInstance` objects, in the same order as the
+    ///   recipe's `phases` block, or an [Error](super::error::Error) describing the first phase
+    ///   that could not be scheduled. Dependencies upon phases of other recipes are not yet added
+    ///   to those phases.
+    fn schedule_phases(&mut self, recipe_spec: &RecipeSpec, graph: &PhaseGraph,
+                       start_date: NaiveDateTime, constraints: &RecipeConstraints)
+      -> Result<Vec<PhaseInstance>, Error> {
+        // The scheduled phases, indexed by their position within the recipe's `phases` block.
+        let mut scheduled: Vec<Option<PhaseInstance>> = vec![None; recipe_spec.phase_specs.len()];
+
+        for phase_index in graph.get_phase_order().iter().cloned() {
+            let next_spec: &PhaseInstanceSpec = &recipe_spec.phase_specs[phase_index];
+            let id: usize = self.get_next_id();
+
+            // The production phase template we're going to use to construct this instance.
+            let template: &ProductionPhaseTemplate = find_template(recipe_spec, phase_index,
+                                                                   self.templates)?;
+
+            let duration: Duration = get_phase_duration(recipe_spec, phase_index, template)?;
+
+            // If the description is specified in the spec, use that description.
+            // Otherwise, use the description by looking up from the template.
+            let description: String = match next_spec.description.is_empty() {
+                true => template.description.clone(),
+                false => next_spec.description.clone()
+            };
+
+            // The phase can't start until every phase it depends upon has reached the point it
+            // depends upon. Since phases and recipes are scheduled in dependency order, those
+            // phases have already been scheduled.
+            let mut next_start_date: NaiveDateTime = start_date;
+            for next_dependency in graph.get_dependencies_of(phase_index) {
+                let depended_upon: Option<&PhaseInstance> = match next_dependency.recipe_index {
+                    Some(other_index) => self.recipes[other_index].as_ref()
+                      .map(|recipe| &recipe.phases[next_dependency.phase_index]),
+                    None => scheduled[next_dependency.phase_index].as_ref()
+                };
+
+                if let Some(phase) = depended_upon {
+                    let earliest_start_date = next_dependency.get_earliest_start_date(phase.start_date,
+                                                                                      phase.duration);
+                    if earliest_start_date > next_start_date {
+                        next_start_date = earliest_start_date;
+                    }
+                }
+            }
+
+            let mut resources_used : Vec<Resource> = vec![];
+
+            let requests: Vec<ResourceRequest> = get_resource_requests(recipe_spec, phase_index,
+                                                                       template,
+                                                                       constraints.batch_size,
+                                                                       self.tracker)?;
+
+            // We need to look through all the resources to determine if we have to push the start
+            // date back due to resource allocation constraints. Pushing the start date back for
+            // one resource might make another unavailable, or land within a blackout, or outside
+            // working time, so this repeats until every resource is free over the same period,
+            // at a time that every window allows.
+            let mut is_start_date_settled = false;
+            while !is_start_date_settled {
+                is_start_date_settled = true;
+
+                let allowed_start_date = get_earliest_start_date_outside_blackouts(&constraints.blackouts,
+                                                                                   &template.id[..],
+                                                                                   next_start_date,
+                                                                                   duration);
+                if allowed_start_date > next_start_date {
+                    next_start_date = allowed_start_date;
+                    is_start_date_settled = false;
+                }
+
+                // Hands-on phases also have to start while people are at work.
+                if let (true, Some(calendar)) = (template.is_hands_on, &constraints.working_calendar) {
+                    match calendar.get_next_working_time(next_start_date) {
+                        Some(x) if x > next_start_date => {
+                            next_start_date = x;
+                            is_start_date_settled = false;
+                        },
+                        Some(_) => (),
+                        None => return Err(Error::NoWorkingTime {
+                            recipe: recipe_spec.name.clone(),
+                            phase_index
+                        })
+                    }
+                }
+
+                for next_request in requests.iter() {
+                    let requested_period = NaivePeriod::from_start_duration(next_start_date,
+                                                                            duration);
+
+                    let result_start_date = match self.tracker
+                      .get_next_available_resource_date_for_request_over_period(next_request,
+                                                                                requested_period) {
+                      Some(date) => date,
+                      None => return Err(get_unsatisfiable_request_error(recipe_spec, phase_index,
+                                                                         next_request,
+                                                                         self.tracker))
+                    };
+
+                    // If we can't allocate a resource in the given timeframe, we need to push
+                    // back the start date of the phase.
+                    if result_start_date > next_start_date {
+                        let blocking = self.tracker
+                          .get_allocations_blocking_request_over_period(next_request,
+                                                                        requested_period);
+                        self.tracker.record_delay(DelayEvent {
+                            recipe: recipe_spec.name.clone(),
+                            phase_id: id,
+                            phase: description.clone(),
+                            resource_type: next_request.resource_type.clone(),
+                            requested_start: next_start_date,
+                            actual_start: result_start_date,
+                            blocking
+                        });
+
+                        next_start_date = result_start_date;
+                        is_start_date_settled = false;
+                    }
+                }
+            }
+
+            for next_request in requests {
+                // Allocate the resources. If more than one resource of the type is needed, or the
+                // batch doesn't fit in a single resource, this allocates several of the same type.
+                let allocation_period = NaivePeriod::from_start_duration(next_start_date,
+                                                                         duration);
+
+                let holder = AllocationHolder::Phase {
+                    recipe: recipe_spec.name.clone(),
+                    phase_id: id,
+                    description: description.clone()
+                };
+
+                let allocated_resources
+                  = match self.tracker.allocate_resources_for_request_over_period_to_holder(&next_request,
+                                                                                            allocation_period,
+                                                                                            holder) {
+                      Some(x) => x,
+                      None => return Err(get_unsatisfiable_request_error(recipe_spec, phase_index,
+                                                                         &next_request,
+                                                                         self.tracker))
+                  };
+
+                // Put the allocated resources into the vector
+                resources_used.extend(allocated_resources);
+            }
+
+            let mut phase = PhaseInstance::new(id, description, recipe_spec.color_hex.clone(),
+                                               duration, next_start_date, resources_used);
+            phase.is_hands_on = template.is_hands_on;
+
+            scheduled[phase_index] = Some(phase);
+        }
+
+        // This is a weird nuance of pla that tasks X that are dependent on some task Y are
+        // actually defined in the definition of Y, not X. It basically means you have to specify
+        // that there will be defined a task with id X, but that task hasn't been defined yet. So,
+        // each dependency is added to the phase depended upon, rather than the dependent phase.
+        for phase_index in graph.get_phase_order().iter().cloned() {
+            let dependent_id: usize = scheduled[phase_index].as_ref().unwrap().id;
+            for next_dependency in graph.get_dependencies_of(phase_index) {
+                if next_dependency.recipe_index.is_none() {
+                    scheduled[next_dependency.phase_index].as_mut().unwrap()
+                                                          .add_dependency(dependent_id);
+                }
+            }
+        }
+
+        Ok(scheduled.into_iter().map(|x| x.unwrap()).collect())
+    }
+
+    /// Retrieve the scheduled recipes, once every recipe has been scheduled, adding the
+    /// dependencies upon phases of other recipes to those phases.
+    fn finish(self) -> Vec<Recipe> {
+        let mut recipes: Vec<Recipe> = self.recipes.into_iter().map(|x| x.unwrap()).collect();
+
+        // Dependencies upon phases of other recipes are added to the phase depended upon, in the
+        // same way as dependencies within a recipe (see `schedule_phases`).
+        for (recipe_index, graph) in self.graphs.iter().enumerate() {
+            let graph: &PhaseGraph = graph.as_ref().unwrap();
+            for phase_index in 0..recipes[recipe_index].phases.len() {
+                let dependent_id: usize = recipes[recipe_index].phases[phase_index].id;
+                for next_dependency in graph.get_dependencies_of(phase_index) {
+                    if let Some(other_index) = next_dependency.recipe_index {
+                        recipes[other_index].phases[next_dependency.phase_index]
+                          .add_dependency(dependent_id);
+                    }
+                }
+            }
+        }
+
+        recipes
+    }
+}
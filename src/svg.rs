@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+
+use chrono_period::NaivePeriod;
+
+use string_builder::Builder;
+
+use super::{DISPLAY_DATE_FORMAT, UNAVAILABLE_COLOR};
+use super::phases::PhaseInstance;
+use super::recipes::Recipe;
+use super::resources::{Allocation, AllocationHolder, Resource};
+use super::util::is_valid_hex_color;
+
+/// The width of the column holding the names of recipes, phases and resources, in pixels.
+const LABEL_WIDTH: f64 = 240.0;
+
+/// The width of the area in which bars are drawn, in pixels.
+const CHART_WIDTH: f64 = 960.0;
+
+const MARGIN: f64 = 16.0;
+const AXIS_HEIGHT: f64 = 40.0;
+const ROW_HEIGHT: f64 = 24.0;
+const BAR_HEIGHT: f64 = 16.0;
+
+/// The least space between two labels of the date axis, in pixels.
+const MIN_TICK_SPACING: f64 = 72.0;
+
+/// The intervals between labels of the date axis, in days, from which the shortest that leaves
+/// enough space between labels is chosen.
+const TICK_INTERVALS: [i64; 9] = [1, 2, 7, 14, 28, 56, 91, 182, 364];
+
+/// The color of bars whose phase has no valid color of its own.
+const DEFAULT_COLOR: &str = "#4682B4";
+
+/// A row of the optional resource view of an SVG chart: a `Resource`, and the periods over which
+/// it is allocated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceLane {
+    pub resource: Resource,
+    pub allocations: Vec<Allocation>
+}
+
+/// Retrieve a `String` containing a standalone SVG Gantt chart of a set of scheduled recipes.
+///
+/// # Arguments
+/// - `title`: The title of the chart.
+/// - `recipes`: The scheduled `Recipe`s. Each is drawn as a heading row, followed by a row for
+///   each of its phases, with a bar colored in the phase's `color_hex`. An arrow is drawn from the
+///   end of each phase to the start of each phase that depends upon it.
+/// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) covered by the date axis. Bars
+///   outside of it are cut off at its edges.
+/// - `lanes`: The `ResourceLane`s to draw below the recipes, one row per `Resource`, with a bar
+///   for each of its allocations. If empty, the resource view is left out.
+///
+/// # Returns
+/// - A `String` containing the SVG document. Hovering over a bar shows its details.
+pub fn get_string_in_svg_format(title: &str, recipes: &[Recipe], period: NaivePeriod,
+                                lanes: &[ResourceLane]) -> String {
+    let row_count: usize = recipes.iter().map(|recipe| recipe.phases.len() + 1).sum::<usize>()
+                             + match lanes.is_empty() {
+                                 true => 0,
+                                 false => lanes.len() + 1
+                             };
+    let width: f64 = LABEL_WIDTH + CHART_WIDTH + 2.0 * MARGIN;
+    let height: f64 = MARGIN + AXIS_HEIGHT + row_count as f64 * ROW_HEIGHT + MARGIN;
+    let scale = TimeScale::new(period);
+
+    let mut builder = Builder::default();
+    builder.append(format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+                           width, height, width, height));
+    builder.append(format!("  <title>{}</title>\n", escape_xml(title)));
+    builder.append("  <defs>\n");
+    builder.append("    <marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"6\" markerHeight=\"6\" orient=\"auto\">\n");
+    builder.append("      <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#555555\"/>\n");
+    builder.append("    </marker>\n");
+    builder.append("  </defs>\n");
+    builder.append(format!("  <rect width=\"{:.0}\" height=\"{:.0}\" fill=\"#FFFFFF\"/>\n", width, height));
+
+    builder.append(get_axis_string(&scale, height));
+
+    // The bounds of each phase's bar, by phase id, from which dependency arrows are drawn.
+    let mut bars: HashMap<usize, (f64, f64, f64)> = HashMap::new();
+    let mut row: usize = 0;
+
+    builder.append("  <g class=\"recipes\">\n");
+    for next_recipe in recipes {
+        builder.append(get_heading_string(&next_recipe.name, row));
+        row += 1;
+
+        for next_phase in next_recipe.get_phase_iterator() {
+            let y: f64 = get_row_y(row);
+            let start_x: f64 = scale.get_x(next_phase.start_date);
+            let end_x: f64 = scale.get_x(next_phase.start_date + next_phase.duration);
+            bars.insert(next_phase.id, (start_x, end_x, y + ROW_HEIGHT / 2.0));
+
+            builder.append(format!("    <text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                                   MARGIN + 12.0, y + ROW_HEIGHT / 2.0 + 4.0,
+                                   escape_xml(&next_phase.description)));
+            builder.append(get_bar_string(start_x, end_x, y, get_color(&next_phase.color_hex),
+                                          &get_phase_tooltip(&next_recipe.name, next_phase)));
+            row += 1;
+        }
+    }
+    builder.append("  </g>\n");
+
+    builder.append("  <g class=\"dependencies\" fill=\"none\" stroke=\"#555555\" stroke-width=\"1\">\n");
+    for next_phase in recipes.iter().flat_map(|recipe| recipe.get_phase_iterator()) {
+        let (_, from_x, from_y) = bars[&next_phase.id];
+        for next_dependent in &next_phase.dependencies {
+            if let Some((to_x, _, to_y)) = bars.get(next_dependent) {
+                // Arrows come into the start of the dependent bar from the left, if there's room,
+                // and otherwise down onto its top.
+                let bend_x: f64 = from_x + 6.0;
+                let path: String = match *to_x >= bend_x + 6.0 {
+                    true => format!("M {:.1} {:.1} H {:.1} V {:.1} H {:.1}", from_x, from_y,
+                                    bend_x, to_y, to_x),
+                    false => format!("M {:.1} {:.1} H {:.1} V {:.1}", from_x, from_y, bend_x,
+                                     to_y - BAR_HEIGHT / 2.0)
+                };
+
+                builder.append(format!("    <path d=\"{}\" marker-end=\"url(#arrow)\"/>\n", path));
+            }
+        }
+    }
+    builder.append("  </g>\n");
+
+    if !lanes.is_empty() {
+        let phase_colors: HashMap<usize, &str> = recipes.iter()
+          .flat_map(|recipe| recipe.get_phase_iterator())
+          .map(|phase| (phase.id, get_color(&phase.color_hex)))
+          .collect();
+
+        builder.append("  <g class=\"resources\">\n");
+        builder.append(get_heading_string("Resources", row));
+        row += 1;
+
+        for next_lane in lanes {
+            let y: f64 = get_row_y(row);
+            builder.append(format!("    <text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                                   MARGIN + 12.0, y + ROW_HEIGHT / 2.0 + 4.0,
+                                   escape_xml(&next_lane.resource.name)));
+
+            for next_allocation in &next_lane.allocations {
+                let color: &str = match &next_allocation.holder {
+                    AllocationHolder::Phase { phase_id, .. } => {
+                        phase_colors.get(phase_id).cloned().unwrap_or(DEFAULT_COLOR)
+                    },
+                    AllocationHolder::Unavailable { .. } => UNAVAILABLE_COLOR,
+                    AllocationHolder::Unknown => DEFAULT_COLOR
+                };
+
+                builder.append(get_bar_string(scale.get_x(next_allocation.period.start),
+                                              scale.get_x(next_allocation.period.end), y, color,
+                                              &get_allocation_tooltip(next_allocation)));
+            }
+            row += 1;
+        }
+        builder.append("  </g>\n");
+    }
+
+    builder.append("</svg>\n");
+
+    builder.string().unwrap()
+}
+
+/// The mapping from dates to horizontal positions within the chart.
+struct TimeScale {
+    period: NaivePeriod,
+    pixels_per_second: f64
+}
+
+impl TimeScale {
+    fn new(period: NaivePeriod) -> Self {
+        // An empty period would otherwise put every date at the same place.
+        let seconds: i64 = std::cmp::max(period.duration().num_seconds(), 86400);
+
+        TimeScale {
+            period,
+            pixels_per_second: CHART_WIDTH / seconds as f64
+        }
+    }
+
+    /// Retrieve the horizontal position of a date, kept within the chart.
+    fn get_x(&self, date: NaiveDateTime) -> f64 {
+        let offset: f64 = (date - self.period.start).num_seconds() as f64 * self.pixels_per_second;
+
+        LABEL_WIDTH + MARGIN + offset.clamp(0.0, CHART_WIDTH)
+    }
+
+    /// Retrieve the number of days between labels of the date axis.
+    fn get_tick_interval(&self) -> i64 {
+        let pixels_per_day: f64 = self.pixels_per_second * 86400.0;
+
+        TICK_INTERVALS.iter()
+                      .cloned()
+                      .find(|days| *days as f64 * pixels_per_day >= MIN_TICK_SPACING)
+                      .unwrap_or(TICK_INTERVALS[TICK_INTERVALS.len() - 1])
+    }
+}
+
+/// Retrieve the date axis: a label and a grid line at regular intervals of days from the start of
+/// the chart's period.
+fn get_axis_string(scale: &TimeScale, height: f64) -> String {
+    let interval: Duration = Duration::days(scale.get_tick_interval());
+
+    let mut builder = Builder::default();
+    builder.append("  <g class=\"axis\" stroke=\"#DDDDDD\">\n");
+
+    let mut tick: NaiveDateTime = scale.period.start;
+    while tick <= scale.period.end {
+        let x: f64 = scale.get_x(tick);
+        builder.append(format!("    <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\n",
+                               x, MARGIN + AXIS_HEIGHT - 8.0, x, height - MARGIN));
+        builder.append(format!("    <text x=\"{:.1}\" y=\"{:.1}\" stroke=\"none\" fill=\"#333333\" text-anchor=\"middle\">{}</text>\n",
+                               x, MARGIN + AXIS_HEIGHT - 14.0, tick.format("%Y-%m-%d")));
+        tick += interval;
+    }
+
+    builder.append("  </g>\n");
+
+    builder.string().unwrap()
+}
+
+fn get_row_y(row: usize) -> f64 {
+    MARGIN + AXIS_HEIGHT + row as f64 * ROW_HEIGHT
+}
+
+fn get_heading_string(text: &str, row: usize) -> String {
+    format!("    <text x=\"{:.1}\" y=\"{:.1}\" font-weight=\"bold\">{}</text>\n", MARGIN,
+            get_row_y(row) + ROW_HEIGHT / 2.0 + 4.0, escape_xml(text))
+}
+
+/// Retrieve a bar between two horizontal positions, within the row starting at `y`, with a
+/// tooltip. Bars are always at least a pixel wide, so that short phases can still be seen.
+fn get_bar_string(start_x: f64, end_x: f64, y: f64, color: &str, tooltip: &str) -> String {
+    format!("    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"2\" fill=\"{}\"><title>{}</title></rect>\n",
+            start_x, y + (ROW_HEIGHT - BAR_HEIGHT) / 2.0, (end_x - start_x).max(1.0), BAR_HEIGHT,
+            color, escape_xml(tooltip))
+}
+
+fn get_color(color_hex: &str) -> &str {
+    match is_valid_hex_color(color_hex) {
+        true => color_hex,
+        false => DEFAULT_COLOR
+    }
+}
+
+fn get_phase_tooltip(recipe_name: &str, phase: &PhaseInstance) -> String {
+    let end: NaiveDateTime = phase.start_date + phase.duration;
+    let mut tooltip: String = format!("{}: {}\n{} to {}", recipe_name, phase.description,
+                                      phase.start_date.format(DISPLAY_DATE_FORMAT),
+                                      end.format(DISPLAY_DATE_FORMAT));
+    if !phase.resources_used.is_empty() {
+        let names: Vec<&str> = phase.resources_used.iter().map(|res| &res.name[..]).collect();
+        tooltip.push_str(&format!("\n{}", names.join(", ")));
+    }
+
+    tooltip
+}
+
+fn get_allocation_tooltip(allocation: &Allocation) -> String {
+    let holder: String = match &allocation.holder {
+        AllocationHolder::Phase { recipe, description, .. } => format!("{}: {}", recipe, description),
+        AllocationHolder::Unavailable { reason: Some(reason) } => format!("Unavailable: {}", reason),
+        AllocationHolder::Unavailable { reason: None } => String::from("Unavailable"),
+        AllocationHolder::Unknown => String::from("Allocated")
+    };
+
+    format!("{}\n{} to {}", holder, allocation.period.start.format(DISPLAY_DATE_FORMAT),
+            allocation.period.end.format(DISPLAY_DATE_FORMAT))
+}
+
+/// Escape the characters that have a special meaning within XML text and attribute values.
+fn escape_xml(text: &str) -> String {
+    let mut escaped: String = String::with_capacity(text.len());
+    for next_char in text.chars() {
+        match next_char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(next_char)
+        }
+    }
+
+    escaped
+}
@@ -4,6 +4,15 @@ use std::io::{BufReader, Read};
 
 use chrono::{Duration, NaiveDate, NaiveDateTime, ParseError};
 
+/// The format of dates with a time, as they may be given in a BPD file, and as they are written
+/// in machine-readable output, such as a [ScheduleExport](super::export::ScheduleExport) or a
+/// [UtilizationReport](super::utilization::UtilizationReport). Changing it changes the schema of
+/// that output.
+pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The longest duration, in hours, that can be given in a BPD file: about ten thousand years.
+const MAX_DURATION_HOURS: i64 = 10_000 * 366 * 24;
+
 pub fn convert_string_to_duration(duration_string: &str) -> Option<Duration> {
     let mut characters: Vec<_> = duration_string.chars().collect();
     let mut identifier = None;
@@ -19,12 +28,22 @@ pub fn convert_string_to_duration(duration_string: &str) -> Option<Duration> {
     match identifier {
         Some(x) => {
             let digit_string: String = characters.into_iter().collect();
-            let digits: i64 = digit_string.parse::<i64>().unwrap();
-            match x {
-                'm' => Some(Duration::days(digits*30)),
-                'w' => Some(Duration::weeks(digits)),
-                'd' => Some(Duration::days(digits)),
-                'h' => Some(Duration::hours(digits)),
+            let digits: i64 = match digit_string.parse::<i64>() {
+                Ok(x) => x,
+                Err(_e) => return None
+            };
+            let hours_per_unit: i64 = match x {
+                'm' => 30 * 24,
+                'w' => 7 * 24,
+                'd' => 24,
+                'h' => 1,
+                _ => return None
+            };
+
+            // A negative duration would end before it starts, and anything too long to be added to
+            // a date would otherwise panic later, within chrono.
+            match digits.checked_mul(hours_per_unit) {
+                Some(hours) if (0..=MAX_DURATION_HOURS).contains(&hours) => Some(Duration::hours(hours)),
                 _ => None
             }
         },
@@ -49,7 +68,7 @@ pub fn convert_string_to_duration(duration_string: &str) -> Option<Duration> {
 ///   `YYYY-MM-DD HH:MM:SS`), then this will be converted to a `NaiveDateTime` at `00:00:00`.
 ///
 pub fn get_naive_date_time_from_string(date_string: &str) -> Result<NaiveDateTime, ParseError> {
-    match NaiveDateTime::parse_from_str(date_string, "%Y-%m-%d %H:%M:%S") {
+    match NaiveDateTime::parse_from_str(date_string, DATE_TIME_FORMAT) {
         Ok(x) => Ok(x),
         Err(e) => {
             if e.description() == "premature end of input" {
@@ -64,6 +83,23 @@ pub fn get_naive_date_time_from_string(date_string: &str) -> Result<NaiveDateTim
     }
 }
 
+/// Determine if a string slice is a valid color in hexadecimal format.
+///
+/// # Arguments
+/// * `color_hex`: A string slice that should contain a color in either `#RRGGBB` or `#RGB` format.
+///
+/// # Returns
+/// * `true`, if `color_hex` is a `#` followed by either three or six hexadecimal digits; `false`,
+///   otherwise.
+///
+pub fn is_valid_hex_color(color_hex: &str) -> bool {
+    match color_hex.strip_prefix('#') {
+        Some(digits) => (digits.len() == 3 || digits.len() == 6)
+                          && digits.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false
+    }
+}
+
 pub fn get_duration_in_hours(duration: Duration) -> i64 {
     duration.num_hours()
 }
@@ -0,0 +1,356 @@
+use chrono::{Duration, NaiveDateTime};
+
+use chrono_period::NaivePeriod;
+
+use serde::Serialize;
+
+use string_builder::Builder;
+
+use super::resources::{Resource, ResourceType};
+use super::util::DATE_TIME_FORMAT;
+
+/// The format in which a [UtilizationReport](UtilizationReport) is written.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json
+}
+
+impl ReportFormat {
+    /// The names of every `ReportFormat`, as given on the command line.
+    pub const NAMES: [&'static str; 3] = ["table", "csv", "json"];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReportFormat::Table => "table",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json"
+        }
+    }
+
+    /// Retrieve the `ReportFormat` with a given name, if there is one.
+    pub fn from_name(name: &str) -> Option<ReportFormat> {
+        match name {
+            "table" => Some(ReportFormat::Table),
+            "csv" => Some(ReportFormat::Csv),
+            "json" => Some(ReportFormat::Json),
+            _ => None
+        }
+    }
+}
+
+/// A stretch of time during which a `Resource` is neither allocated, nor unavailable.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct IdleGap {
+    pub start: String,
+    pub end: String,
+    pub hours: f64
+}
+
+/// How busy a single `Resource` is over the horizon of a [UtilizationReport](UtilizationReport).
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ResourceUtilization {
+    pub id: usize,
+    pub name: String,
+
+    #[serde(rename="type")]
+    pub resource_type: ResourceType,
+
+    /// The hours during which the `Resource` is allocated to phases.
+    #[serde(rename="busyHours")]
+    pub busy_hours: f64,
+
+    /// The hours during which the `Resource` is unavailable, as given by its `unavailable` block.
+    #[serde(rename="downtimeHours")]
+    pub downtime_hours: f64,
+
+    /// The busy hours, as a percentage of the hours during which the `Resource` is available.
+    pub utilization: f64,
+
+    #[serde(rename="idleGaps")]
+    pub idle_gaps: Vec<IdleGap>,
+
+    #[serde(rename="longestIdleHours")]
+    pub longest_idle_hours: f64
+}
+
+impl ResourceUtilization {
+    /// Measure how busy a `Resource` is over a period.
+    ///
+    /// # Arguments
+    /// - `resource`: The `Resource`, as tracked by the
+    ///   [ResourceTracker](super::resources::ResourceTracker), so that its `allocated_periods`
+    ///   include both the phases it was allocated to, and its unavailable periods.
+    /// - `horizon`: The [NaivePeriod](chrono_period::NaivePeriod) over which to measure. Anything
+    ///   outside it is ignored.
+    ///
+    /// # Notes
+    /// The turnaround of a `Resource` isn't counted as busy, since it isn't in use by a phase.
+    pub fn new(resource: &Resource, horizon: NaivePeriod) -> Self {
+        let downtime: Vec<NaivePeriod> = resource.unavailable_periods.iter()
+                                                 .filter_map(|x| x.period().ok())
+                                                 .collect();
+        let busy: Vec<NaivePeriod> = resource.allocated_periods.iter()
+                                             .filter(|period| !downtime.contains(period))
+                                             .cloned()
+                                             .collect();
+
+        let downtime: Vec<NaivePeriod> = merge_periods(clip_periods(&downtime, horizon));
+        let busy: Vec<NaivePeriod> = merge_periods(clip_periods(&busy, horizon));
+
+        let downtime_hours: f64 = get_total_hours(&downtime);
+        let busy_hours: f64 = get_total_hours(&busy);
+        let available_hours: f64 = get_hours(horizon.duration()) - downtime_hours;
+
+        let mut occupied: Vec<NaivePeriod> = busy;
+        occupied.extend(downtime);
+        let idle_gaps: Vec<IdleGap> = get_idle_gaps(&merge_periods(occupied), horizon);
+
+        ResourceUtilization {
+            id: resource.id,
+            name: resource.name.clone(),
+            resource_type: resource.resource_type.clone(),
+            busy_hours,
+            downtime_hours,
+            utilization: get_percentage(busy_hours, available_hours),
+            longest_idle_hours: idle_gaps.iter().map(|gap| gap.hours).fold(0.0, f64::max),
+            idle_gaps
+        }
+    }
+}
+
+/// How busy the `Resource`s of a single `ResourceType` are, taken together, over the horizon of a
+/// [UtilizationReport](UtilizationReport).
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ResourceTypeUtilization {
+    #[serde(rename="type")]
+    pub resource_type: ResourceType,
+
+    #[serde(rename="resourceCount")]
+    pub resource_count: usize,
+
+    #[serde(rename="busyHours")]
+    pub busy_hours: f64,
+
+    #[serde(rename="downtimeHours")]
+    pub downtime_hours: f64,
+
+    /// The busy hours of every `Resource` of the type, as a percentage of the hours during which
+    /// they are available.
+    pub utilization: f64,
+
+    #[serde(rename="idleGapCount")]
+    pub idle_gap_count: usize,
+
+    /// The longest idle stretch of any one `Resource` of the type.
+    #[serde(rename="longestIdleHours")]
+    pub longest_idle_hours: f64
+}
+
+/// A report of how busy every `Resource` of a schedule is, both individually and by
+/// `ResourceType`, over the horizon of the schedule.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct UtilizationReport {
+    pub start: String,
+    pub end: String,
+
+    #[serde(rename="horizonHours")]
+    pub horizon_hours: f64,
+
+    /// The utilization of each `Resource`, in order of `id`.
+    pub resources: Vec<ResourceUtilization>,
+
+    /// The utilization of each `ResourceType`, in the order each first appears in `resources`.
+    #[serde(rename="resourceTypes")]
+    pub resource_types: Vec<ResourceTypeUtilization>
+}
+
+impl UtilizationReport {
+    /// Construct a `UtilizationReport` for a set of `Resource`s.
+    ///
+    /// # Arguments
+    /// - `resources`: The `Resource`s to report on, as tracked by the
+    ///   [ResourceTracker](super::resources::ResourceTracker).
+    /// - `horizon`: The [NaivePeriod](chrono_period::NaivePeriod) over which to measure, usually
+    ///   from the start of the timeline to the end of the last phase.
+    pub fn new(resources: &[Resource], horizon: NaivePeriod) -> Self {
+        let mut by_id: Vec<&Resource> = resources.iter().collect();
+        by_id.sort_by_key(|res| res.id);
+
+        let resource_utilizations: Vec<ResourceUtilization> = by_id.into_iter()
+          .map(|res| ResourceUtilization::new(res, horizon))
+          .collect();
+
+        let mut resource_types: Vec<ResourceType> = vec![];
+        for next_utilization in &resource_utilizations {
+            if !resource_types.contains(&next_utilization.resource_type) {
+                resource_types.push(next_utilization.resource_type.clone());
+            }
+        }
+
+        let horizon_hours: f64 = get_hours(horizon.duration());
+        let type_utilizations: Vec<ResourceTypeUtilization> = resource_types.into_iter()
+          .map(|resource_type| {
+              let of_type: Vec<&ResourceUtilization> = resource_utilizations.iter()
+                .filter(|x| x.resource_type == resource_type)
+                .collect();
+
+              let busy_hours: f64 = of_type.iter().fold(0.0, |total, x| total + x.busy_hours);
+              let downtime_hours: f64 = of_type.iter().fold(0.0, |total, x| total + x.downtime_hours);
+              let available_hours: f64 = horizon_hours * of_type.len() as f64 - downtime_hours;
+
+              ResourceTypeUtilization {
+                  resource_type,
+                  resource_count: of_type.len(),
+                  busy_hours,
+                  downtime_hours,
+                  utilization: get_percentage(busy_hours, available_hours),
+                  idle_gap_count: of_type.iter().map(|x| x.idle_gaps.len()).sum(),
+                  longest_idle_hours: of_type.iter().map(|x| x.longest_idle_hours)
+                                             .fold(0.0, f64::max)
+              }
+          })
+          .collect();
+
+        UtilizationReport {
+            start: horizon.start.format(DATE_TIME_FORMAT).to_string(),
+            end: horizon.end.format(DATE_TIME_FORMAT).to_string(),
+            horizon_hours,
+            resources: resource_utilizations,
+            resource_types: type_utilizations
+        }
+    }
+
+    /// Retrieve a `String` containing this `UtilizationReport` in a given
+    /// [ReportFormat](ReportFormat).
+    pub fn get_string_in_format(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Table => self.get_string_as_table(),
+            ReportFormat::Csv => self.get_string_as_csv(),
+            ReportFormat::Json => self.get_string_as_json()
+        }
+    }
+
+    /// Retrieve a `String` containing this `UtilizationReport` as a pair of plain-text tables, one
+    /// for `Resource`s and one for `ResourceType`s.
+    pub fn get_string_as_table(&self) -> String {
+        let mut builder = Builder::default();
+        builder.append(format!("Utilization from {} to {} ({:.1} hours)\n\n", self.start,
+                               self.end, self.horizon_hours));
+
+        builder.append(format!("{:>4}  {:<24} {:<12} {:>10} {:>10} {:>6} {:>12} {:>8}\n", "ID",
+                               "Resource", "Type", "Busy (h)", "Down (h)", "Gaps",
+                               "Longest (h)", "Util %"));
+        for next in &self.resources {
+            builder.append(format!("{:>4}  {:<24} {:<12} {:>10.1} {:>10.1} {:>6} {:>12.1} {:>8.1}\n",
+                                   next.id, next.name, next.resource_type.as_str(),
+                                   next.busy_hours, next.downtime_hours, next.idle_gaps.len(),
+                                   next.longest_idle_hours, next.utilization));
+        }
+
+        builder.append("\n");
+        builder.append(format!("{:<12} {:>5} {:>10} {:>10} {:>6} {:>12} {:>8}\n", "Type", "Count",
+                               "Busy (h)", "Down (h)", "Gaps", "Longest (h)", "Util %"));
+        for next in &self.resource_types {
+            builder.append(format!("{:<12} {:>5} {:>10.1} {:>10.1} {:>6} {:>12.1} {:>8.1}\n",
+                                   next.resource_type.as_str(), next.resource_count,
+                                   next.busy_hours, next.downtime_hours, next.idle_gap_count,
+                                   next.longest_idle_hours, next.utilization));
+        }
+
+        builder.string().unwrap()
+    }
+
+    /// Retrieve a `String` containing one CSV row per `Resource`, after a header row. The
+    /// `ResourceType` summary can be derived from these rows, so it isn't included.
+    pub fn get_string_as_csv(&self) -> String {
+        let mut builder = Builder::default();
+        builder.append("id,name,type,busy_hours,downtime_hours,idle_gaps,longest_idle_hours,utilization\n");
+        for next in &self.resources {
+            builder.append(format!("{},{},{},{:.2},{:.2},{},{:.2},{:.2}\n", next.id,
+                                   get_csv_field(&next.name), get_csv_field(next.resource_type.as_str()),
+                                   next.busy_hours, next.downtime_hours, next.idle_gaps.len(),
+                                   next.longest_idle_hours, next.utilization));
+        }
+
+        builder.string().unwrap()
+    }
+
+    /// Retrieve a `String` containing this `UtilizationReport` as pretty-printed JSON.
+    pub fn get_string_as_json(&self) -> String {
+        // Every field is a string, number or list of them, so this can't fail.
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Quote a CSV field, if it contains anything that would otherwise break the row.
+fn get_csv_field(field: &str) -> String {
+    match field.contains(&[',', '"', '\n'][..]) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string()
+    }
+}
+
+fn clip_periods(periods: &[NaivePeriod], horizon: NaivePeriod) -> Vec<NaivePeriod> {
+    periods.iter()
+           .filter(|period| period.intersects_with(horizon))
+           .map(|period| NaivePeriod::new(period.start.max(horizon.start),
+                                          period.end.min(horizon.end)))
+           .collect()
+}
+
+/// Merge overlapping periods, returning them in order of start date.
+fn merge_periods(mut periods: Vec<NaivePeriod>) -> Vec<NaivePeriod> {
+    periods.sort();
+
+    let mut merged: Vec<NaivePeriod> = vec![];
+    for next_period in periods {
+        match merged.last_mut() {
+            Some(last) if next_period.start <= last.end => {
+                last.end = last.end.max(next_period.end);
+            },
+            _ => merged.push(next_period)
+        }
+    }
+
+    merged
+}
+
+/// Retrieve the gaps within a horizon between a set of merged, ordered periods.
+///
+/// A `Resource` is handed over a second after the end of one period, so gaps of a second or less
+/// aren't idle time.
+fn get_idle_gaps(occupied: &[NaivePeriod], horizon: NaivePeriod) -> Vec<IdleGap> {
+    let mut boundaries: Vec<(NaiveDateTime, NaiveDateTime)> = vec![];
+    let mut idle_from: NaiveDateTime = horizon.start;
+    for next_period in occupied {
+        boundaries.push((idle_from, next_period.start));
+        idle_from = next_period.end;
+    }
+    boundaries.push((idle_from, horizon.end));
+
+    boundaries.into_iter()
+              .filter(|(start, end)| *end - *start > Duration::seconds(1))
+              .map(|(start, end)| IdleGap {
+                  start: start.format(DATE_TIME_FORMAT).to_string(),
+                  end: end.format(DATE_TIME_FORMAT).to_string(),
+                  hours: get_hours(end - start)
+              })
+              .collect()
+}
+
+fn get_total_hours(periods: &[NaivePeriod]) -> f64 {
+    periods.iter().fold(0.0, |total, period| total + get_hours(period.duration()))
+}
+
+fn get_hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+fn get_percentage(part: f64, whole: f64) -> f64 {
+    match whole > 0.0 {
+        true => part / whole * 100.0,
+        false => 0.0
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{NaiveTime, Weekday};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::ProductionTimeline;
+use super::calendar::{BlackoutSpec, WorkingCalendarSpec};
+use super::dependencies::{get_recipe_order, PhaseGraph};
+use super::error::Error;
+use super::phases::{ProductionPhaseTemplate, ResourceNeed};
+use super::recipes::RecipeSpec;
+use super::resources::{Resource, ResourceReference, ResourceType, TurnaroundSpec};
+use super::util::{convert_string_to_duration, get_naive_date_time_from_string, is_valid_hex_color};
+
+/// How serious a [Diagnostic](Diagnostic) is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The problem will prevent the schedule from being constructed.
+    Error,
+
+    /// The problem will not prevent the schedule from being constructed, but is likely a mistake.
+    Warning
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning")
+        }
+    }
+}
+
+/// A single problem found while validating a BPD file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// The location of the problem within the BPD file, as a JSON path (e.g.
+    /// `$.recipes[0].phases[2].template`).
+    pub path: String,
+
+    pub message: String
+}
+
+impl Diagnostic {
+    fn error(path: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            path: path.to_string(),
+            message
+        }
+    }
+
+    fn warning(path: &str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            path: path.to_string(),
+            message
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+/// Validate a BPD file, collecting every problem found rather than stopping at the first one.
+///
+/// Each of the `timeline`, `resources`, `phaseTemplates` and `recipes` blocks is deserialized
+/// entry by entry, so that a malformed entry doesn't hide problems in the others. The entries
+/// that could be deserialized are then checked against each other (e.g. that every phase
+/// references a defined template, and every resource type needed by a template is provided by
+/// at least one resource). No scheduling is performed.
+///
+/// # Arguments
+/// - `json_data`: A string slice containing the BPD file, in JSON format.
+///
+/// # Returns
+/// - A `Vec` of [Diagnostic](Diagnostic) objects, grouped by the block in which they occur. If
+///   the `Vec` contains no diagnostics of [Severity::Error](Severity::Error), then the file
+///   should be able to be scheduled.
+///
+pub fn validate(json_data: &str) -> Vec<Diagnostic> {
+    let root: Value = match serde_json::from_str(json_data) {
+        Ok(x) => x,
+        Err(e) => return vec![Diagnostic::error("$", e.to_string())]
+    };
+
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+
+    if !root.is_object() {
+        diagnostics.push(Diagnostic::error("$", "expected a JSON object".to_string()));
+        return diagnostics;
+    }
+
+    if !root["name"].is_string() {
+        diagnostics.push(Diagnostic::error("$.name", "expected a string".to_string()));
+    }
+
+    if !root["id"].is_u64() {
+        diagnostics.push(Diagnostic::error("$.id", "expected a non-negative integer".to_string()));
+    }
+
+    let timeline: Option<ProductionTimeline> = deserialize_at(&root["timeline"], "$.timeline",
+                                                              &mut diagnostics);
+    if let Some(timeline) = &timeline {
+        if timeline.start_date().is_err() {
+            diagnostics.push(Diagnostic::error("$.timeline.start",
+                                               format!("unable to parse date '{}'",
+                                                       timeline.start)));
+        }
+    }
+
+    let resources: Vec<(String, Resource)> = deserialize_array_at(&root, "resources",
+                                                                  &mut diagnostics);
+    let templates: Vec<(String, ProductionPhaseTemplate)> =
+      deserialize_array_at(&root, "phaseTemplates", &mut diagnostics);
+    let recipes: Vec<(String, RecipeSpec)> = deserialize_array_at(&root, "recipes",
+                                                                  &mut diagnostics);
+
+    if let Some(timeline) = &timeline {
+        validate_windows("$.timeline", &timeline.not_before_string, &timeline.not_after_string,
+                         &timeline.blackouts, &templates, &root, &mut diagnostics);
+        validate_working_calendar("$.timeline", &timeline.working_calendar, &mut diagnostics);
+    }
+
+    // The `turnarounds` block is optional, unlike the other top-level blocks.
+    let turnarounds: Vec<(String, TurnaroundSpec)> = match root["turnarounds"].is_null() {
+        true => vec![],
+        false => deserialize_array_at(&root, "turnarounds", &mut diagnostics)
+    };
+
+    for (path, turnaround) in &turnarounds {
+        if convert_string_to_duration(&turnaround.duration_string[..]).is_none() {
+            diagnostics.push(Diagnostic::error(&format!("{}.duration", path),
+                                               format!("unable to parse duration '{}'",
+                                                       turnaround.duration_string)));
+        }
+    }
+
+    validate_resources(&resources, &mut diagnostics);
+    validate_templates(&templates, &get_provided_resource_types(&root), &recipes, &root,
+                       &mut diagnostics);
+    validate_recipes(&recipes, &templates, &root, &mut diagnostics);
+
+    diagnostics
+}
+
+fn deserialize_at<T: DeserializeOwned>(value: &Value, path: &str,
+                                       diagnostics: &mut Vec<Diagnostic>) -> Option<T> {
+    if value.is_null() {
+        diagnostics.push(Diagnostic::error(path, "missing required block".to_string()));
+        return None;
+    }
+
+    match serde_json::from_value(value.clone()) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            diagnostics.push(Diagnostic::error(path, e.to_string()));
+            None
+        }
+    }
+}
+
+/// Deserialize every entry of the array `root[key]` that can be deserialized, pairing each with
+/// its JSON path. Entries that can't be deserialized are reported in `diagnostics`.
+fn deserialize_array_at<T: DeserializeOwned>(root: &Value, key: &str,
+                                             diagnostics: &mut Vec<Diagnostic>)
+  -> Vec<(String, T)> {
+    let array_path = format!("$.{}", key);
+    let entries = match &root[key] {
+        Value::Array(x) => x,
+        Value::Null => {
+            diagnostics.push(Diagnostic::error(&array_path, "missing required block".to_string()));
+            return vec![];
+        },
+        _ => {
+            diagnostics.push(Diagnostic::error(&array_path, "expected an array".to_string()));
+            return vec![];
+        }
+    };
+
+    entries.iter().enumerate().filter_map(|(index, entry)| {
+        let path = format!("{}[{}]", array_path, index);
+        deserialize_at(entry, &path, diagnostics).map(|x| (path, x))
+    }).collect()
+}
+
+fn validate_resources(resources: &[(String, Resource)], diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_paths: HashMap<usize, &str> = HashMap::new();
+    for (path, resource) in resources {
+        if resource.resource_type.holds_batch() && resource.capacity().is_none() {
+            diagnostics.push(Diagnostic::warning(&format!("{}.capacity", path),
+                                                 format!("capacity '{}' is not a volume, so any batch will be considered to fit",
+                                                         resource.capacity_str)));
+        }
+
+        if resource.turnaround().is_none() {
+            diagnostics.push(Diagnostic::error(&format!("{}.turnaround", path),
+                                               format!("unable to parse duration '{}'",
+                                                       resource.turnaround_string.clone().unwrap_or_default())));
+        }
+
+        for (index, unavailable) in resource.unavailable_periods.iter().enumerate() {
+            validate_period(&format!("{}.unavailable[{}]", path, index), "unavailable period",
+                            &unavailable.start_string, &unavailable.end_string, diagnostics);
+        }
+
+        match first_paths.get(&resource.id) {
+            Some(first_path) => {
+                diagnostics.push(Diagnostic::error(&format!("{}.id", path),
+                                                   format!("duplicate resource id {} (first defined at {})",
+                                                           resource.id, first_path)));
+            },
+            None => {
+                first_paths.insert(resource.id, path);
+            }
+        }
+    }
+}
+
+fn validate_templates(templates: &[(String, ProductionPhaseTemplate)],
+                      provided_types: &[ResourceType], recipes: &[(String, RecipeSpec)],
+                      root: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_paths: HashMap<&str, &str> = HashMap::new();
+    for (path, template) in templates {
+        match first_paths.get(&template.id[..]) {
+            Some(first_path) => {
+                diagnostics.push(Diagnostic::error(&format!("{}.id", path),
+                                                   format!("duplicate phase template id '{}' (first defined at {})",
+                                                           template.id, first_path)));
+            },
+            None => {
+                first_paths.insert(&template.id[..], path);
+            }
+        }
+
+        if !template.default_duration_string.is_empty() && template.default_duration().is_none() {
+            diagnostics.push(Diagnostic::error(&format!("{}.defaultDuration", path),
+                                               format!("unable to parse duration '{}'",
+                                                       template.default_duration_string)));
+        }
+
+        if !template.color_hex.is_empty() && !is_valid_hex_color(&template.color_hex[..]) {
+            diagnostics.push(Diagnostic::error(&format!("{}.color", path),
+                                               format!("invalid color '{}'", template.color_hex)));
+        }
+
+        // A resource type that isn't provided is only fatal if a recipe actually uses the template.
+        let is_used = recipes.iter().any(|(_, recipe)| {
+            recipe.phase_specs.iter().any(|phase| phase.template == template.id)
+        });
+
+        // The flattened `resources_needed` doesn't preserve the layout of the block, so re-read
+        // it to report each problem at the entry responsible for it.
+        let raw_needs = get_value_at(root, path)["resourcesNeeded"].clone();
+        let needs: Vec<ResourceNeed> = serde_json::from_value(raw_needs).unwrap_or_default();
+        let mut reported_types: Vec<&ResourceType> = vec![];
+        for (index, need) in needs.iter().enumerate() {
+            let resource_type = need.resource_type();
+            if reported_types.contains(&resource_type) {
+                continue;
+            }
+
+            let needed_count = template.resources_needed.iter()
+                                                        .filter(|t| *t == resource_type)
+                                                        .count();
+            let provided_count = provided_types.iter().filter(|t| *t == resource_type).count();
+            let message = match provided_count {
+                0 => format!("no resource provides type '{}'", resource_type),
+                x if x < needed_count => format!("{} resources of type '{}' are needed, but only {} provided",
+                                                 needed_count, resource_type, x),
+                _ => continue
+            };
+
+            reported_types.push(resource_type);
+            let path = format!("{}.resourcesNeeded[{}]", path, index);
+            diagnostics.push(match is_used {
+                true => Diagnostic::error(&path, message),
+                false => Diagnostic::warning(&path, message)
+            });
+        }
+    }
+}
+
+/// Retrieve the value at a JSON path produced by this module (e.g. `$.phaseTemplates[2]`), or
+/// `Value::Null` if there is nothing there.
+fn get_value_at<'a>(root: &'a Value, path: &str) -> &'a Value {
+    let pointer = path.trim_start_matches('$')
+                      .replace(['.', '['], "/")
+                      .replace(']', "");
+
+    static NULL: Value = Value::Null;
+    root.pointer(&pointer).unwrap_or(&NULL)
+}
+
+fn validate_optional_date(path: &str, date: &Option<String>, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(date) = date {
+        if get_naive_date_time_from_string(&date[..]).is_err() {
+            diagnostics.push(Diagnostic::error(path, format!("unable to parse date '{}'", date)));
+        }
+    }
+}
+
+/// Validate a period with a `start` and `end` date, found at `path`, where `kind` describes what
+/// the period is (e.g. `"blackout"`).
+fn validate_period(path: &str, kind: &str, start: &str, end: &str,
+                   diagnostics: &mut Vec<Diagnostic>) {
+    let start_date = get_naive_date_time_from_string(start);
+    let end_date = get_naive_date_time_from_string(end);
+    for (field, value, parsed) in [("start", start, &start_date), ("end", end, &end_date)] {
+        if parsed.is_err() {
+            diagnostics.push(Diagnostic::error(&format!("{}.{}", path, field),
+                                               format!("unable to parse date '{}'", value)));
+        }
+    }
+
+    if let (Ok(start_date), Ok(end_date)) = (start_date, end_date) {
+        if end_date < start_date {
+            diagnostics.push(Diagnostic::error(path, format!("{} ends before it starts", kind)));
+        }
+    }
+}
+
+/// Validate the `notBefore`, `notAfter` and `blackouts` entries of either the timeline or a
+/// recipe, found at `path`.
+fn validate_windows(path: &str, not_before: &Option<String>, not_after: &Option<String>,
+                    blackouts: &[BlackoutSpec], templates: &[(String, ProductionPhaseTemplate)],
+                    root: &Value, diagnostics: &mut Vec<Diagnostic>) {
+    validate_optional_date(&format!("{}.notBefore", path), not_before, diagnostics);
+    validate_optional_date(&format!("{}.notAfter", path), not_after, diagnostics);
+
+    for (index, blackout) in blackouts.iter().enumerate() {
+        let blackout_path = format!("{}.blackouts[{}]", path, index);
+        validate_period(&blackout_path, "blackout", &blackout.start_string, &blackout.end_string,
+                        diagnostics);
+
+        for (template_index, template_id) in blackout.whole_phase_templates.iter().enumerate() {
+            if !is_known_template(&template_id[..], templates, root) {
+                diagnostics.push(Diagnostic::warning(&format!("{}.wholePhaseTemplates[{}]",
+                                                              blackout_path, template_index),
+                                                     format!("unknown phase template '{}'",
+                                                             template_id)));
+            }
+        }
+    }
+}
+
+/// Validate the `workingCalendar` entry of either the timeline or a recipe, found at `path`.
+fn validate_working_calendar(path: &str, calendar: &Option<WorkingCalendarSpec>,
+                             diagnostics: &mut Vec<Diagnostic>) {
+    let calendar: &WorkingCalendarSpec = match calendar {
+        Some(x) => x,
+        None => return
+    };
+
+    let calendar_path = format!("{}.workingCalendar", path);
+    if calendar.weekdays.is_empty() {
+        diagnostics.push(Diagnostic::error(&format!("{}.weekdays", calendar_path),
+                                           "no weekdays are worked".to_string()));
+    }
+
+    for (index, weekday) in calendar.weekdays.iter().enumerate() {
+        if weekday.parse::<Weekday>().is_err() {
+            diagnostics.push(Diagnostic::error(&format!("{}.weekdays[{}]", calendar_path, index),
+                                               format!("unknown weekday '{}'", weekday)));
+        }
+    }
+
+    if let Some(hours) = &calendar.hours {
+        let start = NaiveTime::parse_from_str(&hours.start_string[..], "%H:%M");
+        let end = NaiveTime::parse_from_str(&hours.end_string[..], "%H:%M");
+        for (field, value, parsed) in [("start", &hours.start_string, &start),
+                                      ("end", &hours.end_string, &end)] {
+            if parsed.is_err() {
+                diagnostics.push(Diagnostic::error(&format!("{}.hours.{}", calendar_path, field),
+                                                   format!("unable to parse time '{}'", value)));
+            }
+        }
+
+        if let (Ok(start), Ok(end)) = (start, end) {
+            if end <= start {
+                diagnostics.push(Diagnostic::error(&format!("{}.hours", calendar_path),
+                                                   "working hours end before they start".to_string()));
+            }
+        }
+    }
+
+    for (index, holiday) in calendar.holidays.iter().enumerate() {
+        validate_optional_date(&format!("{}.holidays[{}]", calendar_path, index),
+                               &Some(holiday.clone()), diagnostics);
+    }
+}
+
+fn validate_recipes(recipes: &[(String, RecipeSpec)],
+                    templates: &[(String, ProductionPhaseTemplate)], root: &Value,
+                    diagnostics: &mut Vec<Diagnostic>) {
+    let parsed_templates: Vec<ProductionPhaseTemplate> = templates.iter()
+                                                                  .map(|(_, t)| t.clone())
+                                                                  .collect();
+    let parsed_recipes: Vec<RecipeSpec> = recipes.iter().map(|(_, r)| r.clone()).collect();
+
+    for (path, recipe) in recipes {
+        if !is_valid_hex_color(&recipe.color_hex[..]) {
+            diagnostics.push(Diagnostic::error(&format!("{}.color", path),
+                                               format!("invalid color '{}'", recipe.color_hex)));
+        }
+
+        if let Err(batch_size) = recipe.batch_size() {
+            diagnostics.push(Diagnostic::error(&format!("{}.batchSize", path),
+                                               format!("unable to parse batch size '{}'",
+                                                       batch_size)));
+        }
+
+        if let Some(start) = &recipe.start_string {
+            if get_naive_date_time_from_string(&start[..]).is_err() {
+                diagnostics.push(Diagnostic::error(&format!("{}.start", path),
+                                                   format!("unable to parse date '{}'", start)));
+            }
+        }
+
+        validate_optional_date(&format!("{}.readyBy", path), &recipe.ready_by_string,
+                               diagnostics);
+        validate_windows(path, &recipe.not_before_string, &recipe.not_after_string,
+                         &recipe.blackouts, templates, root, diagnostics);
+        validate_working_calendar(path, &recipe.working_calendar, diagnostics);
+
+        for (index, phase) in recipe.phase_specs.iter().enumerate() {
+            let phase_path = format!("{}.phases[{}]", path, index);
+
+            if !is_known_template(&phase.template[..], templates, root) {
+                diagnostics.push(Diagnostic::error(&format!("{}.template", phase_path),
+                                                   format!("unknown phase template '{}'",
+                                                           phase.template)));
+            }
+
+            if !phase.duration_string.is_empty()
+                 && convert_string_to_duration(&phase.duration_string[..]).is_none() {
+                diagnostics.push(Diagnostic::error(&format!("{}.duration", phase_path),
+                                                   format!("unable to parse duration '{}'",
+                                                           phase.duration_string)));
+            }
+
+            let references = phase.pinned_resources.iter().map(|x| ("pinnedResources", x))
+              .chain(phase.excluded_resources.iter().map(|x| ("excludedResources", x)));
+            for (key, reference) in references {
+                if !is_known_resource(reference, root) {
+                    diagnostics.push(Diagnostic::error(&format!("{}.{}", phase_path, key),
+                                                       format!("unknown resource '{}'",
+                                                               reference)));
+                }
+            }
+        }
+
+        if let Err(e) = PhaseGraph::new(recipe, &parsed_recipes, &parsed_templates) {
+            let (phase_index, message) = match e {
+                Error::UnknownPhase { phase_index, phase, .. } => {
+                    (phase_index, format!("depends on unknown phase '{}'", phase))
+                },
+                Error::AmbiguousPhase { phase_index, phase, .. } => {
+                    (phase_index, format!("depends on phase '{}', but more than one phase has that id",
+                                          phase))
+                },
+                Error::UnparseableDuration { phase_index, duration, .. } => {
+                    (phase_index, format!("unable to parse lag '{}'", duration))
+                },
+                Error::UnresolvedRecipePhase { phase_index, other_recipe, phase, .. } => {
+                    (phase_index, format!("depends on phase '{}' of recipe '{}', which doesn't identify a single phase",
+                                          phase, other_recipe))
+                },
+                Error::DependencyCycle { phase_index, .. } => {
+                    (phase_index, "phase dependencies form a cycle".to_string())
+                },
+                _ => continue
+            };
+
+            diagnostics.push(Diagnostic::error(&format!("{}.phases[{}].after", path, phase_index),
+                                               message));
+        }
+    }
+
+    // Dependencies that can't be resolved were reported above, so only a cycle between recipes
+    // is left to report.
+    let given_order: Vec<usize> = (0..parsed_recipes.len()).collect();
+    if let Err(Error::DependencyCycle { recipe, phase_index }) = get_recipe_order(&parsed_recipes, &given_order) {
+        if let Some((path, _)) = recipes.iter().find(|(_, spec)| spec.name == recipe) {
+            diagnostics.push(Diagnostic::error(&format!("{}.phases[{}].after", path, phase_index),
+                                               "recipe dependencies form a cycle".to_string()));
+        }
+    }
+}
+
+/// Retrieve every `ResourceType` provided by an entry in the `resources` block. Entries that
+/// couldn't be deserialized still provide their `type`, if present, for the same reason as in
+/// [is_known_template](is_known_template).
+fn get_provided_resource_types(root: &Value) -> Vec<ResourceType> {
+    match &root["resources"] {
+        Value::Array(entries) => entries.iter()
+                                        .filter_map(|entry| entry["type"].as_str())
+                                        .map(ResourceType::from)
+                                        .collect(),
+        _ => vec![]
+    }
+}
+
+/// Determine if a template id is defined. Templates that couldn't be deserialized are still
+/// considered defined if their `id` is present, so that one malformed template doesn't cause an
+/// "unknown template" diagnostic on every phase that uses it.
+fn is_known_template(id: &str, templates: &[(String, ProductionPhaseTemplate)],
+                     root: &Value) -> bool {
+    if templates.iter().any(|(_, template)| template.id == id) {
+        return true;
+    }
+
+    match &root["phaseTemplates"] {
+        Value::Array(entries) => entries.iter().any(|entry| entry["id"].as_str() == Some(id)),
+        _ => false
+    }
+}
+
+/// Determine if a `ResourceReference` refers to an entry in the `resources` block. Entries that
+/// couldn't be deserialized are still considered, for the same reason as in
+/// [is_known_template](is_known_template).
+fn is_known_resource(reference: &ResourceReference, root: &Value) -> bool {
+    match &root["resources"] {
+        Value::Array(entries) => entries.iter().any(|entry| match reference {
+            ResourceReference::Id(id) => entry["id"].as_u64() == Some(*id as u64),
+            ResourceReference::Name(name) => entry["name"].as_str() == Some(&name[..])
+        }),
+        _ => false
+    }
+}
@@ -0,0 +1,144 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The number of liters in a US gallon.
+const LITERS_PER_GALLON: f64 = 3.785_411_784;
+
+/// The number of liters in a US beer barrel (31 US gallons).
+const LITERS_PER_BARREL: f64 = 31.0 * LITERS_PER_GALLON;
+
+/// The number of liters in a hectoliter.
+const LITERS_PER_HECTOLITER: f64 = 100.0;
+
+/// A unit in which a [Volume](Volume) can be expressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VolumeUnit {
+    /// US gallons, written with a `g` or `gal` suffix.
+    Gallons,
+
+    /// Liters, written with an `l` suffix.
+    Liters,
+
+    /// US beer barrels (31 US gallons), written with a `bbl` suffix.
+    Barrels,
+
+    /// Hectoliters (100 liters), written with an `hl` suffix.
+    Hectoliters
+}
+
+impl VolumeUnit {
+    /// Retrieve the number of liters in a single unit of this `VolumeUnit`.
+    pub fn liters_per_unit(&self) -> f64 {
+        match self {
+            VolumeUnit::Gallons => LITERS_PER_GALLON,
+            VolumeUnit::Liters => 1.0,
+            VolumeUnit::Barrels => LITERS_PER_BARREL,
+            VolumeUnit::Hectoliters => LITERS_PER_HECTOLITER
+        }
+    }
+
+    /// Retrieve the suffix used to denote this `VolumeUnit` within a BPD file.
+    pub fn suffix(&self) -> &str {
+        match self {
+            VolumeUnit::Gallons => "g",
+            VolumeUnit::Liters => "l",
+            VolumeUnit::Barrels => "bbl",
+            VolumeUnit::Hectoliters => "hl"
+        }
+    }
+
+    fn from_suffix(suffix: &str) -> Option<VolumeUnit> {
+        match &suffix.to_lowercase()[..] {
+            "g" | "gal" => Some(VolumeUnit::Gallons),
+            "l" => Some(VolumeUnit::Liters),
+            "bbl" => Some(VolumeUnit::Barrels),
+            "hl" => Some(VolumeUnit::Hectoliters),
+            _ => None
+        }
+    }
+}
+
+/// A volume of liquid, such as the size of a batch of beer, or the capacity of a `Resource`.
+///
+/// `Volume`s retain the unit in which they were specified, but are compared with each other by
+/// their equivalent volume in liters, so `Volume`s given in different units can be compared
+/// directly.
+#[derive(Clone, Copy, Debug)]
+pub struct Volume {
+    pub amount: f64,
+    pub unit: VolumeUnit
+}
+
+impl Volume {
+    pub fn new(amount: f64, unit: VolumeUnit) -> Self {
+        Volume {
+            amount,
+            unit
+        }
+    }
+
+    /// Try to parse a `Volume` from a string slice, such as `5g`, `19.5l`, `3bbl` or `1.2hl`.
+    ///
+    /// # Arguments
+    /// - `volume_string`: A string slice containing a non-negative number, optionally followed by
+    ///   whitespace, followed by a unit suffix. Suffixes are case-insensitive.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Volume`, if one could be parsed; `None`, otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chronogrog::volume::{Volume, VolumeUnit};
+    /// assert_eq!(Some(Volume::new(5.0, VolumeUnit::Gallons)), Volume::parse("5g"));
+    /// assert_eq!(Some(Volume::new(1.5, VolumeUnit::Barrels)), Volume::parse("1.5 bbl"));
+    /// assert_eq!(None, Volume::parse("5p"));
+    /// ```
+    pub fn parse(volume_string: &str) -> Option<Volume> {
+        let trimmed = volume_string.trim();
+        let split_index = trimmed.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let (amount_string, suffix) = trimmed.split_at(split_index);
+
+        let amount: f64 = match amount_string.parse::<f64>() {
+            Ok(x) => x,
+            Err(_e) => return None
+        };
+
+        VolumeUnit::from_suffix(suffix.trim()).map(|unit| Volume::new(amount, unit))
+    }
+
+    /// Retrieve the amount of this `Volume`, in liters.
+    pub fn in_liters(&self) -> f64 {
+        self.amount * self.unit.liters_per_unit()
+    }
+
+    /// Convert this `Volume` to an equivalent `Volume` in a different `VolumeUnit`.
+    pub fn convert_to(&self, unit: VolumeUnit) -> Volume {
+        Volume::new(self.in_liters() / unit.liters_per_unit(), unit)
+    }
+}
+
+impl PartialEq for Volume {
+    fn eq(&self, other: &Volume) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Volume {
+    fn partial_cmp(&self, other: &Volume) -> Option<Ordering> {
+        // Conversions between units aren't exact, so volumes within a milliliter of each other
+        // are considered equal.
+        let difference = self.in_liters() - other.in_liters();
+        if difference.abs() < 0.001 {
+            return Some(Ordering::Equal);
+        }
+
+        self.in_liters().partial_cmp(&other.in_liters())
+    }
+}
+
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.unit.suffix())
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use chronogrog::ProductionSchedule;
+use chronogrog::bottlenecks::{rank_bottlenecks, Bottleneck, DelayEvent};
+use chronogrog::resources::{AllocationHolder, ResourceType};
+
+use chronogrog::util::get_json_data_from_file;
+
+fn get_date(day: u32) -> NaiveDateTime {
+    NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, day), NaiveTime::from_hms(0, 0, 0))
+}
+
+fn get_delay_event(resource_type: ResourceType, requested_day: u32, actual_day: u32) -> DelayEvent {
+    DelayEvent {
+        recipe: String::from("Damned Squirrel"),
+        phase_id: 2,
+        phase: String::from("Primary Fermentation"),
+        resource_type,
+        requested_start: get_date(requested_day),
+        actual_start: get_date(actual_day),
+        blocking: vec![]
+    }
+}
+
+#[test]
+fn it_should_rank_resource_types_by_the_total_delay_they_caused() {
+    let events = vec![
+        get_delay_event(ResourceType::Kettle, 1, 2),
+        get_delay_event(ResourceType::Fermentor, 2, 4),
+        get_delay_event(ResourceType::Kettle, 5, 6),
+        get_delay_event(ResourceType::Keg, 10, 12)
+    ];
+
+    assert_eq!(Duration::days(2), events[1].delay());
+
+    // The kettle and fermentor both caused two days of delay, but the kettle did so first.
+    assert_eq!(vec![
+        Bottleneck { resource_type: ResourceType::Kettle, total_delay: Duration::days(2), event_count: 2 },
+        Bottleneck { resource_type: ResourceType::Fermentor, total_delay: Duration::days(2), event_count: 1 },
+        Bottleneck { resource_type: ResourceType::Keg, total_delay: Duration::days(2), event_count: 1 }
+    ], rank_bottlenecks(&events));
+
+    assert!(rank_bottlenecks(&[]).is_empty());
+}
+
+#[test]
+fn it_should_record_which_resource_and_phase_delayed_each_phase() {
+    // One kettle and one fermentor, in which both recipes are brewed on the same day, and
+    // ferment for ten days.
+    let json = get_json_data_from_file("tests/fixtures/contended_schedule.json").unwrap();
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let events: Vec<DelayEvent> = ps.get_delay_events();
+    assert_eq!(2, events.len());
+
+    // The second recipe can't brew until the first has finished with the kettle.
+    let kolsch_brewing = &events[0];
+    assert_eq!("Kolsch", kolsch_brewing.recipe);
+    assert_eq!("Brewing", kolsch_brewing.phase);
+    assert_eq!(ResourceType::Kettle, kolsch_brewing.resource_type);
+    assert_eq!(get_date(1), kolsch_brewing.requested_start);
+    assert_eq!(get_date(2) + Duration::seconds(1), kolsch_brewing.actual_start);
+
+    assert_eq!(1, kolsch_brewing.blocking.len());
+    assert_eq!(1, kolsch_brewing.blocking[0].resource_id);
+    match &kolsch_brewing.blocking[0].holder {
+        AllocationHolder::Phase { recipe, description, .. } => {
+            assert_eq!("Damned Squirrel", recipe);
+            assert_eq!("Brewing", description);
+        },
+        _ => panic!("the kettle should be held by a phase")
+    }
+
+    // Then its primary fermentation waits for the first recipe's to end.
+    let kolsch_primary = &events[1];
+    assert_eq!("Primary Fermentation", kolsch_primary.phase);
+    assert_eq!(ResourceType::Fermentor, kolsch_primary.resource_type);
+    assert_eq!(get_date(12) + Duration::seconds(1), kolsch_primary.actual_start);
+    assert_eq!(Duration::days(9), kolsch_primary.delay());
+    assert_eq!(2, kolsch_primary.blocking[0].resource_id);
+
+    // So the fermentor held things up the most.
+    let bottlenecks: Vec<Bottleneck> = ps.get_bottlenecks();
+    assert_eq!(vec![ResourceType::Fermentor, ResourceType::Kettle],
+               bottlenecks.iter().map(|x| x.resource_type.clone()).collect::<Vec<ResourceType>>());
+    assert_eq!(Duration::days(1) + Duration::seconds(1), bottlenecks[1].total_delay);
+}
@@ -0,0 +1,110 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chronogrog::calendar::{get_earliest_start_date_outside_blackouts, Blackout, BlackoutSpec,
+                           WorkingCalendar, WorkingCalendarSpec, WorkingHoursSpec};
+
+fn get_date(year: i32, month: u32, day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(year, month, day).and_hms(0, 0, 0)
+}
+
+fn get_blackout(start: &str, end: &str, whole_phase_templates: &[&str]) -> Blackout {
+    let spec = BlackoutSpec {
+        start_string: start.to_string(),
+        end_string: end.to_string(),
+        whole_phase_templates: whole_phase_templates.iter().map(|id| id.to_string()).collect()
+    };
+
+    spec.blackout().unwrap()
+}
+
+#[test]
+fn it_should_only_be_broken_by_phases_starting_within_it() {
+    let blackout = get_blackout("2020-12-24", "2020-12-27", &[]);
+
+    assert!(blackout.is_broken_by("brewing", get_date(2020, 12, 25), Duration::days(1)));
+    assert!(!blackout.is_broken_by("brewing", get_date(2020, 12, 23), Duration::days(10)));
+    assert!(!blackout.is_broken_by("brewing", get_date(2020, 12, 28), Duration::days(1)));
+}
+
+#[test]
+fn it_should_be_broken_by_whole_phase_templates_running_during_it() {
+    let blackout = get_blackout("2020-12-24", "2020-12-27", &["brewing"]);
+
+    assert!(blackout.is_broken_by("brewing", get_date(2020, 12, 23), Duration::days(2)));
+    assert!(!blackout.is_broken_by("primary", get_date(2020, 12, 23), Duration::days(2)));
+}
+
+#[test]
+fn it_should_move_a_phase_past_every_blackout_it_would_break() {
+    let blackouts = vec![get_blackout("2020-12-24", "2020-12-27", &[]),
+                         get_blackout("2020-12-27", "2020-12-28", &[])];
+
+    assert_eq!(get_date(2020, 12, 28) + Duration::seconds(1),
+               get_earliest_start_date_outside_blackouts(&blackouts, "brewing",
+                                                         get_date(2020, 12, 24),
+                                                         Duration::days(1)));
+    assert_eq!(get_date(2020, 12, 20),
+               get_earliest_start_date_outside_blackouts(&blackouts, "brewing",
+                                                         get_date(2020, 12, 20),
+                                                         Duration::days(1)));
+}
+
+#[test]
+fn it_should_report_an_unparseable_blackout_date() {
+    let spec = BlackoutSpec {
+        start_string: String::from("2020-12-24"),
+        end_string: String::from("after the holidays"),
+        whole_phase_templates: vec![]
+    };
+
+    assert_eq!(Err(String::from("after the holidays")), spec.blackout());
+}
+
+fn get_weekend_calendar() -> WorkingCalendar {
+    let spec = WorkingCalendarSpec {
+        weekdays: vec![String::from("saturday"), String::from("sun")],
+        hours: Some(WorkingHoursSpec {
+            start_string: String::from("08:00"),
+            end_string: String::from("18:00")
+        }),
+        holidays: vec![String::from("2020-01-05")]
+    };
+
+    spec.calendar().unwrap()
+}
+
+#[test]
+fn it_should_only_consider_working_hours_on_working_days_to_be_working_time() {
+    let calendar = get_weekend_calendar();
+
+    // 2020-01-04 is a Saturday, 2020-01-05 a Sunday, and 2020-01-01 a Wednesday.
+    assert!(calendar.is_working_time(NaiveDate::from_ymd(2020, 1, 4).and_hms(8, 0, 0)));
+    assert!(!calendar.is_working_time(NaiveDate::from_ymd(2020, 1, 4).and_hms(18, 0, 0)));
+    assert!(!calendar.is_working_time(NaiveDate::from_ymd(2020, 1, 5).and_hms(12, 0, 0)));
+    assert!(!calendar.is_working_time(NaiveDate::from_ymd(2020, 1, 1).and_hms(12, 0, 0)));
+}
+
+#[test]
+fn it_should_find_the_next_working_time() {
+    let calendar = get_weekend_calendar();
+
+    assert_eq!(Some(NaiveDate::from_ymd(2020, 1, 4).and_hms(8, 0, 0)),
+               calendar.get_next_working_time(NaiveDate::from_ymd(2020, 1, 1).and_hms(4, 0, 0)));
+    assert_eq!(Some(NaiveDate::from_ymd(2020, 1, 4).and_hms(9, 30, 0)),
+               calendar.get_next_working_time(NaiveDate::from_ymd(2020, 1, 4).and_hms(9, 30, 0)));
+
+    // After hours on Saturday, the next working time skips the holiday on Sunday.
+    assert_eq!(Some(NaiveDate::from_ymd(2020, 1, 11).and_hms(8, 0, 0)),
+               calendar.get_next_working_time(NaiveDate::from_ymd(2020, 1, 4).and_hms(19, 0, 0)));
+}
+
+#[test]
+fn it_should_find_no_working_time_in_a_calendar_without_working_days() {
+    let spec = WorkingCalendarSpec {
+        weekdays: vec![],
+        hours: None,
+        holidays: vec![]
+    };
+
+    assert_eq!(None, spec.calendar().unwrap().get_next_working_time(get_date(2020, 1, 1)));
+}
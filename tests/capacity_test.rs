@@ -0,0 +1,103 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::capacity::{CapacityComparison, CapacityScenario, ResourceDelta};
+use chronogrog::resources::ResourceType;
+
+use chronogrog::util::get_json_data_from_file;
+
+fn get_date(day: u32) -> NaiveDateTime {
+    NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, day), NaiveTime::from_hms(0, 0, 0))
+}
+
+/// Load the scenarios in the same way as the `capacity` subcommand loads its `--scenarios` file.
+fn get_capacity_scenarios() -> Vec<CapacityScenario> {
+    let json_data = get_json_data_from_file("tests/fixtures/capacity_scenarios.json").unwrap();
+    serde_json::from_str(&json_data[..]).unwrap()
+}
+
+#[test]
+fn it_should_deserialize_capacity_scenarios() {
+    let scenarios: Vec<CapacityScenario> = get_capacity_scenarios();
+
+    assert_eq!(vec![
+        CapacityScenario {
+            name: String::from("Another fermentor"),
+            deltas: vec![ResourceDelta::Add {
+                resource_type: ResourceType::Fermentor,
+                name: None,
+                capacity: String::from("5g"),
+                count: 1
+            }]
+        },
+        CapacityScenario {
+            name: String::from("No fermentor"),
+            deltas: vec![ResourceDelta::Remove { id: 1 }]
+        }
+    ], scenarios);
+}
+
+#[test]
+fn it_should_apply_resource_deltas_before_tracking_resources() {
+    // A single fermentor, shared by two recipes that each ferment for ten days.
+    let json_data = get_json_data_from_file("tests/fixtures/one_fermentor_schedule.json").unwrap();
+    let mut ps: ProductionSchedule = serde_json::from_str(&json_data[..]).unwrap();
+    ps.apply_resource_deltas(&[
+        ResourceDelta::Add {
+            resource_type: ResourceType::Fermentor,
+            name: Some(String::from("Conical")),
+            capacity: String::from("7g"),
+            count: 2
+        },
+        ResourceDelta::Remove { id: 2 }
+    ]).unwrap();
+    ps.init().unwrap();
+
+    let mut names: Vec<String> = ps.resources().into_iter().map(|x| x.name).collect();
+    names.sort();
+    assert_eq!(vec!["Conical 1", "Conical 2", "FV-001"], names);
+    assert_eq!(Some(String::from("Conical 2")), ps.get_resource_by_id(4).map(|x| x.name));
+
+    assert_eq!(Err(Error::UnknownResource { id: 9 }),
+               ps.apply_resource_deltas(&[ResourceDelta::Remove { id: 9 }]));
+}
+
+#[test]
+fn it_should_compare_the_makespan_and_completions_of_each_scenario_against_the_baseline() {
+    let json_data = get_json_data_from_file("tests/fixtures/one_fermentor_schedule.json").unwrap();
+    let comparison = CapacityComparison::new(&json_data[..], None,
+                                             &get_capacity_scenarios()).unwrap();
+
+    // The recipes ferment one after the other, so the second finishes on the 21st.
+    assert_eq!(Duration::days(20) + Duration::seconds(1), comparison.baseline.makespan);
+    assert_eq!(Some(get_date(21) + Duration::seconds(1)),
+               comparison.baseline.get_completion_date("Kolsch"));
+
+    // With a second fermentor, they ferment side by side.
+    let another = &comparison.scenarios[0];
+    assert_eq!(Some(-Duration::days(10) - Duration::seconds(1)),
+               another.get_makespan_change(&comparison.baseline));
+    assert_eq!(Some(Duration::zero()),
+               another.get_completion_change(&comparison.baseline, "Damned Squirrel"));
+    assert_eq!(Some(get_date(11)),
+               another.result.as_ref().unwrap().get_completion_date("Kolsch"));
+
+    let none = &comparison.scenarios[1];
+    assert!(none.result.is_err());
+    assert_eq!(None, none.get_makespan_change(&comparison.baseline));
+
+    let table: String = comparison.get_string_as_table();
+    let lines: Vec<&str> = table.lines().collect();
+    assert!(lines[0].contains("baseline") && lines[0].contains("Another fermentor"));
+    assert!(lines[1].starts_with("makespan"));
+    assert!(lines[1].contains("10.0d (-10.0d)"));
+    assert!(lines[3].starts_with("Kolsch"));
+    assert!(table.contains("scenario 'No fermentor': recipe 'Damned Squirrel'"));
+}
+
+#[test]
+fn it_should_fail_a_capacity_comparison_if_the_baseline_cant_be_scheduled() {
+    let json_data = get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap();
+
+    assert!(CapacityComparison::new(&json_data[..], None, &[]).is_err());
+}
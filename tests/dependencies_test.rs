@@ -0,0 +1,176 @@
+use chrono::Duration;
+
+use chronogrog::Error;
+use chronogrog::dependencies::{get_recipe_order, PhaseGraph};
+use chronogrog::phases::{DependencyAnchor, ProductionPhaseTemplate};
+use chronogrog::recipes::RecipeSpec;
+
+fn get_templates() -> Vec<ProductionPhaseTemplate> {
+    let json = r#"[
+        { "description": "Brewing", "id": "brewing", "order": 1 },
+        { "description": "Primary Fermentation", "id": "primary", "order": 2 },
+        { "description": "Dry Hopping", "id": "dryhop", "order": 3 },
+        { "description": "Carbonation", "id": "carbonation", "order": 4 }
+    ]"#;
+
+    serde_json::from_str(json).unwrap()
+}
+
+fn get_recipe_spec_with_phases(phases: &str) -> RecipeSpec {
+    serde_json::from_str(&format!(r##"{{ "name": "Graph Ale", "color": "#FFFFFF", "phases": {} }}"##,
+                                  phases)[..]).unwrap()
+}
+
+#[test]
+fn it_should_order_phases_by_template_order() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "carbonation" },
+        { "template": "brewing" },
+        { "template": "primary" }
+    ]"#);
+
+    let graph = PhaseGraph::new(&recipe_spec, &[], &get_templates()).unwrap();
+
+    assert_eq!(&[1, 2, 0], graph.get_phase_order());
+    assert!(graph.get_dependencies_of(1).is_empty());
+    assert_eq!(1, graph.get_dependencies_of(2)[0].phase_index);
+    assert_eq!(2, graph.get_dependencies_of(0)[0].phase_index);
+}
+
+#[test]
+fn it_should_allow_parallel_branches() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing" },
+        { "template": "primary" },
+        { "template": "dryhop", "after": { "phase": "primary", "from": "start", "lag": "7d" } },
+        { "template": "carbonation", "after": [ "primary", "dryhop" ] }
+    ]"#);
+
+    let graph = PhaseGraph::new(&recipe_spec, &[], &get_templates()).unwrap();
+
+    assert_eq!(&[0, 1, 2, 3], graph.get_phase_order());
+
+    let dry_hop_dependencies = graph.get_dependencies_of(2);
+    assert_eq!(1, dry_hop_dependencies.len());
+    assert_eq!(1, dry_hop_dependencies[0].phase_index);
+    assert_eq!(DependencyAnchor::Start, dry_hop_dependencies[0].from);
+    assert_eq!(Duration::days(7), dry_hop_dependencies[0].lag);
+
+    let carbonation_dependencies: Vec<usize> = graph.get_dependencies_of(3).iter()
+                                                    .map(|dep| dep.phase_index)
+                                                    .collect();
+    assert_eq!(vec![1, 2], carbonation_dependencies);
+}
+
+#[test]
+fn it_should_start_phases_with_an_empty_after_block_with_the_recipe() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing" },
+        { "template": "primary", "id": "starter", "after": [] }
+    ]"#);
+
+    let graph = PhaseGraph::new(&recipe_spec, &[], &get_templates()).unwrap();
+
+    assert!(graph.get_dependencies_of(0).is_empty());
+    assert!(graph.get_dependencies_of(1).is_empty());
+}
+
+#[test]
+fn it_should_reject_a_dependency_cycle() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing" },
+        { "template": "primary", "after": "dryhop" },
+        { "template": "dryhop", "after": "primary" }
+    ]"#);
+
+    assert_eq!(Some(Error::DependencyCycle { recipe: "Graph Ale".to_string(), phase_index: 1 }),
+               PhaseGraph::new(&recipe_spec, &[], &get_templates()).err());
+}
+
+#[test]
+fn it_should_reject_a_dependency_on_an_unknown_phase() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing" },
+        { "template": "primary", "after": "mashing" }
+    ]"#);
+
+    assert_eq!(Some(Error::UnknownPhase {
+                   recipe: "Graph Ale".to_string(),
+                   phase_index: 1,
+                   phase: "mashing".to_string()
+               }), PhaseGraph::new(&recipe_spec, &[], &get_templates()).err());
+}
+
+#[test]
+fn it_should_reject_a_dependency_on_an_ambiguous_phase() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "primary" },
+        { "template": "primary" },
+        { "template": "carbonation", "after": "primary" }
+    ]"#);
+
+    assert_eq!(Some(Error::AmbiguousPhase {
+                   recipe: "Graph Ale".to_string(),
+                   phase_index: 2,
+                   phase: "primary".to_string()
+               }), PhaseGraph::new(&recipe_spec, &[], &get_templates()).err());
+}
+
+#[test]
+fn it_should_resolve_a_dependency_upon_another_recipe() {
+    let yeast_donor = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing" },
+        { "template": "primary" }
+    ]"#);
+
+    let repitched: RecipeSpec = serde_json::from_str(r##"{
+        "name": "Repitched Ale",
+        "color": "#FFFFFF",
+        "phases": [
+            { "template": "brewing", "after": { "recipe": "Graph Ale", "phase": "primary" } },
+            { "template": "primary" }
+        ]
+    }"##).unwrap();
+
+    let recipe_specs = vec![repitched.clone(), yeast_donor];
+    let graph = PhaseGraph::new(&repitched, &recipe_specs, &get_templates()).unwrap();
+
+    let brewing_dependencies = graph.get_dependencies_of(0);
+    assert_eq!(Some(1), brewing_dependencies[0].recipe_index);
+    assert_eq!(1, brewing_dependencies[0].phase_index);
+
+    // The recipe providing the yeast has to be scheduled first.
+    assert_eq!(vec![1, 0], get_recipe_order(&recipe_specs, &[0, 1]).unwrap());
+}
+
+#[test]
+fn it_should_reject_a_dependency_upon_a_phase_of_an_unknown_recipe() {
+    let recipe_spec = get_recipe_spec_with_phases(r#"[
+        { "template": "brewing", "after": { "recipe": "Missing Ale", "phase": "primary" } }
+    ]"#);
+
+    assert_eq!(Some(Error::UnresolvedRecipePhase {
+                   recipe: "Graph Ale".to_string(),
+                   phase_index: 0,
+                   other_recipe: "Missing Ale".to_string(),
+                   phase: "primary".to_string()
+               }), get_recipe_order(&[recipe_spec], &[0]).err());
+}
+
+#[test]
+fn it_should_reject_a_dependency_cycle_between_recipes() {
+    let first: RecipeSpec = serde_json::from_str(r##"{
+        "name": "First Ale",
+        "color": "#FFFFFF",
+        "phases": [ { "template": "brewing", "after": { "recipe": "Second Ale", "phase": "brewing" } } ]
+    }"##).unwrap();
+
+    let second: RecipeSpec = serde_json::from_str(r##"{
+        "name": "Second Ale",
+        "color": "#FFFFFF",
+        "phases": [ { "template": "brewing", "after": { "recipe": "First Ale", "phase": "brewing" } } ]
+    }"##).unwrap();
+
+    assert_eq!(Some(Error::DependencyCycle { recipe: "First Ale".to_string(), phase_index: 0 }),
+               get_recipe_order(&[first, second], &[0, 1]).err());
+}
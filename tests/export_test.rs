@@ -0,0 +1,60 @@
+use serde_json::{json, Value};
+
+use chronogrog::ProductionSchedule;
+use chronogrog::export::{ScheduleExport, SCHEMA_VERSION};
+
+use chronogrog::util::get_json_data_from_file;
+
+#[test]
+fn it_should_export_each_recipe_and_phase_with_its_resolved_schedule() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let export: ScheduleExport = ps.get_schedule_export();
+    assert_eq!(SCHEMA_VERSION, export.schema_version);
+    assert_eq!("Simple Production Schedule", export.name);
+
+    let phase_count: usize = ps.get_recipe_iterator().map(|recipe| recipe.phases.len()).sum();
+    assert_eq!(phase_count, export.recipes.iter().map(|recipe| recipe.phases.len()).sum::<usize>());
+
+    // Brewing depends upon planning, and uses the kettle, mash tun and lauter tun.
+    let recipe = &export.recipes[0];
+    let brewing = &recipe.phases[1];
+    assert_eq!("Brewing", brewing.description);
+    assert_eq!("2020-01-01 04:00:00", brewing.start);
+    assert_eq!("2020-01-02 04:00:00", brewing.end);
+    assert_eq!(86400, brewing.duration_seconds);
+    assert_eq!(vec![recipe.phases[0].id], brewing.depends_on);
+    assert_eq!(vec![recipe.phases[2].id], brewing.dependents);
+    assert_eq!(vec![3, 4, 5], brewing.resource_ids);
+    assert_eq!(recipe.phases[0].start, recipe.start);
+
+    // Every resource is listed in order of id, even if nothing is allocated to it.
+    let ids: Vec<usize> = export.resources.iter().map(|resource| resource.id).collect();
+    assert_eq!((1..=9).collect::<Vec<usize>>(), ids);
+    assert!(export.resources[0].allocations.is_empty());
+
+    let big_bertha = &export.resources[1];
+    assert_eq!("Big Bertha", big_bertha.name);
+    assert_eq!(vec![Some(recipe.phases[2].id), Some(recipe.phases[3].id)],
+               big_bertha.allocations.iter().map(|x| x.phase_id).collect::<Vec<Option<usize>>>());
+    assert_eq!(Some(recipe.id), big_bertha.allocations[0].recipe_id);
+}
+
+#[test]
+fn it_should_output_a_schedule_export_as_json() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let value: Value = serde_json::from_str(&ps.get_string_in_json_format()[..]).unwrap();
+    assert_eq!(json!(SCHEMA_VERSION), value["schemaVersion"]);
+    assert_eq!(json!({
+                   "start": "2020-01-02 04:00:00",
+                   "end": "2020-02-04 04:00:00",
+                   "kind": "phase",
+                   "phaseId": 4,
+                   "recipeId": 1
+               }),
+               value["resources"][1]["allocations"][0]);
+    assert_eq!(json!("fermentor"), value["resources"][1]["type"]);
+    assert_eq!(json!([2]), value["recipes"][0]["phases"][1]["dependsOn"]);
+    assert_eq!(json!(false), value["recipes"][0]["phases"][1]["handsOn"]);
+}
@@ -0,0 +1,109 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use serde_json::{json, Value};
+
+use chronogrog::ProductionSchedule;
+use chronogrog::gantt::{get_string_in_mermaid_format, get_string_in_plantuml_format};
+use chronogrog::phases::PhaseInstance;
+use chronogrog::recipes::Recipe;
+
+use chronogrog::util::get_json_data_from_file;
+
+fn get_date(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(2020, 1, day).and_hms(hour, 0, 0)
+}
+
+/// Construct a recipe whose brewing is followed straight away by fermentation, and whose
+/// conditioning waits five days longer than fermentation needs it to.
+fn get_kolsch() -> Recipe {
+    let mut brewing = PhaseInstance::new(2, "Brewing".to_string(), "#F0E68C".to_string(),
+                                         Duration::days(1), get_date(1, 0), vec![]);
+    brewing.add_dependency(3);
+
+    let mut fermentation = PhaseInstance::new(3, "Primary: Fermentation".to_string(),
+                                              "#F0E68C".to_string(), Duration::days(10),
+                                              get_date(2, 0), vec![]);
+    fermentation.add_dependency(4);
+
+    let conditioning = PhaseInstance::new(4, "Conditioning [cold]".to_string(),
+                                          "#F0E68C".to_string(), Duration::hours(36),
+                                          get_date(17, 0), vec![]);
+
+    Recipe {
+        id: 1,
+        name: "Kolsch".to_string(),
+        color: "#F0E68C".to_string(),
+        phases: vec![brewing, fermentation, conditioning],
+        start_date: get_date(1, 0),
+        batch_size: None
+    }
+}
+
+#[test]
+fn it_should_output_recipes_as_a_mermaid_gantt_block() {
+    assert_eq!("gantt\n\
+                \x20   title Summer Schedule\n\
+                \x20   dateFormat YYYY-MM-DD HH:mm:ss\n\
+                \x20   axisFormat %Y-%m-%d\n\
+                \x20   section Kolsch\n\
+                \x20   Brewing :p2, 2020-01-01 00:00:00, 1d\n\
+                \x20   Primary#58; Fermentation :p3, after p2, 10d\n\
+                \x20   Conditioning [cold] :p4, 2020-01-17 00:00:00, 36h\n",
+               get_string_in_mermaid_format("Summer Schedule", &[get_kolsch()]));
+}
+
+#[test]
+fn it_should_output_recipes_as_a_plantuml_gantt_document() {
+    assert_eq!("@startgantt\n\
+                title Summer Schedule\n\
+                Project starts 2020-01-01\n\
+                -- Kolsch --\n\
+                [Brewing] as [P2] starts 2020-01-01 and lasts 1 day and is colored in #F0E68C\n\
+                [Primary: Fermentation] as [P3] starts at [P2]'s end and lasts 10 days and is colored in #F0E68C\n\
+                [Conditioning (cold)] as [P4] starts 2020-01-17 and lasts 2 days and is colored in #F0E68C\n\
+                @endgantt\n",
+               get_string_in_plantuml_format("Summer Schedule", &[get_kolsch()]));
+}
+
+#[test]
+fn it_should_place_a_phase_after_the_phase_of_its_recipe_it_follows_in_gantt_output() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let mermaid: String = ps.get_string_in_mermaid_format();
+    let phase_count: usize = ps.get_recipe_iterator().map(|recipe| recipe.phases.len()).sum();
+    assert_eq!(ps.get_recipe_iterator().count(), mermaid.matches("    section ").count());
+    assert_eq!(phase_count, mermaid.lines().filter(|line| line.contains(" :p")).count());
+
+    // Fermentation starts as soon as brewing ends, so is placed after it.
+    let recipe = ps.get_recipe_iterator().next().unwrap();
+    assert!(mermaid.contains(&format!("    Primary Fermentation :p{}, after p{}, 33d\n",
+                                      recipe.phases[2].id, recipe.phases[1].id)[..]));
+
+    let plantuml: String = ps.get_string_in_plantuml_format();
+    assert!(plantuml.starts_with("@startgantt\ntitle Simple Production Schedule\nProject starts 2020-01-01\n"));
+    assert_eq!(phase_count, plantuml.matches(" as [P").count());
+}
+
+#[test]
+fn it_should_carry_dependencies_between_recipes_into_gantt_output() {
+    let json = get_json_data_from_file("tests/fixtures/cross_recipe_dependency.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // The repitched batch starts as soon as the batch it takes its yeast from ends.
+    let repitched = ps.get_recipe_by_name("Repitched Ale").unwrap().phases[0].id;
+    let donor = ps.get_recipe_by_name("Erroneous Ale").unwrap().phases[0].id;
+    assert!(ps.get_string_in_mermaid_format()
+              .contains(&format!("    Primary Fermentation :p{}, after p{}, 10d\n", repitched, donor)[..]));
+    assert!(ps.get_string_in_plantuml_format()
+              .contains(&format!(" as [P{}] starts at [P{}]'s end and ", repitched, donor)[..]));
+
+    // With a lag, it starts later than the donor batch ends, so it's given an explicit start.
+    let mut bpd: Value = serde_json::from_str(&json[..]).unwrap();
+    bpd["recipes"][0]["phases"][0]["after"]["lag"] = json!("2d");
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+
+    assert!(ps.get_string_in_mermaid_format()
+              .contains(&format!("    Primary Fermentation :p{}, 2020-01-13 00:00:00, 10d\n", repitched)[..]));
+    assert!(ps.get_string_in_plantuml_format()
+              .contains(&format!(" as [P{}] starts 2020-01-13 and ", repitched)[..]));
+}
@@ -0,0 +1,39 @@
+use chrono::Duration;
+
+use chronogrog::ical::{escape_text, format_date_time, format_duration, get_folded_line};
+use chronogrog::util::get_naive_date_time_from_string;
+
+#[test]
+fn it_should_escape_delimiters_within_text() {
+    assert_eq!("Kettle\\, Mash Tun\\; Lauter Tun\\nC:\\\\brewery",
+               escape_text("Kettle, Mash Tun; Lauter Tun\r\nC:\\brewery"));
+}
+
+#[test]
+fn it_should_fold_lines_longer_than_seventy_five_octets() {
+    assert_eq!("SUMMARY:Short\r\n", get_folded_line("SUMMARY:Short"));
+
+    let line: String = format!("DESCRIPTION:{}", "a".repeat(100));
+    let folded: String = get_folded_line(&line[..]);
+    let lines: Vec<&str> = folded.trim_end().split("\r\n").collect();
+    assert_eq!(2, lines.len());
+    assert_eq!(75, lines[0].len());
+    assert!(lines[1].starts_with(' '));
+    assert_eq!(line, lines.concat().replacen(' ', "", 1));
+
+    // Multi-byte characters are never split across lines.
+    let folded: String = get_folded_line(&"é".repeat(40)[..]);
+    assert_eq!(vec![74, 7], folded.trim_end().split("\r\n").map(|x| x.len()).collect::<Vec<usize>>());
+}
+
+#[test]
+fn it_should_format_dates_and_durations() {
+    assert_eq!("20200101T093000",
+               format_date_time(get_naive_date_time_from_string("2020-01-01 09:30:00").unwrap()));
+
+    assert_eq!("P10D", format_duration(Duration::days(10)));
+    assert_eq!("P1DT12H", format_duration(Duration::hours(36)));
+    assert_eq!("PT4H30M1S", format_duration(Duration::seconds(16201)));
+    assert_eq!("-PT2H", format_duration(-Duration::hours(2)));
+    assert_eq!("PT0S", format_duration(Duration::zero()));
+}
@@ -2,8 +2,12 @@ use std::fs;
 
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 
-use chronogrog::ProductionSchedule;
-use chronogrog::resources::ResourceType;
+use chrono_period::NaivePeriod;
+
+use serde_json::{json, Value};
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::resources::{Allocation, AllocationHolder, ResourceType};
 
 use chronogrog::util::get_json_data_from_file;
 
@@ -88,15 +92,62 @@ fn it_should_be_able_to_retrieve_recipes_by_name_and_id() {
 fn it_should_be_able_to_retrieve_an_available_resource_by_type() {
     let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
 
-    let res = ps.get_available_resource_by_type(ResourceType::Kettle).unwrap();
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 1), NaiveTime::from_hms(0, 0, 0));
+    let res = ps.get_available_resource_by_type(ResourceType::Kettle, start).unwrap();
 
     assert_eq!("Large Kettle", res.name);
 
-    let res2 = ps.get_available_resource_by_type(ResourceType::Other("nitrogastank".to_string()));
+    // The only kettle is in use while brewing, from 04:00 on the first day.
+    let brewing = start + Duration::hours(12);
+    assert_eq!(None, ps.get_available_resource_by_type(ResourceType::Kettle, brewing));
+
+    let res2 = ps.get_available_resource_by_type(ResourceType::Other("nitrogastank".to_string()),
+                                                 start);
 
     assert_eq!(None, res2);
 }
 
+#[test]
+fn it_should_report_the_phase_and_recipe_holding_each_allocation_of_a_resource() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    // Big Bertha holds the batch through both primary and secondary fermentation.
+    let allocations: Vec<Allocation> = ps.get_allocations_of_resource(2);
+    let holders: Vec<AllocationHolder> = allocations.into_iter().map(|x| x.holder).collect();
+    assert_eq!(vec![
+        AllocationHolder::Phase {
+            recipe: String::from("Damned Squirrel Mk. II"),
+            phase_id: 4,
+            description: String::from("Primary Fermentation")
+        },
+        AllocationHolder::Phase {
+            recipe: String::from("Damned Squirrel Mk. II"),
+            phase_id: 5,
+            description: String::from("Secondary Fermentation")
+        }
+    ], holders);
+
+    let during_primary = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 10),
+                                            NaiveTime::from_hms(0, 0, 0));
+    let phase_id: Option<usize> = match ps.get_allocation_of_resource_at(2, during_primary) {
+        Some(Allocation { holder: AllocationHolder::Phase { phase_id, .. }, .. }) => Some(phase_id),
+        _ => None
+    };
+    assert_eq!(Some(4), phase_id);
+
+    let free: Vec<usize> = ps.get_resources_free_at(during_primary).iter().map(|x| x.id).collect();
+    assert!(free.contains(&1));
+    assert!(!free.contains(&2));
+
+    let fermenting = NaivePeriod::from_start_duration(during_primary, Duration::days(60));
+    let free_over_period: Vec<usize> = ps.get_resources_free_over_period(fermenting)
+                                         .iter().map(|x| x.id).collect();
+    assert_eq!(vec![1, 3, 4, 5, 6, 7, 8, 9], free_over_period);
+
+    // Resources are retrieved as tracked, with their allocations.
+    assert_eq!(2, ps.get_resource_by_id(2).unwrap().allocated_periods.len());
+}
+
 #[test]
 fn it_should_be_able_to_convert_a_simple_bpd_file_to_a_pla_file() {
     let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
@@ -124,3 +175,271 @@ fn it_should_be_able_to_convert_a_complicated_bpd_file_to_a_pla_file() {
 fn it_should_panic_on_an_unparseable_json_file() {
     ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap()[..]);
 }
+
+#[test]
+fn it_should_return_a_parse_error_on_an_unparseable_json_file() {
+    let result = ProductionSchedule::try_new(&get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap()[..]);
+
+    match result {
+        Err(Error::Parse(_)) => {},
+        _ => panic!("Expected a parse error")
+    }
+}
+
+/// Load a BPD document with a small fermentor, FV-001, and a large one, Big Bertha, and a recipe,
+/// "Erroneous Ale", with a single primary fermentation phase, as a `Value`, so that a test can
+/// make it erroneous.
+fn get_error_bpd() -> Value {
+    let json = get_json_data_from_file("tests/fixtures/error_schedule.json").unwrap();
+    serde_json::from_str(&json[..]).unwrap()
+}
+
+/// Retrieve the error BPD document, giving its only phase a duration.
+fn get_bpd_json_with_duration(duration: &str) -> String {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["phases"][0]["duration"] = json!(duration);
+
+    bpd.to_string()
+}
+
+#[test]
+fn it_should_return_an_error_for_an_unknown_template() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["phases"].as_array_mut().unwrap().push(json!({ "template": "secondray" }));
+    let json: String = bpd.to_string();
+
+    let result = ProductionSchedule::try_new(&json[..]);
+
+    assert_eq!(Some(Error::UnknownTemplate {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 1,
+                   template: "secondray".to_string()
+               }), result.err());
+}
+
+#[test]
+fn it_should_return_an_error_for_an_unparseable_duration() {
+    let result = ProductionSchedule::try_new(&get_bpd_json_with_duration("1.5d")[..]);
+
+    assert_eq!(Some(Error::UnparseableDuration {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 0,
+                   duration: "1.5d".to_string()
+               }), result.err());
+}
+
+#[test]
+fn it_should_return_an_error_for_a_duration_too_long_to_schedule() {
+    let result = ProductionSchedule::try_new(&get_bpd_json_with_duration("999999999999999m")[..]);
+
+    assert_eq!(Some(Error::UnparseableDuration {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 0,
+                   duration: "999999999999999m".to_string()
+               }), result.err());
+}
+
+#[test]
+fn it_should_return_an_error_for_a_negative_duration() {
+    let result = ProductionSchedule::try_new(&get_bpd_json_with_duration("-5d")[..]);
+
+    assert_eq!(Some(Error::UnparseableDuration {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 0,
+                   duration: "-5d".to_string()
+               }), result.err());
+}
+
+#[test]
+fn it_should_return_an_error_for_an_unsatisfiable_resource() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["phases"].as_array_mut().unwrap().push(json!({ "template": "carbonation" }));
+    let json: String = bpd.to_string();
+
+    let result = ProductionSchedule::try_new(&json[..]);
+
+    assert_eq!(Some(Error::UnsatisfiableResource {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 1,
+                   resource_type: ResourceType::GasTank
+               }), result.err());
+}
+
+#[test]
+fn it_should_return_an_error_for_an_unparseable_recipe_start_date() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["phases"] = json!([]);
+    bpd["recipes"][0]["start"] = json!("2020-13-45");
+    let json: String = bpd.to_string();
+
+    let result = ProductionSchedule::try_new(&json[..]);
+
+    assert_eq!(Some(Error::UnparseableDate {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: None,
+                   date: "2020-13-45".to_string()
+               }), result.err());
+}
+
+#[test]
+fn it_should_allocate_a_fermentor_large_enough_for_the_batch() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["batchSize"] = json!("10g");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    let recipe = ps.get_recipe_by_name("Erroneous Ale").unwrap();
+    let primary = &recipe.phases[0];
+
+    assert_eq!(vec!["Big Bertha"],
+               primary.resources_used.iter().map(|res| &res.name[..]).collect::<Vec<&str>>());
+}
+
+#[test]
+fn it_should_return_an_error_when_no_resource_is_large_enough_for_the_batch() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["recipes"][0]["batchSize"] = json!("20g");
+    let json: String = bpd.to_string();
+
+    let result = ProductionSchedule::try_new(&json[..]);
+
+    assert_eq!(Some(Error::UnsatisfiableResource {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 0,
+                   resource_type: ResourceType::Fermentor
+               }), result.err());
+}
+
+#[test]
+fn it_should_schedule_parallel_phases_from_their_dependencies() {
+    let json = get_json_data_from_file("tests/fixtures/parallel_phases.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    let recipe = ps.get_recipe_by_name("Erroneous Ale").unwrap();
+    let primary = &recipe.phases[0];
+    let split = &recipe.phases[1];
+
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 8).and_hms(0, 0, 0), split.start_date);
+
+    // In PLA, the dependency is listed with the phase depended upon.
+    assert_eq!(vec![split.id], primary.dependencies);
+    assert!(split.dependencies.is_empty());
+}
+
+#[test]
+fn it_should_return_an_error_for_a_dependency_cycle() {
+    let json = get_json_data_from_file("tests/fixtures/dependency_cycle.json").unwrap();
+
+    let result = ProductionSchedule::try_new(&json[..]);
+
+    assert_eq!(Some(Error::DependencyCycle {
+                   recipe: "Erroneous Ale".to_string(),
+                   phase_index: 0
+               }), result.err());
+}
+
+#[test]
+fn it_should_schedule_a_recipe_after_a_phase_of_another_recipe() {
+    let json = get_json_data_from_file("tests/fixtures/cross_recipe_dependency.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    let donor_primary = &ps.get_recipe_by_name("Erroneous Ale").unwrap().phases[0];
+    let repitched_primary = &ps.get_recipe_by_name("Repitched Ale").unwrap().phases[0];
+
+    // Both fermentors are free, but the yeast isn't ready until the first batch finishes.
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 11).and_hms(0, 0, 0), repitched_primary.start_date);
+    assert_eq!(vec![repitched_primary.id], donor_primary.dependencies);
+
+    // The recipes are still output in the order they were given.
+    let names: Vec<&str> = ps.get_recipe_iterator().map(|recipe| &recipe.name[..]).collect();
+    assert_eq!(vec!["Repitched Ale", "Erroneous Ale"], names);
+}
+
+#[test]
+fn it_should_schedule_around_periods_when_resources_are_unavailable() {
+    let json = get_json_data_from_file("tests/fixtures/unavailable_fermentor.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // Only Big Bertha is large enough for the batch, so the recipe waits until it's been
+    // passivated.
+    let primary = &ps.get_recipe_by_name("Erroneous Ale").unwrap().phases[0];
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 3).and_hms(0, 0, 1), primary.start_date);
+
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("[3] Resource Downtime\n  child 4\n"));
+    assert!(pla.ends_with("  [4] Big Bertha unavailable: passivation\n    start 2019-12-30\n    color #808080\n    duration 96\n    res Big Bertha\n"));
+}
+
+#[test]
+fn it_should_return_an_error_for_an_unparseable_unavailable_period() {
+    let mut bpd: Value = get_error_bpd();
+    bpd["resources"][0]["unavailable"] = json!([ { "start": "2020-01-01", "end": "someday" } ]);
+    bpd["recipes"][0]["phases"] = json!([]);
+    let json: String = bpd.to_string();
+
+    assert_eq!(Some(Error::UnparseableUnavailablePeriod {
+                   resource: String::from("FV-001"),
+                   date: String::from("someday")
+               }),
+               ProductionSchedule::try_new(&json[..]).err());
+}
+
+#[test]
+fn it_should_output_an_icalendar_document_with_an_event_per_phase() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let timestamp: NaiveDateTime = NaiveDate::from_ymd(2020, 6, 1).and_hms(12, 30, 0);
+    let ics: String = ps.get_string_in_ics_format(None, timestamp);
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//FoamFactory//chronogrog//EN\r\n"));
+    assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    assert!(ics.contains("X-WR-CALNAME:Simple Production Schedule\r\n"));
+
+    let phase_count: usize = ps.get_recipe_iterator().map(|recipe| recipe.phases.len()).sum();
+    assert_eq!(phase_count, ics.matches("BEGIN:VEVENT").count());
+    assert!(ics.contains("SUMMARY:Damned Squirrel Mk. II: Primary Fermentation\r\n\
+                          DESCRIPTION:Recipe: Damned Squirrel Mk. II\\nPhase: Primary Fermentation\\nRe\r\n \
+                          sources: Big Bertha\r\n\
+                          RESOURCES:Big Bertha\r\n"));
+    assert!(ics.lines().all(|line| line.len() <= 75));
+
+    // Every event is stamped with the time the document was created, so the same schedule
+    // always produces the same document.
+    assert_eq!(phase_count, ics.matches("DTSTAMP:20200601T123000Z\r\n").count());
+    assert_eq!(ics, ps.get_string_in_ics_format(None, timestamp));
+}
+
+#[test]
+fn it_should_output_a_resource_view_with_the_phases_occupying_each_resource() {
+    let json = get_json_data_from_file("tests/fixtures/unavailable_fermentor.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // FV-001 is too small for the batch, so it sits empty, while Big Bertha is passivated, and
+    // then ferments the batch.
+    assert_eq!("[1] FV-001\n\
+                \n\
+                [2] Big Bertha\n\
+                \x20 child 3\n\
+                \x20 child 4\n\
+                \n\
+                \x20 [3] Unavailable: passivation\n\
+                \x20   start 2019-12-30\n\
+                \x20   color #808080\n\
+                \x20   duration 96\n\
+                \x20   res Big Bertha\n\
+                \n\
+                \x20 [4] Erroneous Ale: Primary Fermentation\n\
+                \x20   start 2020-01-03 00\n\
+                \x20   color #FFFFFF\n\
+                \x20   duration 240\n\
+                \x20   res Big Bertha\n",
+               ps.get_resource_view_in_pla_format());
+}
+
+#[test]
+fn it_should_output_an_empty_pla_document_for_an_empty_schedule() {
+    let json = get_json_data_from_file("tests/fixtures/empty_schedule.json").unwrap();
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    assert_eq!("", ps.get_string_in_pla_format());
+    assert_eq!("", ps.get_resource_view_in_pla_format());
+}
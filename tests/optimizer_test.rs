@@ -0,0 +1,103 @@
+use serde_json::{json, Value};
+
+use chronogrog::ProductionSchedule;
+use chronogrog::optimizer::{Objective, OptimizerConfig, Optimizing};
+use chronogrog::recipes::RecipeSpec;
+use chronogrog::phases::ProductionPhaseTemplate;
+use chronogrog::resources::{Resource, ResourceTracker, ResourceType};
+use chronogrog::scheduler::SchedulerKind;
+
+use chronogrog::util::get_json_data_from_file;
+
+/// Load a BPD document in which a Lager, fermenting for twenty days, is given before a Stout that
+/// also conditions for thirty, as a `Value`, so that a test can vary it before scheduling it.
+fn get_reorderable_bpd() -> Value {
+    let json = get_json_data_from_file("tests/fixtures/reorderable_schedule.json").unwrap();
+    serde_json::from_str(&json[..]).unwrap()
+}
+
+fn get_specs(json: &str) -> (Vec<RecipeSpec>, Vec<ProductionPhaseTemplate>, ResourceTracker) {
+    let ps = ProductionSchedule::new(json);
+
+    let mut tracker = ResourceTracker::new();
+    for next_resource in ps.resources() {
+        tracker.track_resource(Resource::new(next_resource.id, &next_resource.name[..],
+                                             next_resource.resource_type.clone(),
+                                             &next_resource.capacity_str[..]));
+    }
+
+    (ps.recipe_specs.clone(), ps.phase_templates.clone(), tracker)
+}
+
+#[test]
+fn it_should_reduce_the_makespan_of_a_greedy_schedule() {
+    // Consecutive allocations of a resource are a second apart, so only whole hours are compared.
+    let greedy = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/reorderable_schedule.json").unwrap()[..]);
+    assert_eq!(60.0 * 24.0, greedy.get_objective_value(Objective::Makespan).round());
+
+    let mut bpd: Value = get_reorderable_bpd();
+    bpd["timeline"]["scheduler"] = json!("optimizing");
+    let json: String = bpd.to_string();
+    let optimized = ProductionSchedule::new(&json[..]);
+    assert_eq!(40.0 * 24.0, optimized.get_objective_value(Objective::Makespan));
+
+    // The recipe with the long conditioning phase goes first.
+    let stout = optimized.get_recipe_by_name("Stout").unwrap();
+    assert_eq!(greedy.timeline.start_date().unwrap(), stout.phases[0].start_date);
+}
+
+#[test]
+fn it_should_report_the_objective_value_achieved() {
+    let json = get_json_data_from_file("tests/fixtures/reorderable_schedule.json").unwrap();
+    let (recipe_specs, templates, mut tracker) = get_specs(&json[..]);
+
+    let optimizer = Optimizing::new(OptimizerConfig::default());
+    let result = optimizer.optimize(&recipe_specs, &templates, &mut tracker).unwrap();
+
+    assert_eq!(40.0 * 24.0, result.objective_value);
+    assert_eq!(result.objective_value,
+               Objective::Makespan.evaluate(&result.recipes, &tracker.get_all_tracked_resources()));
+}
+
+#[test]
+fn it_should_be_deterministic_for_a_given_seed() {
+    let mut bpd: Value = get_reorderable_bpd();
+    bpd["timeline"]["scheduler"] = json!("optimizing");
+    bpd["timeline"]["optimizer"] = json!({ "objective": "fermentor-idle", "seed": 7, "iterations": 50 });
+    let json: String = bpd.to_string();
+
+    let first = ProductionSchedule::new(&json[..]);
+    let second = ProductionSchedule::new(&json[..]);
+
+    assert_eq!(SchedulerKind::Optimizing, first.timeline.scheduler);
+    assert_eq!(OptimizerConfig { objective: Objective::FermentorIdle, seed: 7, iterations: 50 },
+               first.timeline.optimizer);
+    assert_eq!(first.get_string_in_pla_format(), second.get_string_in_pla_format());
+}
+
+#[test]
+fn it_should_evaluate_fermentor_idle_time() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/reorderable_schedule.json").unwrap()[..]);
+
+    // Both fermentors are busy for 30 of the 60 days.
+    assert_eq!(2.0 * 30.0 * 24.0, ps.get_objective_value(Objective::FermentorIdle).round());
+}
+
+#[test]
+fn it_should_not_try_to_reorder_a_single_recipe() {
+    let mut bpd: Value = get_reorderable_bpd();
+    bpd["recipes"].as_array_mut().unwrap().remove(0);
+    let json: String = bpd.to_string();
+    let (recipe_specs, templates, mut tracker) = get_specs(&json[..]);
+
+    let optimizer = Optimizing::new(OptimizerConfig::default());
+    let result = optimizer.optimize(&recipe_specs, &templates, &mut tracker).unwrap();
+
+    assert_eq!(1, result.recipes.len());
+    assert_eq!(40.0 * 24.0, result.objective_value);
+
+    // The phases of the best schedule are allocated from the tracker given.
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor,
+        chrono_period::NaivePeriod::from_start_duration(result.recipes[0].phases[0].start_date,
+                                                        chrono::Duration::days(1))));
+}
@@ -1,7 +1,7 @@
 use chrono::Duration;
 
 use chronogrog::phases::{PhaseInstance, ProductionPhaseTemplate};
-use chronogrog::resources::ResourceType;
+use chronogrog::resources::{Resource, ResourceType};
 use chronogrog::util::get_naive_date_time_from_string;
 
 #[test]
@@ -22,6 +22,33 @@ fn it_should_deserialize_a_production_phase_template_from_json() {
    assert_eq!("Available to Drink", result.description);
 }
 
+#[test]
+fn it_should_deserialize_a_count_of_resources_needed() {
+    let json = r#"{
+        "description": "Split Fermentation",
+        "id": "split-primary",
+        "order": 2,
+        "resourcesNeeded": [ { "type": "fermentor", "count": 2 }, "gastank" ]
+    }"#;
+
+    let result: ProductionPhaseTemplate = serde_json::from_str(json).unwrap();
+    assert_eq!(vec!(ResourceType::Fermentor, ResourceType::Fermentor, ResourceType::GasTank),
+               result.resources_needed);
+}
+
+#[test]
+fn it_should_reject_a_count_of_zero_resources_needed() {
+    let json = r#"{
+        "description": "Split Fermentation",
+        "id": "split-primary",
+        "order": 2,
+        "resourcesNeeded": [ { "type": "fermentor", "count": 0 } ]
+    }"#;
+
+    let result: Result<ProductionPhaseTemplate, serde_json::Error> = serde_json::from_str(json);
+    assert!(result.is_err());
+}
+
 #[test]
 fn it_should_not_allow_for_an_empty_default_duration() {
     let funny_prod_schedule_json = r#"
@@ -111,3 +138,51 @@ fn it_should_output_standard_pla_for_a_phaseinstance_with_two_dependencies() {
     assert_eq!("[0] A phase instance\n  start 2020-01-01\n  color #FFFFFF\n  duration 240\n    dep 2\n    dep 3\n\n",
                phase_instance.get_string_in_pla_format(0));
 }
+
+#[test]
+fn it_should_reject_a_fractional_default_duration() {
+    let funny_prod_schedule_json = r#"
+        {
+            "id": "erroneous",
+            "description": "Erroneous Phase",
+            "order": 39182,
+            "defaultDuration": "1.5d"
+        }
+    "#;
+
+    let result: ProductionPhaseTemplate = serde_json::from_str(funny_prod_schedule_json).unwrap();
+
+    assert_eq!(None, result.default_duration());
+}
+
+#[test]
+fn it_should_output_a_phaseinstance_as_an_icalendar_event() {
+    let resources = vec![Resource::new(1, "Kettle", ResourceType::Kettle, "10g"),
+                         Resource::new(2, "Mash Tun", ResourceType::MashTun, "10g")];
+    let mut phase_instance = PhaseInstance::new(3, "Brewing".to_string(), "#FFFFFF".to_string(),
+                                                Duration::hours(6),
+                                                get_naive_date_time_from_string("2020-01-01 09:00:00").unwrap(),
+                                                resources);
+    let timestamp = get_naive_date_time_from_string("2019-12-01").unwrap();
+
+    assert_eq!("BEGIN:VEVENT\r\n\
+                UID:1-3@chronogrog\r\n\
+                DTSTAMP:20191201T000000Z\r\n\
+                DTSTART:20200101T090000\r\n\
+                DURATION:PT6H\r\n\
+                SUMMARY:Kolsch: Brewing\r\n\
+                DESCRIPTION:Recipe: Kolsch\\nPhase: Brewing\\nResources: Kettle\\, Mash Tun\r\n\
+                RESOURCES:Kettle,Mash Tun\r\n\
+                END:VEVENT\r\n",
+               phase_instance.get_string_in_ics_format("Kolsch", 1, Some(Duration::hours(1)),
+                                                       timestamp));
+
+    // Only hands-on phases get reminders.
+    phase_instance.is_hands_on = true;
+    let event: String = phase_instance.get_string_in_ics_format("Kolsch", 1,
+                                                                Some(Duration::hours(1)),
+                                                                timestamp);
+    assert!(event.contains("BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT1H\r\nDESCRIPTION:Kolsch: Brewing\r\nEND:VALARM\r\nEND:VEVENT\r\n"));
+    assert!(!phase_instance.get_string_in_ics_format("Kolsch", 1, None, timestamp)
+                           .contains("VALARM"));
+}
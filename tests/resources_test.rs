@@ -5,9 +5,14 @@ use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
 extern crate chrono_period;
 use chrono_period::NaivePeriod;
 
+use chronogrog::resources::{Allocation, AllocationHolder};
 use chronogrog::resources::Resource;
 use chronogrog::resources::ResourceTracker;
+use chronogrog::resources::ResourceRequest;
 use chronogrog::resources::ResourceType;
+use chronogrog::resources::SelectionPolicy;
+use chronogrog::resources::UnavailablePeriod;
+use chronogrog::volume::Volume;
 
 extern crate serde_test;
 use serde_test::{Token, assert_tokens};
@@ -333,3 +338,338 @@ fn test_long_term_allocation() {
     assert!(first_available_date.is_some());
     assert_eq!(NaiveDate::from_ymd(2020, 10, 15).and_hms(4, 0, 1), first_available_date.unwrap());
 }
+
+#[test]
+fn test_allocate_resource_for_request_prefers_smallest_resource_that_fits() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "Big Bertha", ResourceType::Fermentor, "14g"));
+    tracker.track_resource(Resource::new(2, "FV-001", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(3, "FV-002", ResourceType::Fermentor, "40l"));
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                                  Duration::days(10));
+
+    let small_batch = ResourceRequest::new(ResourceType::Fermentor,
+                                           Volume::parse("5g"));
+    let allocated = tracker.allocate_resource_for_request_over_period(&small_batch, period);
+    assert_eq!("FV-001", allocated.unwrap().name);
+
+    // FV-001 is now in use, so the next smallest fermentor that fits (40l) should be used.
+    let allocated = tracker.allocate_resource_for_request_over_period(&small_batch, period);
+    assert_eq!("FV-002", allocated.unwrap().name);
+
+    let large_batch = ResourceRequest::new(ResourceType::Fermentor,
+                                           Volume::parse("12g"));
+    let allocated = tracker.allocate_resource_for_request_over_period(&large_batch, period);
+    assert_eq!("Big Bertha", allocated.unwrap().name);
+}
+
+#[test]
+fn test_request_only_considers_resources_that_fit_the_batch() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "FV-001", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(2, "Scott's CO2 Tank", ResourceType::GasTank, "5g"));
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                                  Duration::days(10));
+
+    let fermentor_request = ResourceRequest::new(ResourceType::Fermentor, Volume::parse("14g"));
+    assert!(!tracker.is_resource_free_for_request_over_period(&fermentor_request, period));
+    assert!(tracker.get_next_available_resource_date_for_request_over_period(&fermentor_request,
+                                                                             period).is_none());
+    assert!(tracker.allocate_resource_for_request_over_period(&fermentor_request,
+                                                              period).is_none());
+
+    // Gas tanks don't hold the batch, so their capacity isn't compared with the batch size.
+    let gas_request = ResourceRequest::new(ResourceType::GasTank, Volume::parse("14g"));
+    assert!(tracker.allocate_resource_for_request_over_period(&gas_request, period).is_some());
+}
+
+#[test]
+fn test_allocate_resources_for_request_splits_a_batch_across_resources() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(6, "Keg 001", ResourceType::Keg, "5g"));
+    tracker.track_resource(Resource::new(7, "Keg 002", ResourceType::Keg, "5g"));
+    tracker.track_resource(Resource::new(8, "Keg 003", ResourceType::Keg, "5g"));
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                                  Duration::days(10));
+
+    let request = ResourceRequest::new(ResourceType::Keg, Volume::parse("10g"));
+    let allocated = tracker.allocate_resources_for_request_over_period(&request, period).unwrap();
+
+    assert_eq!(vec![6, 7], allocated.iter().map(|res| res.id).collect::<Vec<usize>>());
+
+    // Only one keg is left, so the batch can't be split again until the others are free.
+    assert!(tracker.allocate_resources_for_request_over_period(&request, period).is_none());
+}
+
+#[test]
+fn test_allocate_resources_for_request_with_a_count_shares_the_batch() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "FV-001", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(3, "Big Bertha", ResourceType::Fermentor, "14g"));
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                                  Duration::days(10));
+
+    // Each fermentor holds half of the batch, so the two smaller ones are preferred, even though
+    // Big Bertha could hold the whole batch on its own.
+    let requests = ResourceRequest::group(&[ResourceType::Fermentor, ResourceType::Fermentor],
+                                          Volume::parse("10g"));
+    assert_eq!(1, requests.len());
+    assert_eq!(2, requests[0].count);
+
+    let allocated = tracker.allocate_resources_for_request_over_period(&requests[0], period).unwrap();
+    assert_eq!(vec![1, 2], allocated.iter().map(|res| res.id).collect::<Vec<usize>>());
+
+    // Only one fermentor is left, so a second request for two can't be satisfied.
+    assert!(tracker.allocate_resources_for_request_over_period(&requests[0], period).is_none());
+}
+
+#[test]
+fn test_allocate_resources_for_request_prefers_a_single_resource() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "FV-001", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(3, "Big Bertha", ResourceType::Fermentor, "14g"));
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                                  Duration::days(10));
+
+    let request = ResourceRequest::new(ResourceType::Fermentor, Volume::parse("10g"));
+    let allocated = tracker.allocate_resources_for_request_over_period(&request, period).unwrap();
+
+    assert_eq!(vec!["Big Bertha"], allocated.iter().map(|res| &res.name[..]).collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_next_available_date_for_a_split_batch_waits_for_all_resources() {
+    // Keg 001 Allocation |----------|
+    // Keg 002 Allocation       |----------|
+    // Request (2 kegs)      |---|
+    // Available                           |---|
+    let mut keg1 = Resource::new(6, "Keg 001", ResourceType::Keg, "5g");
+    keg1.allocate_over_start_duration(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0),
+                                      Duration::days(10));
+
+    let mut keg2 = Resource::new(7, "Keg 002", ResourceType::Keg, "5g");
+    keg2.allocate_over_start_duration(NaiveDate::from_ymd(2020, 1, 5).and_hms(0, 0, 0),
+                                      Duration::days(10));
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(keg1);
+    tracker.track_resource(keg2);
+
+    let period = NaivePeriod::from_start_duration(NaiveDate::from_ymd(2020, 1, 3).and_hms(0, 0, 0),
+                                                  Duration::days(3));
+
+    let single_keg = ResourceRequest::new(ResourceType::Keg, Volume::parse("5g"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 11).and_hms(0, 0, 1),
+               tracker.get_next_available_resource_date_for_request_over_period(&single_keg, period)
+                      .unwrap());
+
+    let two_kegs = ResourceRequest::new(ResourceType::Keg, Volume::parse("10g"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 15).and_hms(0, 0, 1),
+               tracker.get_next_available_resource_date_for_request_over_period(&two_kegs, period)
+                      .unwrap());
+
+    let too_large = ResourceRequest::new(ResourceType::Keg, Volume::parse("15g"));
+    assert!(tracker.get_next_available_resource_date_for_request_over_period(&too_large, period)
+                   .is_none());
+}
+
+#[test]
+fn it_should_allocate_unavailable_periods_when_tracking_a_resource() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.unavailable_periods = vec![UnavailablePeriod {
+        start_string: String::from("2020-01-01"),
+        end_string: String::from("2020-01-03"),
+        reason: Some(String::from("cleaning"))
+    }];
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(fermentor);
+
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 2), NaiveTime::from_hms(0, 0, 0));
+    let period = NaivePeriod::from_start_duration(start, Duration::days(1));
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor, period));
+
+    let later = NaivePeriod::from_start_duration(start + Duration::days(2), Duration::days(1));
+    assert!(tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor, later));
+}
+
+#[test]
+fn it_should_leave_a_resources_turnaround_between_allocations() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.turnaround_string = Some(String::from("1d"));
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(fermentor);
+
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 1), NaiveTime::from_hms(0, 0, 0));
+    let first = NaivePeriod::from_start_duration(start, Duration::days(2));
+    assert!(tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor, first).is_some());
+
+    // The fermentor is empty from the 3rd, but isn't clean until the 4th.
+    let request = ResourceRequest::new(ResourceType::Fermentor, None);
+    let second = NaivePeriod::from_start_duration(start + Duration::days(2), Duration::days(1));
+    assert_eq!(Some(start + Duration::days(3) + Duration::seconds(1)),
+               tracker.get_next_available_resource_date_for_request_over_period(&request, second));
+
+    // Nor can it be used just before the first allocation, since it wouldn't be clean in time.
+    let before = NaivePeriod::from_start_duration(start - Duration::hours(12), Duration::hours(6));
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor, before));
+}
+
+#[test]
+fn it_should_report_an_unparseable_turnaround() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    assert_eq!(Some(Duration::zero()), fermentor.turnaround());
+
+    fermentor.turnaround_string = Some(String::from("a while"));
+    assert_eq!(None, fermentor.turnaround());
+}
+
+#[test]
+fn it_should_record_what_each_allocation_of_a_resource_is_held_by() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.unavailable_periods = vec![UnavailablePeriod {
+        start_string: String::from("2020-01-01"),
+        end_string: String::from("2020-01-03"),
+        reason: Some(String::from("cleaning"))
+    }];
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(fermentor);
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 5), NaiveTime::from_hms(0, 0, 0));
+    let primary = NaivePeriod::from_start_duration(start, Duration::days(7));
+    let holder = AllocationHolder::Phase {
+        recipe: String::from("Damned Squirrel"),
+        phase_id: 4,
+        description: String::from("Primary Fermentation")
+    };
+    let request = ResourceRequest::new(ResourceType::Fermentor, None);
+    let allocated = tracker.allocate_resources_for_request_over_period_to_holder(&request, primary,
+                                                                                 holder.clone());
+    assert_eq!(1, allocated.unwrap()[0].id);
+
+    let secondary = NaivePeriod::from_start_duration(start + Duration::days(7), Duration::days(7));
+    tracker.allocate_resources_for_request_over_period(&request, secondary);
+
+    let allocations: Vec<Allocation> = tracker.get_allocations_of_resource(1);
+    assert_eq!(2, allocations.len());
+    assert_eq!(AllocationHolder::Unavailable { reason: Some(String::from("cleaning")) },
+               allocations[0].holder);
+    assert_eq!(holder, allocations[1].holder);
+    assert_eq!(primary, allocations[1].period);
+
+    // The secondary phase couldn't use FV-001 straight away, so it went to FV-002, without a
+    // holder.
+    assert_eq!(AllocationHolder::Unknown, tracker.get_allocations_of_resource(2)[0].holder);
+
+    let during_primary = start + Duration::days(1);
+    assert_eq!(Some(holder), tracker.get_allocation_of_resource_at(1, during_primary)
+                                    .map(|allocation| allocation.holder));
+    assert_eq!(None, tracker.get_allocation_of_resource_at(1, start - Duration::days(1)));
+    assert!(tracker.get_allocations_of_resource(3).is_empty());
+}
+
+#[test]
+fn it_should_retrieve_the_resources_free_at_a_date_and_over_a_period() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.turnaround_string = Some(String::from("1d"));
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(fermentor);
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 1), NaiveTime::from_hms(0, 0, 0));
+    let first = NaivePeriod::from_start_duration(start, Duration::days(2));
+    tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor, first);
+
+    let get_ids = |resources: Vec<Resource>| -> Vec<usize> {
+        resources.iter().map(|res| res.id).collect()
+    };
+
+    assert_eq!(vec![2], get_ids(tracker.get_resources_free_at(start + Duration::days(1))));
+
+    // FV-001 is still within its turnaround on the 3rd, and isn't held by anything.
+    let turning_around = start + Duration::days(2) + Duration::hours(12);
+    assert_eq!(vec![2], get_ids(tracker.get_resources_free_at(turning_around)));
+    assert_eq!(None, tracker.get_allocation_of_resource_at(1, turning_around));
+    assert_eq!(vec![1, 2], get_ids(tracker.get_resources_free_at(start + Duration::days(4))));
+
+    let overlapping = NaivePeriod::from_start_duration(start + Duration::days(2), Duration::days(2));
+    assert_eq!(vec![2], get_ids(tracker.get_resources_free_over_period(overlapping)));
+    assert_eq!(Some(String::from("FV-001")), tracker.get_resource(1).map(|res| res.name));
+    assert_eq!(None, tracker.get_resource(3));
+}
+
+/// Track three fermentors, holding 10, 7 and 5 gallons, last used on the 4th, the 2nd and the 6th
+/// of January 2020, respectively, using a given `SelectionPolicy`.
+fn get_used_fermentor_tracker(policy: SelectionPolicy) -> ResourceTracker {
+    let mut tracker = ResourceTracker::with_selection_policy(policy);
+    let uses = [(1, "10g", 3, None), (2, "7g", 1, Some(5)), (3, "5g", 5, Some(5))];
+    for (id, capacity, day, priority) in uses.iter() {
+        let mut fermentor = Resource::new(*id, &format!("FV-00{}", id)[..], ResourceType::Fermentor,
+                                          capacity);
+        let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, *day), NaiveTime::from_hms(0, 0, 0));
+        fermentor.allocated_periods.push(NaivePeriod::from_start_duration(start, Duration::days(1)));
+        fermentor.priority = *priority;
+
+        tracker.track_resource(fermentor);
+    }
+
+    tracker
+}
+
+#[test]
+fn it_should_select_resources_according_to_the_selection_policy() {
+    let start = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 10), NaiveTime::from_hms(0, 0, 0));
+    let period = NaivePeriod::from_start_duration(start, Duration::days(7));
+    let request = ResourceRequest::new(ResourceType::Fermentor, Volume::parse("5g"));
+
+    let expectations = [
+        (SelectionPolicy::LowestId, 1),
+        (SelectionPolicy::BestFit, 3),
+        (SelectionPolicy::LeastRecentlyUsed, 2),
+        (SelectionPolicy::MostRecentlyUsed, 3),
+
+        // FV-002 and FV-003 have the same priority, so the one with the lower id wins.
+        (SelectionPolicy::Priority, 2)
+    ];
+
+    for (policy, expected_id) in expectations.iter() {
+        let mut tracker = get_used_fermentor_tracker(*policy);
+        let allocated = tracker.allocate_resources_for_request_over_period(&request, period).unwrap();
+
+        assert_eq!(*expected_id, allocated[0].id, "{}", policy.as_str());
+    }
+
+    assert_eq!(SelectionPolicy::BestFit, SelectionPolicy::default());
+}
+
+#[test]
+fn it_should_not_count_unavailable_periods_as_uses() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.unavailable_periods = vec![UnavailablePeriod {
+        start_string: String::from("2020-01-05"),
+        end_string: String::from("2020-01-06"),
+        reason: None
+    }];
+
+    let used = NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, 1), NaiveTime::from_hms(0, 0, 0));
+    fermentor.allocated_periods.push(NaivePeriod::from_start_duration(used, Duration::days(1)));
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(fermentor);
+
+    let later = used + Duration::days(10);
+    assert_eq!(Some(used + Duration::days(1)),
+               tracker.get_resource(1).unwrap().get_last_use_before(later));
+    assert_eq!(None, tracker.get_resource(1).unwrap().get_last_use_before(used));
+}
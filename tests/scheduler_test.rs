@@ -0,0 +1,440 @@
+use chrono::NaiveDate;
+
+use serde_json::{json, Value};
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::phases::ProductionPhaseTemplate;
+use chronogrog::recipes::RecipeSpec;
+use chronogrog::resources::{Resource, ResourceTracker, ResourceType};
+use chronogrog::scheduler::{GreedySequential, Scheduler, SchedulerKind};
+
+use chronogrog::util::get_json_data_from_file;
+
+/// Load a BPD document in which a four week Barleywine and a ten day Kolsch compete for a single
+/// fermentor, as a `Value`, so that a test can vary it before scheduling it.
+fn get_competing_bpd() -> Value {
+    let json = get_json_data_from_file("tests/fixtures/competing_schedule.json").unwrap();
+    serde_json::from_str(&json[..]).unwrap()
+}
+
+fn get_start_date_of_recipe(ps: &ProductionSchedule, name: &str) -> NaiveDate {
+    ps.get_recipe_by_name(name).unwrap().phases[0].start_date.date()
+}
+
+#[test]
+fn it_should_default_to_the_greedy_sequential_scheduler() {
+    let ps = ProductionSchedule::new(&get_competing_bpd().to_string()[..]);
+
+    assert_eq!(SchedulerKind::GreedySequential, ps.timeline.scheduler);
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 29), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_select_a_scheduler_from_the_timeline_block() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["timeline"]["scheduler"] = json!("shortest-total-duration-first");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    assert_eq!(SchedulerKind::ShortestTotalDurationFirst, ps.timeline.scheduler);
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Kolsch"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 11), get_start_date_of_recipe(&ps, "Barleywine"));
+
+    // Recipes are still output in the order they were given.
+    let names: Vec<&str> = ps.get_recipe_iterator().map(|recipe| &recipe.name[..]).collect();
+    assert_eq!(vec!["Barleywine", "Kolsch"], names);
+}
+
+#[test]
+fn it_should_override_the_scheduler_from_the_timeline_block() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["timeline"]["scheduler"] = json!("shortest-total-duration-first");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::try_new_with_scheduler(&json[..],
+                                                        SchedulerKind::GreedySequential).unwrap();
+
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Barleywine"));
+}
+
+#[test]
+fn it_should_convert_scheduler_kinds_to_and_from_names() {
+    for next_name in SchedulerKind::NAMES.iter() {
+        assert_eq!(*next_name, SchedulerKind::from_name(next_name).unwrap().as_str());
+    }
+
+    assert_eq!(None, SchedulerKind::from_name("fastest"));
+}
+
+#[test]
+fn it_should_schedule_recipe_specs_directly_with_a_scheduler() {
+    let templates: Vec<ProductionPhaseTemplate> = serde_json::from_str(r#"[
+        { "description": "Brewing", "id": "brewing", "order": 0, "defaultDuration": "1d", "resourcesNeeded": [ "kettle" ] }
+    ]"#).unwrap();
+
+    let recipe_specs: Vec<RecipeSpec> = serde_json::from_str(r##"[
+        { "name": "First", "color": "#FFFFFF", "start": "2020-01-01", "phases": [ { "template": "brewing" } ] },
+        { "name": "Second", "color": "#FFFFFF", "start": "2020-01-01", "phases": [ { "template": "brewing" } ] }
+    ]"##).unwrap();
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "Kettle", ResourceType::Kettle, "10g"));
+
+    let recipes = GreedySequential.schedule(&recipe_specs, &templates, &mut tracker).unwrap();
+
+    assert_eq!(2, recipes.len());
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0), recipes[0].phases[0].start_date);
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 2).and_hms(0, 0, 1), recipes[1].phases[0].start_date);
+}
+
+#[test]
+fn it_should_start_a_recipe_as_late_as_its_ready_by_date_allows() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][0]["readyBy"] = json!("2020-03-01");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    assert_eq!(NaiveDate::from_ymd(2020, 2, 2), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 2, 2),
+               ps.get_recipe_by_name("Barleywine").unwrap().start_date.date());
+
+    // Starting the Barleywine late leaves the fermentor free for the Kolsch in the meantime.
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_work_backwards_around_resources_already_allocated() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][0]["start"] = json!("2020-01-20");
+    bpd["recipes"][1]["deadline"] = json!("2020-02-01");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // The fermentor is busy from the 20th, so the Kolsch has to be finished before then.
+    let kolsch = ps.get_recipe_by_name("Kolsch").unwrap();
+    let kolsch_end = kolsch.phases[0].start_date + kolsch.phases[0].duration;
+    assert!(kolsch_end <= NaiveDate::from_ymd(2020, 1, 20).and_hms(0, 0, 0));
+    assert!(kolsch_end >= NaiveDate::from_ymd(2020, 1, 19).and_hms(0, 0, 0));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 20), get_start_date_of_recipe(&ps, "Barleywine"));
+}
+
+#[test]
+fn it_should_find_the_latest_start_on_either_side_of_a_blackout() {
+    // Only the Kolsch is left to ferment, and it can't start during the blackout, so it can be
+    // ready in time starting before the blackout, or, if it's due late enough, after it.
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"].as_array_mut().unwrap().remove(0);
+    bpd["timeline"]["blackouts"] = json!([ { "start": "2020-01-18", "end": "2020-01-22" } ]);
+
+    bpd["recipes"][0]["readyBy"] = json!("2020-02-05");
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+    let primary = &ps.get_recipe_by_name("Kolsch").unwrap().phases[0];
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 26).and_hms(0, 0, 0), primary.start_date);
+
+    // Starting within the blackout, or just after it, is too late, so it starts just before it.
+    bpd["recipes"][0]["readyBy"] = json!("2020-01-31");
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+    let primary = &ps.get_recipe_by_name("Kolsch").unwrap().phases[0];
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 17).and_hms(23, 0, 0), primary.start_date);
+}
+
+#[test]
+fn it_should_report_the_bottleneck_resource_when_a_ready_by_date_is_missed() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][1]["readyBy"] = json!("2020-02-05");
+    let json: String = bpd.to_string();
+
+    let error = ProductionSchedule::try_new(&json[..]).err().unwrap();
+
+    // The Barleywine holds the only fermentor until after the Kolsch had to be ready.
+    assert_eq!(Error::DeadlineMissed {
+                   recipe: String::from("Kolsch"),
+                   ready_by: String::from("2020-02-05"),
+                   bottleneck: Some(ResourceType::Fermentor),
+                   blocking_resources: vec![String::from("FV-001")]
+               }, error);
+    assert_eq!("recipe 'Kolsch': can't be ready by '2020-02-05', because resources of type 'fermentor' aren't free in time (held: 'FV-001')",
+               error.to_string());
+}
+
+#[test]
+fn it_should_report_a_ready_by_date_missed_without_any_bottleneck() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][0]["readyBy"] = json!("2020-01-15");
+    let json: String = bpd.to_string();
+    let error = ProductionSchedule::try_new(&json[..]).err().unwrap();
+
+    assert_eq!(Error::DeadlineMissed {
+                   recipe: String::from("Barleywine"),
+                   ready_by: String::from("2020-01-15"),
+                   bottleneck: None,
+                   blocking_resources: vec![]
+               }, error);
+    assert_eq!("recipe 'Barleywine': can't be ready by '2020-01-15', even if it starts on its start date",
+               error.to_string());
+}
+
+#[test]
+fn it_should_not_start_a_recipe_before_its_not_before_date() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][0]["notBefore"] = json!("2020-03-01");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    assert_eq!(NaiveDate::from_ymd(2020, 3, 1), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_not_start_a_phase_within_a_blackout_of_the_timeline() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["timeline"]["blackouts"] = json!([ { "start": "2019-12-30", "end": "2020-01-05" } ]);
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // The Barleywine starts after the blackout, rather than within it. The Kolsch starts after
+    // the Barleywine releases the fermentor, which is outside the blackout anyway.
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 5), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 2, 2), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_keep_whole_phases_of_selected_templates_out_of_a_blackout() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][1]["blackouts"] = json!([
+        { "start": "2020-01-20", "end": "2020-01-31", "wholePhaseTemplates": [ "primary" ] }
+    ]);
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    // The Barleywine started before the Kolsch's blackout, which doesn't apply to it. The Kolsch
+    // can't run during the blackout at all, so it waits until the blackout ends.
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 31), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_report_the_bottleneck_resource_when_a_not_after_date_is_missed() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["recipes"][1]["notAfter"] = json!("2020-01-15");
+    let json: String = bpd.to_string();
+    let error = ProductionSchedule::try_new(&json[..]).err().unwrap();
+
+    assert_eq!(Error::LateStart {
+                   recipe: String::from("Kolsch"),
+                   not_after: String::from("2020-01-15"),
+                   bottleneck: Some(ResourceType::Fermentor),
+                   blocking_resources: vec![String::from("FV-001")]
+               }, error);
+}
+
+#[test]
+fn it_should_use_the_tighter_of_the_timeline_and_recipe_windows() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["timeline"]["notBefore"] = json!("2020-01-10");
+    bpd["recipes"][1]["notBefore"] = json!("2020-01-05");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 10), get_start_date_of_recipe(&ps, "Barleywine"));
+    assert_eq!(NaiveDate::from_ymd(2020, 2, 7), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_only_start_hands_on_phases_within_working_time() {
+    let json = r##"{
+        "name": "Weekend Schedule",
+        "id": 1,
+        "timeline": {
+            "configuration": "calendar",
+            "start": "2020-01-01",
+            "workingCalendar": {
+                "weekdays": [ "saturday", "sunday" ],
+                "hours": { "start": "08:00", "end": "18:00" }
+            }
+        },
+        "resources": [
+            { "id": 1, "name": "Kettle", "type": "kettle", "capacity": "10g" },
+            { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Planning", "id": "planning", "order": 0, "defaultDuration": "4h" },
+            {
+                "description": "Brewing",
+                "id": "brewing",
+                "order": 1,
+                "defaultDuration": "6h",
+                "handsOn": true,
+                "resourcesNeeded": [ "kettle" ]
+            },
+            {
+                "description": "Primary Fermentation",
+                "id": "primary",
+                "order": 2,
+                "defaultDuration": "10d",
+                "resourcesNeeded": [ "fermentor" ]
+            }
+        ],
+        "recipes": [
+            {
+                "name": "Weekend Pale Ale",
+                "color": "#FFFFFF",
+                "phases": [ { "template": "planning" }, { "template": "brewing" }, { "template": "primary" } ]
+            }
+        ]
+    }"##;
+
+    let ps = ProductionSchedule::new(json);
+    let phases = &ps.get_recipe_by_name("Weekend Pale Ale").unwrap().phases;
+
+    // Planning ends at 04:00 on Wednesday, but brewing waits for 08:00 on Saturday. Fermentation
+    // isn't hands-on, so it starts as soon as brewing ends, at 14:00.
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0), phases[0].start_date);
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 4).and_hms(8, 0, 0), phases[1].start_date);
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 4).and_hms(14, 0, 0), phases[2].start_date);
+    assert_eq!(vec![false, true, false], phases.iter().map(|x| x.is_hands_on).collect::<Vec<bool>>());
+}
+
+#[test]
+fn it_should_leave_the_turnaround_of_a_resource_type_between_uses() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["turnarounds"] = json!([ { "type": "fermentor", "duration": "2d" } ]);
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 31), get_start_date_of_recipe(&ps, "Kolsch"));
+
+    // A resource's own turnaround takes precedence over that of its type.
+    bpd["resources"][0]["turnaround"] = json!("1d");
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 30), get_start_date_of_recipe(&ps, "Kolsch"));
+}
+
+#[test]
+fn it_should_not_leave_a_turnaround_around_a_resources_downtime() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["resources"][0]["turnaround"] = json!("2d");
+    bpd["resources"][0]["unavailable"] = json!([
+        { "start": "2019-12-30", "end": "2020-01-03", "reason": "passivation" },
+        { "start": "2020-01-14", "end": "2020-01-16", "reason": "inspection" }
+    ]);
+    bpd["recipes"].as_array_mut().unwrap().remove(0);
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+
+    // With only the Kolsch to ferment, FV-001 is ready as soon as it's been passivated, and may
+    // be used up to the day before it's inspected, since neither is a use that needs turning
+    // around.
+    let primary = &ps.get_recipe_by_name("Kolsch").unwrap().phases[0];
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 3).and_hms(0, 0, 1), primary.start_date);
+}
+
+#[test]
+fn it_should_optionally_show_turnarounds_as_tasks_in_pla_output() {
+    let mut bpd: Value = get_competing_bpd();
+    bpd["resources"][0]["turnaround"] = json!("12h");
+    bpd["timeline"]["showTurnarounds"] = json!(true);
+    let ps = ProductionSchedule::new(&bpd.to_string()[..]);
+    let pla = ps.get_string_in_pla_format();
+
+    assert!(pla.contains("[5] Resource Turnaround\n  child 6\n  child 7\n"));
+    assert!(pla.contains("  [6] FV-001 turnaround\n    start 2020-01-29\n    color #B0B0B0\n    duration 12\n    res FV-001\n"));
+
+    // Turnarounds are hidden unless asked for.
+    bpd["timeline"].as_object_mut().unwrap().remove("showTurnarounds");
+    assert!(!ProductionSchedule::new(&bpd.to_string()[..]).get_string_in_pla_format().contains("turnaround"));
+}
+
+fn get_fermentors_used(ps: &ProductionSchedule) -> Vec<String> {
+    ps.get_recipe_iterator()
+      .map(|recipe| recipe.phases[0].resources_used[0].name.clone())
+      .collect()
+}
+
+#[test]
+fn it_should_select_resources_by_the_policy_given_in_the_timeline_block() {
+    // Three recipes, each fermenting for ten days, started two weeks apart, so that any of them
+    // could use either fermentor.
+    let json = get_json_data_from_file("tests/fixtures/spaced_schedule.json").unwrap();
+    let ps = ProductionSchedule::new(&json[..]);
+    assert_eq!(vec!["FV-001", "FV-001", "FV-001"], get_fermentors_used(&ps));
+
+    let mut bpd: Value = serde_json::from_str(&json[..]).unwrap();
+    bpd["timeline"]["resourceSelection"] = json!("least-recently-used");
+    let json: String = bpd.to_string();
+    let ps = ProductionSchedule::new(&json[..]);
+    assert_eq!(vec!["FV-001", "FV-002", "FV-001"], get_fermentors_used(&ps));
+
+    // The same input always produces the same schedule.
+    assert_eq!(get_fermentors_used(&ps), get_fermentors_used(&ProductionSchedule::new(&json[..])));
+}
+
+/// Load a BPD document with two 5 gallon fermentors, "Big Bertha", and a keg, in which a 5 gallon
+/// Kolsch and a "Pinned Ale" start fermenting on the same day, giving the "Pinned Ale" a batch size,
+/// and its phase a list of resources under the given key.
+fn get_pinned_bpd_json(batch_size: &str, key: &str, resources: Value) -> String {
+    let json = get_json_data_from_file("tests/fixtures/pinned_schedule.json").unwrap();
+    let mut bpd: Value = serde_json::from_str(&json[..]).unwrap();
+    bpd["recipes"][1]["batchSize"] = json!(batch_size);
+    bpd["recipes"][1]["phases"][0][key] = resources;
+
+    bpd.to_string()
+}
+
+fn get_resource_names_of_recipe(ps: &ProductionSchedule, name: &str) -> Vec<String> {
+    ps.get_recipe_by_name(name).unwrap().phases[0].resources_used.iter()
+      .map(|res| res.name.clone())
+      .collect()
+}
+
+#[test]
+fn it_should_only_allocate_the_resources_a_phase_is_pinned_to() {
+    // The first recipe takes FV-001, so the pinned recipe waits for it, even though FV-002 is free.
+    let json = get_pinned_bpd_json("5g", "pinnedResources", json!([ "FV-001" ]));
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    assert_eq!(vec!["FV-001"], get_resource_names_of_recipe(&ps, "Kolsch"));
+    assert_eq!(vec!["FV-001"], get_resource_names_of_recipe(&ps, "Pinned Ale"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 11), get_start_date_of_recipe(&ps, "Pinned Ale"));
+
+    // Pinned resources can be given by id, and are chosen among if more are pinned than needed.
+    let json = get_pinned_bpd_json("5g", "pinnedResources", json!([ 3, "FV-002" ]));
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    assert_eq!(vec!["FV-002"], get_resource_names_of_recipe(&ps, "Pinned Ale"));
+    assert_eq!(NaiveDate::from_ymd(2020, 1, 1), get_start_date_of_recipe(&ps, "Pinned Ale"));
+}
+
+#[test]
+fn it_should_never_allocate_the_resources_a_phase_excludes() {
+    let json = get_pinned_bpd_json("5g", "excludedResources", json!([ "FV-002", 1 ]));
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    assert_eq!(vec!["Big Bertha"], get_resource_names_of_recipe(&ps, "Pinned Ale"));
+}
+
+#[test]
+fn it_should_report_pinned_resources_that_can_never_be_free() {
+    // The batch can be split across fermentors, but not even both pinned ones can hold it.
+    let json = get_pinned_bpd_json("12g", "pinnedResources", json!([ "FV-001", "FV-002" ]));
+    let error = ProductionSchedule::try_new(&json[..]).err();
+    assert_eq!(Some(Error::UnsatisfiablePin {
+        recipe: String::from("Pinned Ale"),
+        phase_index: 0,
+        resources: vec![String::from("FV-001"), String::from("FV-002")]
+    }), error);
+    assert_eq!("recipe 'Pinned Ale', phase 0: pinned resources 'FV-001', 'FV-002' can never be free for this phase",
+               error.unwrap().to_string());
+
+    // The phase doesn't need a keg at all.
+    let json = get_pinned_bpd_json("5g", "pinnedResources", json!([ "Keg 001" ]));
+    assert_eq!(Some(Error::UnsatisfiablePin {
+        recipe: String::from("Pinned Ale"),
+        phase_index: 0,
+        resources: vec![String::from("Keg 001")]
+    }), ProductionSchedule::try_new(&json[..]).err());
+
+    let json = get_pinned_bpd_json("5g", "excludedResources", json!([ "FV-003" ]));
+    assert_eq!(Some(Error::UnknownResourceReference {
+        recipe: String::from("Pinned Ale"),
+        phase_index: 0,
+        resource: String::from("FV-003")
+    }), ProductionSchedule::try_new(&json[..]).err());
+}
@@ -0,0 +1,84 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chrono_period::NaivePeriod;
+
+use chronogrog::ProductionSchedule;
+use chronogrog::phases::PhaseInstance;
+use chronogrog::recipes::Recipe;
+use chronogrog::svg::get_string_in_svg_format;
+
+use chronogrog::util::get_json_data_from_file;
+
+fn get_date(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(2020, 1, day).and_hms(hour, 0, 0)
+}
+
+/// Construct a recipe whose brewing is followed straight away by fermentation, and whose
+/// conditioning waits five days longer than fermentation needs it to.
+fn get_kolsch() -> Recipe {
+    let mut brewing = PhaseInstance::new(2, "Brewing".to_string(), "#F0E68C".to_string(),
+                                         Duration::days(1), get_date(1, 0), vec![]);
+    brewing.add_dependency(3);
+
+    let mut fermentation = PhaseInstance::new(3, "Primary Fermentation".to_string(),
+                                              "#7A5624".to_string(), Duration::days(10),
+                                              get_date(2, 0), vec![]);
+    fermentation.add_dependency(4);
+
+    let conditioning = PhaseInstance::new(4, "Conditioning <cold>".to_string(),
+                                          "not a color".to_string(), Duration::hours(36),
+                                          get_date(17, 0), vec![]);
+
+    Recipe {
+        id: 1,
+        name: "Kolsch & Friends".to_string(),
+        color: "#F0E68C".to_string(),
+        phases: vec![brewing, fermentation, conditioning],
+        start_date: get_date(1, 0),
+        batch_size: None
+    }
+}
+
+#[test]
+fn it_should_output_recipes_as_an_svg_chart() {
+    let svg: String = get_string_in_svg_format("Summer Schedule", &[get_kolsch()],
+                                               NaivePeriod::new(get_date(1, 0), get_date(19, 0)),
+                                               &[]);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("<title>Summer Schedule</title>"));
+
+    // One bar per phase, colored by the phase, or in the default color if it has none.
+    assert_eq!(3, svg.matches("<rect x=").count());
+    assert!(svg.contains("fill=\"#F0E68C\"><title>Kolsch &amp; Friends: Brewing\n2020-01-01 00:00 to 2020-01-02 00:00</title>"));
+    assert!(svg.contains("fill=\"#7A5624\""));
+    assert!(svg.contains("fill=\"#4682B4\""));
+    assert!(svg.contains(">Conditioning &lt;cold&gt;</text>"));
+
+    // An arrow for each dependency, and no resource view without lanes.
+    assert_eq!(2, svg.matches("marker-end=\"url(#arrow)\"").count());
+    assert!(!svg.contains("class=\"resources\""));
+
+    // The axis starts at the start of the period.
+    assert!(svg.contains(">2020-01-01</text>"));
+}
+
+#[test]
+fn it_should_include_resource_lanes_in_svg_output_if_asked() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let without_lanes: String = ps.get_string_in_svg_format(false);
+    assert!(!without_lanes.contains("class=\"resources\""));
+
+    let with_lanes: String = ps.get_string_in_svg_format(true);
+    assert!(with_lanes.contains("class=\"resources\""));
+    assert!(with_lanes.contains(">Resources</text>"));
+    for next_resource in ps.resources() {
+        let name: String = next_resource.name.replace('\'', "&apos;");
+        assert!(with_lanes.contains(&format!(">{}</text>", name)[..]));
+    }
+
+    // Each allocation adds a bar to the lanes.
+    assert!(with_lanes.matches("<rect x=").count() > without_lanes.matches("<rect x=").count());
+}
@@ -0,0 +1,108 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use chrono_period::NaivePeriod;
+
+use chronogrog::ProductionSchedule;
+use chronogrog::resources::{Resource, ResourceTracker, ResourceType, UnavailablePeriod};
+use chronogrog::utilization::{ReportFormat, UtilizationReport};
+
+use chronogrog::util::get_json_data_from_file;
+
+fn get_date(day: u32) -> NaiveDateTime {
+    NaiveDateTime::new(NaiveDate::from_ymd(2020, 1, day), NaiveTime::from_hms(0, 0, 0))
+}
+
+/// Track two fermentors over the first ten days of 2020, one of which is in use for the 2nd and
+/// 3rd, and again for the 6th, and is being repaired on the 9th.
+fn get_tracked_fermentors() -> Vec<Resource> {
+    let mut repaired = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    repaired.unavailable_periods = vec![UnavailablePeriod {
+        start_string: String::from("2020-01-09"),
+        end_string: String::from("2020-01-10"),
+        reason: Some(String::from("repair"))
+    }];
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(repaired);
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+
+    tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor,
+                                                 NaivePeriod::new(get_date(2), get_date(4)));
+    tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor,
+                                                 NaivePeriod::new(get_date(6), get_date(7)));
+
+    tracker.get_all_tracked_resources()
+}
+
+#[test]
+fn it_should_measure_busy_hours_downtime_and_idle_gaps_of_each_resource() {
+    let report = UtilizationReport::new(&get_tracked_fermentors(),
+                                        NaivePeriod::new(get_date(1), get_date(11)));
+
+    assert_eq!(240.0, report.horizon_hours);
+    assert_eq!(vec![1, 2], report.resources.iter().map(|x| x.id).collect::<Vec<usize>>());
+
+    let repaired = &report.resources[0];
+    assert_eq!(72.0, repaired.busy_hours);
+    assert_eq!(24.0, repaired.downtime_hours);
+    assert_eq!(72.0 / 216.0 * 100.0, repaired.utilization);
+
+    // Idle from the 1st to the 2nd, the 4th to the 6th, the 7th to the 9th, and the 10th to the
+    // 11th.
+    assert_eq!(4, repaired.idle_gaps.len());
+    assert_eq!("2020-01-04 00:00:00", repaired.idle_gaps[1].start);
+    assert_eq!(48.0, repaired.longest_idle_hours);
+
+    let unused = &report.resources[1];
+    assert_eq!(0.0, unused.busy_hours);
+    assert_eq!(0.0, unused.utilization);
+    assert_eq!(240.0, unused.longest_idle_hours);
+
+    assert_eq!(1, report.resource_types.len());
+    let fermentors = &report.resource_types[0];
+    assert_eq!(ResourceType::Fermentor, fermentors.resource_type);
+    assert_eq!(2, fermentors.resource_count);
+    assert_eq!(72.0, fermentors.busy_hours);
+    assert_eq!(5, fermentors.idle_gap_count);
+    assert_eq!(240.0, fermentors.longest_idle_hours);
+    assert_eq!(72.0 / 456.0 * 100.0, fermentors.utilization);
+}
+
+#[test]
+fn it_should_write_a_utilization_report_as_a_table_csv_and_json() {
+    let report = UtilizationReport::new(&get_tracked_fermentors(),
+                                        NaivePeriod::new(get_date(1), get_date(11)));
+
+    let csv: String = report.get_string_in_format(ReportFormat::Csv);
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(3, rows.len());
+    assert_eq!("1,FV-001,fermentor,72.00,24.00,4,48.00,33.33", rows[1]);
+
+    let table: String = report.get_string_in_format(ReportFormat::Table);
+    assert!(table.starts_with("Utilization from 2020-01-01 00:00:00 to 2020-01-11 00:00:00"));
+    assert!(table.contains("FV-002"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.get_string_in_format(ReportFormat::Json)[..]).unwrap();
+    assert_eq!(72.0, json["resources"][0]["busyHours"]);
+    assert_eq!("fermentor", json["resourceTypes"][0]["type"]);
+}
+
+#[test]
+fn it_should_report_utilization_over_the_timeline_of_a_production_schedule() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    let report = ps.get_utilization_report();
+    assert_eq!("2020-01-01 00:00:00", report.start);
+    assert_eq!(9, report.resources.len());
+
+    // Big Bertha holds the batch through 33 days of primary, and 64 days of secondary,
+    // fermentation.
+    let big_bertha = report.resources.iter().find(|x| x.name == "Big Bertha").unwrap();
+    assert_eq!((Duration::days(33) + Duration::days(64)).num_hours() as f64, big_bertha.busy_hours);
+    assert_eq!(2, big_bertha.idle_gaps.len());
+
+    let kettle = report.resource_types.iter()
+                       .find(|x| x.resource_type == ResourceType::Kettle)
+                       .unwrap();
+    assert_eq!(24.0, kettle.busy_hours);
+}
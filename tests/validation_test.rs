@@ -0,0 +1,365 @@
+use chronogrog::util::get_json_data_from_file;
+use chronogrog::validation::{validate, Diagnostic, Severity};
+
+fn get_paths(diagnostics: &[Diagnostic]) -> Vec<&str> {
+    diagnostics.iter().map(|d| &d.path[..]).collect()
+}
+
+#[test]
+fn it_should_report_no_diagnostics_for_a_valid_bpd_file() {
+    let json = get_json_data_from_file("tests/fixtures/complicated_prod_schedule.json").unwrap();
+
+    let diagnostics = validate(&json[..]);
+
+    assert_eq!(Vec::<Diagnostic>::new(), diagnostics);
+}
+
+#[test]
+fn it_should_report_every_problem_in_an_invalid_bpd_file() {
+    let json = get_json_data_from_file("tests/fixtures/invalid_prod_schedule.json").unwrap();
+
+    let diagnostics = validate(&json[..]);
+
+    assert_eq!(vec!["$.resources[2]",
+                    "$.resources[1].id",
+                    "$.phaseTemplates[0].resourcesNeeded[1]",
+                    "$.phaseTemplates[1].defaultDuration",
+                    "$.phaseTemplates[1].color",
+                    "$.phaseTemplates[2].id",
+                    "$.recipes[0].phases[1].template",
+                    "$.recipes[0].phases[1].duration",
+                    "$.recipes[1].color",
+                    "$.recipes[1].batchSize",
+                    "$.recipes[1].start"],
+               get_paths(&diagnostics));
+    assert!(diagnostics.iter().all(|d| d.is_error()));
+
+    assert_eq!("error: $.recipes[0].phases[1].template: unknown phase template 'primry'",
+               diagnostics[6].to_string());
+}
+
+#[test]
+fn it_should_report_a_single_diagnostic_for_unparseable_json() {
+    let json = get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap();
+
+    let diagnostics = validate(&json[..]);
+
+    assert_eq!(vec!["$"], get_paths(&diagnostics));
+}
+
+#[test]
+fn it_should_only_warn_about_missing_resource_types_for_unused_templates() {
+    let json = r##"{
+        "name": "Unused Template Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [
+            {
+                "description": "Carbonation",
+                "id": "carbonation",
+                "order": 0,
+                "resourcesNeeded": [ "gastank" ]
+            }
+        ],
+        "recipes": []
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+    assert_eq!("$.phaseTemplates[0].resourcesNeeded[0]", diagnostics[0].path);
+}
+
+#[test]
+fn it_should_report_a_count_larger_than_the_resources_provided() {
+    let json = r##"{
+        "name": "Counted Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "CO2 Tank", "type": "gastank", "capacity": "20lb" }
+        ],
+        "phaseTemplates": [
+            {
+                "description": "Carbonation",
+                "id": "carbonation",
+                "order": 0,
+                "resourcesNeeded": [ "keg", { "type": "gastank", "count": 2 } ]
+            }
+        ],
+        "recipes": [
+            { "name": "Counted Ale", "color": "#FFFFFF", "phases": [ { "template": "carbonation" } ] }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.phaseTemplates[0].resourcesNeeded[0]",
+                    "$.phaseTemplates[0].resourcesNeeded[1]"],
+               get_paths(&diagnostics));
+    assert!(diagnostics.iter().all(|d| d.is_error()));
+    assert_eq!("2 resources of type 'gastank' are needed, but only 1 provided",
+               diagnostics[1].message);
+}
+
+#[test]
+fn it_should_report_a_dependency_on_an_unknown_phase() {
+    let json = r##"{
+        "name": "Dependency Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [
+            { "description": "Planning", "id": "planning", "order": 0 }
+        ],
+        "recipes": [
+            {
+                "name": "Dependent Ale",
+                "color": "#FFFFFF",
+                "phases": [ { "template": "planning", "after": "brewing" } ]
+            }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.recipes[0].phases[0].after"], get_paths(&diagnostics));
+    assert_eq!("depends on unknown phase 'brewing'", diagnostics[0].message);
+}
+
+#[test]
+fn it_should_report_pinned_and_excluded_resources_that_do_not_exist() {
+    let json = r##"{
+        "name": "Pinned Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Big Bertha", "type": "fermentor", "capacity": "14g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0, "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            {
+                "name": "Pinned Ale",
+                "color": "#FFFFFF",
+                "phases": [
+                    { "template": "primary", "pinnedResources": [ "Big Bertha", 1 ], "excludedResources": [ 2 ] }
+                ]
+            }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.recipes[0].phases[0].excludedResources"], get_paths(&diagnostics));
+    assert_eq!("unknown resource '2'", diagnostics[0].message);
+}
+
+#[test]
+fn it_should_report_missing_blocks() {
+    let diagnostics = validate(r#"{ "name": "Empty", "id": 1 }"#);
+
+    assert_eq!(vec!["$.timeline", "$.resources", "$.phaseTemplates", "$.recipes"],
+               get_paths(&diagnostics));
+}
+
+#[test]
+fn it_should_warn_about_vessel_capacities_that_are_not_volumes() {
+    let json = r##"{
+        "name": "Pounds Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5p" },
+            { "id": 2, "name": "CO2 Tank", "type": "gastank", "capacity": "5p" }
+        ],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.resources[0].capacity"], get_paths(&diagnostics));
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+}
+
+#[test]
+fn it_should_report_an_unparseable_ready_by_date() {
+    let json = r##"{
+        "name": "Deadline Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [
+            { "description": "Planning", "id": "planning", "order": 0 }
+        ],
+        "recipes": [
+            {
+                "name": "Festival Ale",
+                "color": "#FFFFFF",
+                "readyBy": "whenever",
+                "phases": [ { "template": "planning" } ]
+            }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.recipes[0].readyBy"], get_paths(&diagnostics));
+    assert_eq!("unable to parse date 'whenever'", diagnostics[0].message);
+}
+
+#[test]
+fn it_should_report_problems_with_blackouts() {
+    let json = r##"{
+        "name": "Blackout Schedule",
+        "id": 1,
+        "timeline": {
+            "configuration": "calendar",
+            "start": "2020-01-01",
+            "blackouts": [ { "start": "2020-12-27", "end": "2020-12-24" } ]
+        },
+        "resources": [],
+        "phaseTemplates": [
+            { "description": "Planning", "id": "planning", "order": 0 }
+        ],
+        "recipes": [
+            {
+                "name": "Festival Ale",
+                "color": "#FFFFFF",
+                "notBefore": "soon",
+                "blackouts": [ { "start": "2020-12-24", "end": "2020-12-27", "wholePhaseTemplates": [ "brewing" ] } ],
+                "phases": [ { "template": "planning" } ]
+            }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.timeline.blackouts[0]",
+                    "$.recipes[0].notBefore",
+                    "$.recipes[0].blackouts[0].wholePhaseTemplates[0]"],
+               get_paths(&diagnostics));
+    assert_eq!("blackout ends before it starts", diagnostics[0].message);
+    assert!(!diagnostics[2].is_error());
+}
+
+#[test]
+fn it_should_report_problems_with_a_working_calendar() {
+    let json = r##"{
+        "name": "Calendar Schedule",
+        "id": 1,
+        "timeline": {
+            "configuration": "calendar",
+            "start": "2020-01-01",
+            "workingCalendar": {
+                "weekdays": [ "saturday", "caturday" ],
+                "hours": { "start": "18:00", "end": "08:00" },
+                "holidays": [ "2020-12-25", "boxing day" ]
+            }
+        },
+        "resources": [],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.timeline.workingCalendar.weekdays[1]",
+                    "$.timeline.workingCalendar.hours",
+                    "$.timeline.workingCalendar.holidays[1]"],
+               get_paths(&diagnostics));
+    assert_eq!("unknown weekday 'caturday'", diagnostics[0].message);
+    assert_eq!("working hours end before they start", diagnostics[1].message);
+}
+
+#[test]
+fn it_should_report_an_unavailable_period_that_ends_before_it_starts() {
+    let json = r##"{
+        "name": "Maintenance Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            {
+                "id": 1,
+                "name": "FV-001",
+                "type": "fermentor",
+                "capacity": "5g",
+                "unavailable": [ { "start": "2020-01-03", "end": "2020-01-01", "reason": "cleaning" } ]
+            }
+        ],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.resources[0].unavailable[0]"], get_paths(&diagnostics));
+    assert_eq!("unavailable period ends before it starts", diagnostics[0].message);
+}
+
+#[test]
+fn it_should_report_unparseable_turnarounds() {
+    let json = r##"{
+        "name": "Turnaround Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g", "turnaround": "soon" }
+        ],
+        "turnarounds": [ { "type": "kettle", "duration": "2h" }, { "type": "fermentor", "duration": "1.5d" } ],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.turnarounds[1].duration", "$.resources[0].turnaround"],
+               get_paths(&diagnostics));
+}
+
+#[test]
+fn it_should_report_negative_durations() {
+    let json = r##"{
+        "name": "Negative Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g", "turnaround": "-1d" }
+        ],
+        "turnarounds": [ { "type": "fermentor", "duration": "-2h" } ],
+        "phaseTemplates": [
+            {
+                "description": "Primary Fermentation",
+                "id": "primary",
+                "order": 0,
+                "defaultDuration": "-10d",
+                "resourcesNeeded": [ "fermentor" ]
+            }
+        ],
+        "recipes": [
+            {
+                "name": "Negative Ale",
+                "color": "#FFFFFF",
+                "phases": [
+                    { "template": "primary", "duration": "-5d" },
+                    { "template": "primary", "id": "split", "after": { "phase": "primary", "from": "start", "lag": "-1d" } }
+                ]
+            }
+        ]
+    }"##;
+
+    let diagnostics = validate(json);
+
+    assert_eq!(vec!["$.turnarounds[0].duration",
+                    "$.resources[0].turnaround",
+                    "$.phaseTemplates[0].defaultDuration",
+                    "$.recipes[0].phases[0].duration",
+                    "$.recipes[0].phases[1].after"],
+               get_paths(&diagnostics));
+    assert!(diagnostics.iter().all(|d| d.is_error()));
+    assert_eq!("unable to parse lag '-1d'", diagnostics[4].message);
+}
@@ -0,0 +1,37 @@
+use chronogrog::volume::{Volume, VolumeUnit};
+
+#[test]
+fn it_should_parse_volumes_in_every_unit() {
+    assert_eq!(Some(Volume::new(5.0, VolumeUnit::Gallons)), Volume::parse("5g"));
+    assert_eq!(Some(Volume::new(14.0, VolumeUnit::Gallons)), Volume::parse("14gal"));
+    assert_eq!(Some(Volume::new(19.5, VolumeUnit::Liters)), Volume::parse("19.5L"));
+    assert_eq!(Some(Volume::new(3.0, VolumeUnit::Barrels)), Volume::parse("3 bbl"));
+    assert_eq!(Some(Volume::new(1.2, VolumeUnit::Hectoliters)), Volume::parse("1.2hl"));
+}
+
+#[test]
+fn it_should_not_parse_strings_that_are_not_volumes() {
+    assert_eq!(None, Volume::parse(""));
+    assert_eq!(None, Volume::parse("5"));
+    assert_eq!(None, Volume::parse("5p"));
+    assert_eq!(None, Volume::parse("2k"));
+    assert_eq!(None, Volume::parse("g"));
+    assert_eq!(None, Volume::parse("1.2.3g"));
+}
+
+#[test]
+fn it_should_convert_between_units() {
+    let barrel = Volume::new(1.0, VolumeUnit::Barrels);
+
+    assert_eq!(Volume::new(31.0, VolumeUnit::Gallons), barrel);
+    assert!((barrel.convert_to(VolumeUnit::Gallons).amount - 31.0).abs() < 1e-9);
+    assert!((barrel.convert_to(VolumeUnit::Hectoliters).amount - 1.173_477_653).abs() < 1e-6);
+    assert_eq!(Volume::new(100.0, VolumeUnit::Liters), Volume::new(1.0, VolumeUnit::Hectoliters));
+}
+
+#[test]
+fn it_should_compare_volumes_across_units() {
+    assert!(Volume::new(5.0, VolumeUnit::Gallons) < Volume::new(20.0, VolumeUnit::Liters));
+    assert!(Volume::new(1.0, VolumeUnit::Barrels) > Volume::new(1.0, VolumeUnit::Hectoliters));
+    assert_eq!("1.5bbl", Volume::new(1.5, VolumeUnit::Barrels).to_string());
+}